@@ -1,4 +1,5 @@
-use image::RgbaImage;
+use crate::framebrowser::{Frame, FrameBlend, FrameDispose};
+use image::{Rgba, RgbaImage};
 use std::fs::File;
 use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
@@ -90,9 +91,9 @@ pub fn check_apng<P: AsRef<Path>>(path: P) -> Result<ApngInfo, std::io::Error> {
     })
 }
 
-/// APNG解码器 - 使用png crate解码所有帧
+/// APNG解码器 - 使用png crate解码所有帧，并按 fcTL 的偏移、dispose_op 和 blend_op 合成画布
 pub struct ApngDecoder {
-    frames: Vec<RgbaImage>,
+    frames: Vec<Frame>,
 }
 
 impl ApngDecoder {
@@ -101,105 +102,446 @@ impl ApngDecoder {
         let file = File::open(&path)?;
         let decoder = png::Decoder::new(file);
         let mut reader = decoder.read_info()?;
-        
+
         let info = reader.info();
         let width = info.width;
         let height = info.height;
-        let color_type = info.color_type;
-        
+        // 默认图像（IDAT）前存在 fcTL 时才属于动画的第一帧
+        let default_in_animation = info.frame_control.is_some();
+
         let mut frames = Vec::new();
-        
-        // 读取第一帧
+        let mut canvas = RgbaImage::new(width, height);
         let mut buf = vec![0; reader.output_buffer_size()];
-        let output_info = reader.next_frame(&mut buf)?;
-        
-        // 转换为RGBA格式
-        let frame = match color_type {
-            png::ColorType::Rgba => {
-                RgbaImage::from_raw(width, height, buf[..output_info.buffer_size()].to_vec())
-                    .ok_or("无法创建图像")?
-            }
-            png::ColorType::Rgb => {
-                // RGB转RGBA
-                let rgb_data = &buf[..output_info.buffer_size()];
-                let mut rgba_data = Vec::with_capacity((width * height * 4) as usize);
-                for chunk in rgb_data.chunks(3) {
-                    rgba_data.extend_from_slice(chunk);
-                    rgba_data.push(255); // Alpha通道
-                }
-                RgbaImage::from_raw(width, height, rgba_data)
-                    .ok_or("无法创建图像")?
-            }
-            png::ColorType::Grayscale => {
-                // 灰度转RGBA
-                let gray_data = &buf[..output_info.buffer_size()];
-                let mut rgba_data = Vec::with_capacity((width * height * 4) as usize);
-                for &gray in gray_data {
-                    rgba_data.extend_from_slice(&[gray, gray, gray, 255]);
-                }
-                RgbaImage::from_raw(width, height, rgba_data)
-                    .ok_or("无法创建图像")?
-            }
-            png::ColorType::GrayscaleAlpha => {
-                // 灰度+Alpha转RGBA
-                let ga_data = &buf[..output_info.buffer_size()];
-                let mut rgba_data = Vec::with_capacity((width * height * 4) as usize);
-                for chunk in ga_data.chunks(2) {
-                    let gray = chunk[0];
-                    let alpha = chunk[1];
-                    rgba_data.extend_from_slice(&[gray, gray, gray, alpha]);
-                }
-                RgbaImage::from_raw(width, height, rgba_data)
-                    .ok_or("无法创建图像")?
+        let mut first = true;
+        let mut animation_frames = 0usize;
+
+        loop {
+            let output_info = match reader.next_frame(&mut buf) {
+                Ok(output_info) => output_info,
+                Err(e) if first => return Err(e.into()),
+                Err(_) => break,
+            };
+            let (raw, indices, low_bytes) = to_rgba(&buf[..output_info.buffer_size()], &output_info, reader.info())?;
+            let palette = palette_rgba(reader.info());
+            let index_map = indices.map(|indices| index_map(&indices, raw.width(), raw.height()));
+
+            if first && !default_in_animation {
+                // 不属于动画的默认图像：单独作为一帧保留，不参与合成
+                frames.push(Frame {
+                    composed: raw.clone(),
+                    raw,
+                    index_map,
+                    palette,
+                    low_bytes,
+                    note: Some("默认图像（不属于动画）".to_string()),
+                    ..Frame::default()
+                });
+                first = false;
+                continue;
             }
-            _ => {
-                return Err("不支持的颜色类型".into());
+
+            let fc = reader.info().frame_control.unwrap_or_default();
+            let mut dispose = match fc.dispose_op {
+                png::DisposeOp::None => FrameDispose::None,
+                png::DisposeOp::Background => FrameDispose::Background,
+                png::DisposeOp::Previous => FrameDispose::Previous,
+            };
+            // 规范：第一帧的 APNG_DISPOSE_OP_PREVIOUS 视为 APNG_DISPOSE_OP_BACKGROUND
+            if animation_frames == 0 && dispose == FrameDispose::Previous {
+                dispose = FrameDispose::Background;
             }
-        };
-        
-        frames.push(frame);
-        
-        // 尝试读取后续帧（如果是APNG）
-        loop {
-            buf.clear();
-            buf.resize(reader.output_buffer_size(), 0);
-            
-            match reader.next_frame(&mut buf) {
-                Ok(output_info) => {
-                    let frame = match color_type {
-                        png::ColorType::Rgba => {
-                            RgbaImage::from_raw(width, height, buf[..output_info.buffer_size()].to_vec())
-                                .ok_or("无法创建图像")?
-                        }
-                        png::ColorType::Rgb => {
-                            let rgb_data = &buf[..output_info.buffer_size()];
-                            let mut rgba_data = Vec::with_capacity((width * height * 4) as usize);
-                            for chunk in rgb_data.chunks(3) {
-                                rgba_data.extend_from_slice(chunk);
-                                rgba_data.push(255);
-                            }
-                            RgbaImage::from_raw(width, height, rgba_data)
-                                .ok_or("无法创建图像")?
-                        }
-                        _ => continue,
-                    };
-                    frames.push(frame);
+            let blend = match fc.blend_op {
+                png::BlendOp::Source => FrameBlend::Source,
+                png::BlendOp::Over => FrameBlend::Over,
+            };
+
+            let previous = if dispose == FrameDispose::Previous {
+                Some(canvas.clone())
+            } else {
+                None
+            };
+            composite(&mut canvas, &raw, fc.x_offset, fc.y_offset, blend);
+            let composed = canvas.clone();
+
+            // 处置当前帧区域，为下一帧准备画布
+            match dispose {
+                FrameDispose::None => {}
+                FrameDispose::Background => clear_region(&mut canvas, fc.x_offset, fc.y_offset, raw.width(), raw.height()),
+                FrameDispose::Previous => {
+                    if let Some(previous) = previous {
+                        canvas = previous;
+                    }
                 }
-                Err(_) => break,
             }
+
+            frames.push(Frame {
+                composed,
+                raw,
+                x: fc.x_offset,
+                y: fc.y_offset,
+                delay_num: fc.delay_num as u32,
+                // 规范：分母为 0 时按 100 处理
+                delay_den: if fc.delay_den == 0 { 100 } else { fc.delay_den as u32 },
+                dispose,
+                blend,
+                index_map,
+                palette,
+                low_bytes,
+                note: None,
+            });
+            animation_frames += 1;
+            first = false;
         }
-        
+
         Ok(Self { frames })
     }
 
     /// 获取所有帧
-    pub fn into_frames(self) -> Vec<RgbaImage> {
+    pub fn into_frames(self) -> Vec<Frame> {
         self.frames
     }
-    
+
     /// 获取帧数
     pub fn frame_count(&self) -> usize {
         self.frames.len()
     }
 }
 
+/// 解码出的帧：RGBA 图像、索引色图像的调色板索引、16 位图像每个样本的低字节
+type DecodedFrame = (RgbaImage, Option<Vec<u8>>, Option<RgbaImage>);
+
+/// 将解码出的原始数据（任意颜色类型和位深度）转换为 RGBA 图像
+/// 索引色图像同时返回每个像素的原始调色板索引，16 位图像同时返回被缩放丢弃的低字节
+fn to_rgba(data: &[u8], output: &png::OutputInfo, info: &png::Info) -> Result<DecodedFrame, Box<dyn std::error::Error>> {
+    let (width, height) = (output.width, output.height);
+    let depth = output.bit_depth as u8;
+    let channels = output.color_type.samples();
+    let trns = info.trns.as_deref();
+    let palette = info.palette.as_deref();
+    if output.color_type == png::ColorType::Indexed && palette.is_none() {
+        return Err("索引色图像缺少 PLTE 块".into());
+    }
+
+    let mut img = RgbaImage::new(width, height);
+    let mut indices = if output.color_type == png::ColorType::Indexed {
+        Some(Vec::with_capacity((width * height) as usize))
+    } else {
+        None
+    };
+    let mut low_bytes = if depth == 16 { Some(RgbaImage::new(width, height)) } else { None };
+
+    for (y, row) in data.chunks(output.line_size).take(height as usize).enumerate() {
+        for x in 0..width as usize {
+            let s = |c: usize| sample(row, x * channels + c, depth);
+            let pixel = match output.color_type {
+                png::ColorType::Grayscale => {
+                    let v = s(0);
+                    let g = scale_to_u8(v, depth);
+                    // tRNS 中给出的灰度值视为全透明
+                    let transparent = trns.and_then(|t| trns_sample(t, 0, depth)) == Some(v);
+                    [g, g, g, if transparent { 0 } else { 255 }]
+                }
+                png::ColorType::GrayscaleAlpha => {
+                    let g = scale_to_u8(s(0), depth);
+                    [g, g, g, scale_to_u8(s(1), depth)]
+                }
+                png::ColorType::Rgb => {
+                    let (r, g, b) = (s(0), s(1), s(2));
+                    let transparent = trns
+                        .map(|t| (0..3).map(|c| trns_sample(t, c, depth)).eq([Some(r), Some(g), Some(b)]))
+                        .unwrap_or(false);
+                    [
+                        scale_to_u8(r, depth),
+                        scale_to_u8(g, depth),
+                        scale_to_u8(b, depth),
+                        if transparent { 0 } else { 255 },
+                    ]
+                }
+                png::ColorType::Rgba => [
+                    scale_to_u8(s(0), depth),
+                    scale_to_u8(s(1), depth),
+                    scale_to_u8(s(2), depth),
+                    scale_to_u8(s(3), depth),
+                ],
+                png::ColorType::Indexed => {
+                    let index = s(0) as usize;
+                    if let Some(indices) = indices.as_mut() {
+                        indices.push(index as u8);
+                    }
+                    palette_entry(palette.unwrap_or(&[]), trns, index)
+                }
+            };
+            img.put_pixel(x as u32, y as u32, Rgba(pixel));
+            if let Some(low_bytes) = low_bytes.as_mut() {
+                let low = |c: usize| s(c) as u8;
+                let low_pixel = match output.color_type {
+                    png::ColorType::Grayscale => [low(0), low(0), low(0), 255],
+                    png::ColorType::GrayscaleAlpha => [low(0), low(0), low(0), low(1)],
+                    png::ColorType::Rgb => [low(0), low(1), low(2), 255],
+                    _ => [low(0), low(1), low(2), low(3)],
+                };
+                low_bytes.put_pixel(x as u32, y as u32, Rgba(low_pixel));
+            }
+        }
+    }
+    Ok((img, indices, low_bytes))
+}
+
+// 读取一行中第 index 个样本（支持 1/2/4/8/16 位）
+fn sample(row: &[u8], index: usize, depth: u8) -> u16 {
+    match depth {
+        8 => row.get(index).copied().unwrap_or(0) as u16,
+        16 => u16::from_be_bytes([
+            row.get(index * 2).copied().unwrap_or(0),
+            row.get(index * 2 + 1).copied().unwrap_or(0),
+        ]),
+        _ => {
+            // 子字节样本按高位在前的顺序打包
+            let bit = index * depth as usize;
+            let byte = row.get(bit / 8).copied().unwrap_or(0);
+            let shift = 8 - depth as usize - bit % 8;
+            ((byte >> shift) & ((1u8 << depth) - 1)) as u16
+        }
+    }
+}
+
+// 读取 tRNS 中第 index 个颜色样本：png 解码器对 16 位以下的图像只保留每个样本的低字节
+fn trns_sample(trns: &[u8], index: usize, depth: u8) -> Option<u16> {
+    if depth == 16 {
+        trns.get(index * 2..index * 2 + 2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    } else {
+        trns.get(index).map(|&b| b as u16)
+    }
+}
+
+// 将任意位深度的样本缩放到 8 位
+fn scale_to_u8(value: u16, depth: u8) -> u8 {
+    match depth {
+        16 => (value >> 8) as u8,
+        8 => value as u8,
+        _ => (value as u32 * 255 / ((1u32 << depth) - 1)) as u8,
+    }
+}
+
+// 取调色板颜色，tRNS 提供对应索引的 alpha
+fn palette_entry(palette: &[u8], trns: Option<&[u8]>, index: usize) -> [u8; 4] {
+    let rgb = palette.get(index * 3..index * 3 + 3).unwrap_or(&[0, 0, 0]);
+    let alpha = trns.and_then(|t| t.get(index).copied()).unwrap_or(255);
+    [rgb[0], rgb[1], rgb[2], alpha]
+}
+
+/// 调色板（含 tRNS 的 alpha），非索引色图像返回空列表
+fn palette_rgba(info: &png::Info) -> Vec<[u8; 4]> {
+    if info.color_type != png::ColorType::Indexed {
+        return Vec::new();
+    }
+    let palette = info.palette.as_deref().unwrap_or(&[]);
+    (0..palette.len() / 3)
+        .map(|i| palette_entry(palette, info.trns.as_deref(), i))
+        .collect()
+}
+
+/// 将调色板索引渲染为灰度图（像素值即索引值）
+pub fn index_map(indices: &[u8], width: u32, height: u32) -> RgbaImage {
+    let mut img = RgbaImage::new(width, height);
+    for (pixel, &index) in img.pixels_mut().zip(indices) {
+        *pixel = Rgba([index, index, index, 255]);
+    }
+    img
+}
+
+/// 将子帧绘制到画布的 (x, y) 位置：Source 直接覆盖，Over 按 alpha 混合
+pub fn composite(canvas: &mut RgbaImage, frame: &RgbaImage, x: u32, y: u32, blend: FrameBlend) {
+    for (fx, fy, src) in frame.enumerate_pixels() {
+        let (cx, cy) = (x + fx, y + fy);
+        if cx >= canvas.width() || cy >= canvas.height() {
+            continue;
+        }
+        match blend {
+            FrameBlend::Source => canvas.put_pixel(cx, cy, *src),
+            FrameBlend::Over => {
+                let dst = *canvas.get_pixel(cx, cy);
+                canvas.put_pixel(cx, cy, blend_over(*src, dst));
+            }
+        }
+    }
+}
+
+/// 将画布中的矩形区域清为全透明黑色
+pub fn clear_region(canvas: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32) {
+    for cy in y..(y + height).min(canvas.height()) {
+        for cx in x..(x + width).min(canvas.width()) {
+            canvas.put_pixel(cx, cy, Rgba([0, 0, 0, 0]));
+        }
+    }
+}
+
+// 非预乘 alpha 的 "over" 混合
+fn blend_over(src: Rgba<u8>, dst: Rgba<u8>) -> Rgba<u8> {
+    let sa = src[3] as u32;
+    if sa == 255 {
+        return src;
+    }
+    if sa == 0 {
+        return dst;
+    }
+    let da = dst[3] as u32;
+    // 输出 alpha = sa + da * (1 - sa)，以 255*255 为单位
+    let out_a = sa * 255 + da * (255 - sa);
+    if out_a == 0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let mut out = [0u8; 4];
+    for i in 0..3 {
+        let c = src[i] as u32 * sa * 255 + dst[i] as u32 * da * (255 - sa);
+        out[i] = (c / out_a) as u8;
+    }
+    out[3] = (out_a / 255) as u8;
+    Rgba(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(width: u32, height: u32, color: [u8; 4]) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba(color))
+    }
+
+    // 在内存中编码一张 PNG，再按解码器的方式读出第一帧并转换
+    fn encode_and_convert(
+        width: u32,
+        color: png::ColorType,
+        depth: png::BitDepth,
+        palette: Option<Vec<u8>>,
+        trns: Option<Vec<u8>>,
+        data: &[u8],
+    ) -> DecodedFrame {
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, width, 1);
+            encoder.set_color(color);
+            encoder.set_depth(depth);
+            if let Some(palette) = palette {
+                encoder.set_palette(palette);
+            }
+            if let Some(trns) = trns {
+                encoder.set_trns(trns);
+            }
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(data).unwrap();
+        }
+        let mut reader = png::Decoder::new(std::io::Cursor::new(bytes)).read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let output = reader.next_frame(&mut buf).unwrap();
+        to_rgba(&buf[..output.buffer_size()], &output, reader.info()).unwrap()
+    }
+
+    fn pixels(img: &RgbaImage) -> Vec<[u8; 4]> {
+        img.pixels().map(|p| p.0).collect()
+    }
+
+    #[test]
+    fn composite_source_replaces_and_over_blends() {
+        let half_red = filled(1, 1, [255, 0, 0, 128]);
+
+        let mut canvas = filled(1, 1, [0, 0, 255, 255]);
+        composite(&mut canvas, &half_red, 0, 0, FrameBlend::Source);
+        assert_eq!(canvas.get_pixel(0, 0).0, [255, 0, 0, 128]);
+
+        let mut canvas = filled(1, 1, [0, 0, 255, 255]);
+        composite(&mut canvas, &half_red, 0, 0, FrameBlend::Over);
+        assert_eq!(canvas.get_pixel(0, 0).0, [128, 0, 127, 255]);
+
+        // 全透明像素在 Over 下保留画布原值，在透明画布上 Over 等于原像素
+        let mut canvas = filled(1, 1, [0, 0, 255, 255]);
+        composite(&mut canvas, &filled(1, 1, [9, 9, 9, 0]), 0, 0, FrameBlend::Over);
+        assert_eq!(canvas.get_pixel(0, 0).0, [0, 0, 255, 255]);
+        let mut canvas = filled(1, 1, [0, 0, 0, 0]);
+        composite(&mut canvas, &half_red, 0, 0, FrameBlend::Over);
+        assert_eq!(canvas.get_pixel(0, 0).0, [255, 0, 0, 128]);
+    }
+
+    #[test]
+    fn composite_uses_offset_and_clips_to_canvas() {
+        let mut canvas = filled(2, 2, [0, 0, 0, 255]);
+        composite(&mut canvas, &filled(2, 2, [1, 2, 3, 255]), 1, 1, FrameBlend::Source);
+        assert_eq!(canvas.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        assert_eq!(canvas.get_pixel(1, 0).0, [0, 0, 0, 255]);
+        assert_eq!(canvas.get_pixel(0, 1).0, [0, 0, 0, 255]);
+        assert_eq!(canvas.get_pixel(1, 1).0, [1, 2, 3, 255]);
+    }
+
+    #[test]
+    fn clear_region_is_clipped() {
+        let mut canvas = filled(3, 2, [7, 7, 7, 255]);
+        clear_region(&mut canvas, 1, 1, 5, 5);
+        let alpha: Vec<u8> = canvas.pixels().map(|p| p[3]).collect();
+        assert_eq!(alpha, vec![255, 255, 255, 255, 0, 0]);
+        assert_eq!(canvas.get_pixel(2, 1).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn to_rgba_palette_with_trns() {
+        let palette = vec![10, 20, 30, 40, 50, 60, 70, 80, 90];
+        // 2 位索引 0,1,2；tRNS 只覆盖前两个索引
+        let (img, indices, low_bytes) = encode_and_convert(
+            3,
+            png::ColorType::Indexed,
+            png::BitDepth::Two,
+            Some(palette),
+            Some(vec![0, 128]),
+            &[0b0001_1000],
+        );
+        assert_eq!(pixels(&img), vec![[10, 20, 30, 0], [40, 50, 60, 128], [70, 80, 90, 255]]);
+        assert_eq!(indices, Some(vec![0, 1, 2]));
+        assert!(low_bytes.is_none());
+    }
+
+    #[test]
+    fn to_rgba_scales_low_bit_depth_grey() {
+        let grey = |img: &RgbaImage| img.pixels().map(|p| p[0]).collect::<Vec<u8>>();
+
+        let (img, indices, _) =
+            encode_and_convert(3, png::ColorType::Grayscale, png::BitDepth::One, None, None, &[0xa0]);
+        assert_eq!(grey(&img), vec![255, 0, 255]);
+        assert!(indices.is_none());
+
+        let (img, _, _) = encode_and_convert(4, png::ColorType::Grayscale, png::BitDepth::Two, None, None, &[0x1b]);
+        assert_eq!(grey(&img), vec![0, 85, 170, 255]);
+
+        // tRNS 给出的灰度值 5 视为透明
+        let (img, _, _) = encode_and_convert(
+            3,
+            png::ColorType::Grayscale,
+            png::BitDepth::Four,
+            None,
+            Some(vec![0, 5]),
+            &[0x05, 0xf0],
+        );
+        assert_eq!(pixels(&img), vec![[0, 0, 0, 255], [85, 85, 85, 0], [255, 255, 255, 255]]);
+    }
+
+    #[test]
+    fn to_rgba_keeps_low_bytes_of_16_bit_rgba() {
+        let data = [0x12, 0x34, 0xab, 0xcd, 0x00, 0xff, 0xff, 0x00];
+        let (img, indices, low_bytes) =
+            encode_and_convert(1, png::ColorType::Rgba, png::BitDepth::Sixteen, None, None, &data);
+        assert_eq!(pixels(&img), vec![[0x12, 0xab, 0x00, 0xff]]);
+        assert!(indices.is_none());
+        assert_eq!(pixels(&low_bytes.unwrap()), vec![[0x34, 0xcd, 0xff, 0x00]]);
+    }
+
+    #[test]
+    fn to_rgba_rgb_trns_at_8_and_16_bits() {
+        let trns = vec![0, 1, 0, 2, 0, 3];
+        let (img, _, _) =
+            encode_and_convert(2, png::ColorType::Rgb, png::BitDepth::Eight, None, Some(trns), &[1, 2, 3, 1, 2, 4]);
+        assert_eq!(pixels(&img), vec![[1, 2, 3, 0], [1, 2, 4, 255]]);
+
+        let trns = vec![1, 0, 2, 0, 3, 0];
+        let data = [1, 0, 2, 0, 3, 0, 1, 0, 2, 0, 3, 1];
+        let (img, _, _) = encode_and_convert(2, png::ColorType::Rgb, png::BitDepth::Sixteen, None, Some(trns), &data);
+        assert_eq!(pixels(&img), vec![[1, 2, 3, 0], [1, 2, 3, 255]]);
+    }
+}
@@ -0,0 +1,122 @@
+use image::{Rgba, RgbaImage};
+
+// ──────────────────────────────
+// 将提取出的比特流重新渲染为黑白图像或 0/1 文本，便于发现隐藏的二维码、位图等
+
+/// 按 MSB 优先读取第 index 个比特
+fn bit_at(data: &[u8], index: usize) -> bool {
+    data[index / 8] & (0x80 >> (index % 8)) != 0
+}
+
+/// 将比特流按指定宽度渲染为 1 位图像，比特 1 为黑色（invert 时为白色）
+pub fn bits_to_image(data: &[u8], width: u32, invert: bool) -> RgbaImage {
+    bits_to_image_rows(data, width, invert, 0, u32::MAX)
+}
+
+/// 按宽度计算的总行数
+pub fn bit_rows(bit_count: usize, width: u32) -> u32 {
+    (bit_count as u64).div_ceil(width.max(1) as u64).min(u32::MAX as u64) as u32
+}
+
+/// 只渲染从 first_row 开始的至多 max_rows 行，用于显示超出纹理尺寸限制的长比特流
+pub fn bits_to_image_rows(data: &[u8], width: u32, invert: bool, first_row: u32, max_rows: u32) -> RgbaImage {
+    let bit_count = data.len() * 8;
+    let width = width.max(1);
+    let first_row = first_row.min(bit_rows(bit_count, width).saturating_sub(1));
+    let height = (bit_rows(bit_count, width) - first_row).min(max_rows).max(1);
+    let mut img = RgbaImage::from_pixel(width, height, Rgba([128, 128, 128, 255]));
+
+    let start = first_row as usize * width as usize;
+    let end = bit_count.min(start + width as usize * height as usize);
+    for index in start..end {
+        let x = ((index - start) % width as usize) as u32;
+        let y = ((index - start) / width as usize) as u32;
+        let value = if bit_at(data, index) != invert { 0 } else { 255 };
+        img.put_pixel(x, y, Rgba([value, value, value, 255]));
+    }
+    img
+}
+
+/// 将比特流转换为 0/1 字符串；group 为每组比特数，groups_per_line 为每行组数（0 表示不分组 / 不换行）
+pub fn bits_to_string(data: &[u8], group: usize, groups_per_line: usize) -> String {
+    let bit_count = data.len() * 8;
+    let mut out = String::with_capacity(bit_count + bit_count / group.max(1));
+    for index in 0..bit_count {
+        if index > 0 && group > 0 && index % group == 0 {
+            if groups_per_line > 0 && (index / group) % groups_per_line == 0 {
+                out.push('\n');
+            } else {
+                out.push(' ');
+            }
+        }
+        out.push(if bit_at(data, index) { '1' } else { '0' });
+    }
+    out
+}
+
+/// 根据比特总数推荐图像宽度：返回能整除比特数的因子，优先靠近正方形的宽度
+pub fn suggest_widths(bit_count: usize, max_count: usize) -> Vec<u32> {
+    if bit_count == 0 {
+        return Vec::new();
+    }
+    let mut factors: Vec<u32> = (2..=bit_count.min(4096) as u32)
+        .filter(|w| bit_count % *w as usize == 0 && bit_count / *w as usize >= 2)
+        .collect();
+    let square = (bit_count as f64).sqrt();
+    factors.sort_by(|a, b| {
+        let da = (*a as f64 / square).ln().abs();
+        let db = (*b as f64 / square).ln().abs();
+        da.partial_cmp(&db).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    factors.truncate(max_count);
+    factors.sort_unstable();
+    factors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bit_string_groups_and_lines() {
+        assert_eq!(bits_to_string(&[0xa5], 0, 0), "10100101");
+        assert_eq!(bits_to_string(&[0xa5, 0x0f], 4, 0), "1010 0101 0000 1111");
+        assert_eq!(bits_to_string(&[0xa5, 0x0f], 4, 2), "1010 0101\n0000 1111");
+        assert_eq!(bits_to_string(&[0xff], 3, 2), "111 111\n11");
+        assert_eq!(bits_to_string(&[], 8, 4), "");
+    }
+
+    #[test]
+    fn image_pads_last_row() {
+        // 16 个比特按宽度 5 排列，最后一行多出的像素为灰色
+        let img = bits_to_image(&[0b1000_0001, 0b1100_0000], 5, false);
+        assert_eq!(img.dimensions(), (5, 4));
+        assert_eq!(img.get_pixel(0, 0)[0], 0);
+        assert_eq!(img.get_pixel(1, 0)[0], 255);
+        assert_eq!(img.get_pixel(2, 1)[0], 0);
+        assert_eq!(img.get_pixel(4, 1)[0], 0);
+        assert_eq!(img.get_pixel(0, 3)[0], 255);
+        assert_eq!(img.get_pixel(1, 3)[0], 128);
+        assert_eq!(bits_to_image(&[0x80], 8, true).get_pixel(0, 0)[0], 255);
+    }
+
+    #[test]
+    fn image_rows_window() {
+        let data = [0xff, 0x00, 0xff, 0x00];
+        assert_eq!(bit_rows(32, 8), 4);
+        let img = bits_to_image_rows(&data, 8, false, 1, 2);
+        assert_eq!(img.dimensions(), (8, 2));
+        assert_eq!(img.get_pixel(0, 0)[0], 255);
+        assert_eq!(img.get_pixel(0, 1)[0], 0);
+        // 起始行超出范围时显示最后一行
+        assert_eq!(bits_to_image_rows(&data, 8, false, 100, 2).dimensions(), (8, 1));
+    }
+
+    #[test]
+    fn suggested_widths_divide_bit_count() {
+        assert_eq!(suggest_widths(64, 1), [8]);
+        let widths = suggest_widths(21 * 8, 3);
+        assert!(widths.iter().all(|w| 168 % w == 0));
+        assert!(widths.contains(&12) && widths.contains(&14));
+    }
+}
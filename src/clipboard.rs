@@ -0,0 +1,81 @@
+use image::RgbaImage;
+use std::borrow::Cow;
+use std::path::Path;
+
+// ──────────────────────────────
+// 系统剪贴板的图像复制与粘贴
+
+/// 将图像复制到系统剪贴板
+pub fn copy_image(img: &RgbaImage) -> Result<(), String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+    clipboard
+        .set_image(arboard::ImageData {
+            width: img.width() as usize,
+            height: img.height() as usize,
+            bytes: Cow::Borrowed(img.as_raw()),
+        })
+        .map_err(|e| e.to_string())
+}
+
+/// 从剪贴板读取图像（截图、浏览器中复制的图片）
+/// 剪贴板中没有图像时，若 text（粘贴事件中的文本或剪贴板文本）是图片文件的路径则打开该文件
+pub fn paste_image(text: Option<&str>) -> Result<RgbaImage, String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+    if let Ok(data) = clipboard.get_image() {
+        return RgbaImage::from_raw(data.width as u32, data.height as u32, data.bytes.into_owned())
+            .ok_or_else(|| "剪贴板图像数据不完整".to_string());
+    }
+
+    let text = match text {
+        Some(text) => text.to_string(),
+        None => clipboard.get_text().map_err(|_| "剪贴板中没有图像".to_string())?,
+    };
+    // 文件管理器复制的文件可能是 file:// URI，多个文件时取第一个
+    let line = text.lines().next().unwrap_or("").trim();
+    let path = line.strip_prefix("file://").unwrap_or(line);
+    if path.is_empty() || !Path::new(path).is_file() {
+        return Err("剪贴板中没有图像".to_string());
+    }
+    image::open(path)
+        .map(|img| img.to_rgba8())
+        .map_err(|e| format!("打开 {} 失败: {}", path, e))
+}
+
+/// Ctrl+V 快捷键检测
+///
+/// egui-winit 只在剪贴板中有文本时产生 `Event::Paste`，并吞掉按下 V 的按键事件，
+/// 剪贴板中只有图像（截图、浏览器复制的图片）时什么也不会收到；
+/// 但松开 V 的按键事件仍会发出，因此在没有收到粘贴事件时以 Ctrl+V 的松开作为粘贴
+#[derive(Default)]
+pub struct PasteShortcut {
+    /// 本次按键已经通过粘贴事件处理，松开 V 时不再重复粘贴
+    handled: bool,
+}
+
+impl PasteShortcut {
+    /// 检测到粘贴时返回 Some：内层为粘贴事件中的文本，只有图像时为 None
+    pub fn poll(&mut self, ctx: &eframe::egui::Context) -> Option<Option<String>> {
+        use eframe::egui::{Event, Key};
+        let (text, released) = ctx.input(|i| {
+            let text = i.events.iter().find_map(|event| match event {
+                Event::Paste(text) => Some(text.clone()),
+                _ => None,
+            });
+            let released = i.events.iter().any(|event| {
+                matches!(event, Event::Key { key: Key::V, pressed: false, modifiers, .. } if modifiers.command)
+            });
+            (text, released)
+        });
+        if let Some(text) = text {
+            self.handled = !released;
+            return Some(Some(text));
+        }
+        if released {
+            if std::mem::take(&mut self.handled) {
+                return None;
+            }
+            return Some(None);
+        }
+        None
+    }
+}
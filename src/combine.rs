@@ -1,46 +1,390 @@
 use eframe::egui::{self, TextureOptions, TextureHandle};
 use egui::{Context, Slider};
 use image::{RgbaImage, ImageBuffer};
+use crate::clipboard;
+use crate::diffreport::DiffPanel;
+use crate::pixelexpr::{PixelProgram, PixelVars};
 use std::cell::RefCell;
 use std::rc::Rc;
 use rfd;
 
 /// 常量，用于选择合成模式
-const NUM_TRANSFORMS: i32 = 13;
+const NUM_TRANSFORMS: i32 = 24;
+/// 使用逐像素表达式的合成模式
+const EXPRESSION_MODE: i32 = 22;
+/// 视觉密码叠加模式
+const VISUAL_CRYPTO_MODE: i32 = 23;
+
+/// 视觉密码的子像素扩展方式，合成后按块多数还原为一个像素
+#[derive(Clone, Copy, PartialEq)]
+pub enum SubpixelExpansion {
+    /// 不还原，直接显示叠加结果
+    None,
+    /// 每个像素扩展为 2×2 子像素
+    Square,
+    /// 每个像素扩展为横向的 1×2 子像素
+    Horizontal,
+    /// 每个像素扩展为纵向的 2×1 子像素
+    Vertical,
+}
+
+impl SubpixelExpansion {
+    pub fn all() -> [SubpixelExpansion; 4] {
+        [Self::None, Self::Square, Self::Horizontal, Self::Vertical]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "不还原",
+            Self::Square => "2×2",
+            Self::Horizontal => "1×2 (横向)",
+            Self::Vertical => "2×1 (纵向)",
+        }
+    }
+
+    /// 子像素块的宽高
+    pub fn block(&self) -> (u32, u32) {
+        match self {
+            Self::None => (1, 1),
+            Self::Square => (2, 2),
+            Self::Horizontal => (2, 1),
+            Self::Vertical => (1, 2),
+        }
+    }
+}
+
+/// 叠加视觉密码的分享图：任一分享图为黑（不透明且亮度低于阈值）即为黑，
+/// 然后按子像素块统计黑色比例，超过一半的块还原为黑色像素，其余为白色
+/// keep_size 为真时每块保持原大小，否则每块缩为一个像素
+pub fn stack_shares(
+    shares: &[(RgbaImage, (i32, i32))],
+    threshold: u32,
+    expansion: SubpixelExpansion,
+    keep_size: bool,
+) -> RgbaImage {
+    let width = shares.iter().map(|(img, _)| img.width()).max().unwrap_or(0);
+    let height = shares.iter().map(|(img, _)| img.height()).max().unwrap_or(0);
+    let black = |x: u32, y: u32| {
+        shares.iter().any(|(img, offset)| {
+            let p = pixel_at(img, *offset, x, y);
+            // 透明像素视为白色（未遮挡）
+            p[3] >= 128 && luminance(&p) < threshold
+        })
+    };
+
+    let (bw, bh) = expansion.block();
+    let (cols, rows) = (width / bw, height / bh);
+    let (out_w, out_h) = if keep_size { (cols * bw, rows * bh) } else { (cols, rows) };
+    let mut result = RgbaImage::new(out_w.max(1), out_h.max(1));
+    for by in 0..rows {
+        for bx in 0..cols {
+            let mut count = 0;
+            for dy in 0..bh {
+                for dx in 0..bw {
+                    count += black(bx * bw + dx, by * bh + dy) as u32;
+                }
+            }
+            let value = if count * 2 > bw * bh { 0 } else { 255 };
+            let pixel = image::Rgba([value, value, value, 255]);
+            if keep_size {
+                for dy in 0..bh {
+                    for dx in 0..bw {
+                        result.put_pixel(bx * bw + dx, by * bh + dy, pixel);
+                    }
+                }
+            } else {
+                result.put_pixel(bx, by, pixel);
+            }
+        }
+    }
+    result
+}
+
+/// 第二张图像与第一张尺寸不同时的处理方式
+#[derive(Clone, Copy, PartialEq)]
+pub enum SecondFit {
+    /// 保持原样，超出部分以透明黑色补齐
+    None,
+    /// 缩放到第一张图像的尺寸（最近邻，保留像素值）
+    Scale,
+    /// 裁剪 / 补齐到第一张图像的尺寸
+    Crop,
+}
+
+// 灰度图，用于自动对齐
+struct GreyImage {
+    data: Vec<f32>,
+    width: i32,
+    height: i32,
+}
+
+impl GreyImage {
+    fn new(img: &RgbaImage) -> Self {
+        Self {
+            data: img
+                .pixels()
+                .map(|p| 0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32)
+                .collect(),
+            width: img.width() as i32,
+            height: img.height() as i32,
+        }
+    }
+
+    fn at(&self, x: i32, y: i32) -> f32 {
+        self.data[(y * self.width + x) as usize]
+    }
+
+    // 2x2 取平均缩小一半
+    fn half(&self) -> Self {
+        let (width, height) = ((self.width / 2).max(1), (self.height / 2).max(1));
+        let mut data = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let (x0, y0) = (2 * x, 2 * y);
+                let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+                data.push((self.at(x0, y0) + self.at(x1, y0) + self.at(x0, y1) + self.at(x1, y1)) / 4.0);
+            }
+        }
+        Self { data, width, height }
+    }
+}
+
+// 偏移 (dx, dy) 下重叠区域的归一化互相关，重叠区域不足较小图像面积的 1/4 时返回 None
+// 为控制耗时，重叠区域按步长抽样
+fn zncc(g1: &GreyImage, g2: &GreyImage, dx: i32, dy: i32) -> Option<f32> {
+    let min_area = ((g1.width * g1.height).min(g2.width * g2.height) / 4).max(1);
+    // g1 中与 g2 重叠的区域
+    let (x0, y0) = (dx.max(0), dy.max(0));
+    let (x1, y1) = ((dx + g2.width).min(g1.width), (dy + g2.height).min(g1.height));
+    if x1 <= x0 || y1 <= y0 || (x1 - x0) * (y1 - y0) < min_area {
+        return None;
+    }
+    let step = (((x1 - x0) * (y1 - y0)) as f32 / 20000.0).sqrt().ceil().max(1.0) as usize;
+
+    let (mut n, mut sa, mut sb) = (0.0f64, 0.0f64, 0.0f64);
+    let (mut saa, mut sbb, mut sab) = (0.0f64, 0.0f64, 0.0f64);
+    for y in (y0..y1).step_by(step) {
+        for x in (x0..x1).step_by(step) {
+            let a = g1.at(x, y) as f64;
+            let b = g2.at(x - dx, y - dy) as f64;
+            n += 1.0;
+            sa += a;
+            sb += b;
+            saa += a * a;
+            sbb += b * b;
+            sab += a * b;
+        }
+    }
+    let cov = sab - sa * sb / n;
+    let va = saa - sa * sa / n;
+    let vb = sbb - sb * sb / n;
+    Some(if va > 1e-6 && vb > 1e-6 { (cov / (va * vb).sqrt()) as f32 } else { 0.0 })
+}
+
+// 在 center 周围 ±radius（且不超过 ±limit）内搜索得分最高的偏移，得分相同时优先选择位移较小的偏移
+fn best_shift(g1: &GreyImage, g2: &GreyImage, center: (i32, i32), radius: i32, limit: i32) -> (i32, i32) {
+    let mut best = center;
+    let mut best_score = f32::MIN;
+    let distance = |(dx, dy): (i32, i32)| dx.abs() + dy.abs();
+    for dy in (center.1 - radius).max(-limit)..=(center.1 + radius).min(limit) {
+        for dx in (center.0 - radius).max(-limit)..=(center.0 + radius).min(limit) {
+            let Some(score) = zncc(g1, g2, dx, dy) else {
+                continue;
+            };
+            if score > best_score + 1e-6 || (score > best_score - 1e-6 && distance((dx, dy)) < distance(best)) {
+                best_score = score;
+                best = (dx, dy);
+            }
+        }
+    }
+    best
+}
+
+/// 在 ±max_shift 范围内搜索第二张图像相对第一张的偏移，使重叠区域灰度的归一化互相关最大
+/// 由粗到细搜索：先在缩小的图像上穷举，再逐级放大并在上一级结果附近细化
+pub fn auto_align(img1: &RgbaImage, img2: &RgbaImage, max_shift: i32) -> (i32, i32) {
+    // 最粗一级的搜索半径不超过该值
+    const COARSE_RADIUS: i32 = 8;
+    // 缩小后图像的最短边不小于该值
+    const MIN_SIDE: i32 = 16;
+
+    let mut levels = vec![(GreyImage::new(img1), GreyImage::new(img2))];
+    loop {
+        let (g1, g2) = levels.last().unwrap();
+        let scale = 1 << (levels.len() - 1);
+        let min_side = g1.width.min(g1.height).min(g2.width).min(g2.height);
+        if max_shift / scale <= COARSE_RADIUS || min_side / 2 < MIN_SIDE {
+            break;
+        }
+        let next = (g1.half(), g2.half());
+        levels.push(next);
+    }
+
+    let coarsest = levels.len() - 1;
+    let limit = |level: usize| (max_shift + (1 << level) - 1) >> level;
+    let (g1, g2) = &levels[coarsest];
+    let mut best = best_shift(g1, g2, (0, 0), limit(coarsest), limit(coarsest));
+    for level in (0..coarsest).rev() {
+        let (g1, g2) = &levels[level];
+        best = best_shift(g1, g2, (best.0 * 2, best.1 * 2), 2, limit(level));
+    }
+    best
+}
+
+const DEFAULT_EXPRESSION: &str = "r = (a.r ^ b.r) & 1 ? 255 : 0; g = r; b = r";
+
+// 像素亮度（整数近似）
+fn luminance(p: &image::Rgba<u8>) -> u32 {
+    (299 * p[0] as u32 + 587 * p[1] as u32 + 114 * p[2] as u32) / 1000
+}
+
+/// 参与合成的一张图像
+pub struct CombineInput {
+    pub image: RgbaImage,
+    pub name: String,
+    /// 是否参与合成
+    pub enabled: bool,
+    /// 相对第一张启用图像的偏移（像素）
+    pub offset: (i32, i32),
+}
+
+impl CombineInput {
+    pub fn new(image: RgbaImage, name: impl Into<String>) -> Self {
+        Self {
+            image,
+            name: name.into(),
+            enabled: true,
+            offset: (0, 0),
+        }
+    }
+}
+
+// 图像在合成坐标 (x, y) 处的像素（已应用偏移），超出范围为透明黑色
+fn pixel_at(img: &RgbaImage, offset: (i32, i32), x: u32, y: u32) -> image::Rgba<u8> {
+    let sx = x as i64 - offset.0 as i64;
+    let sy = y as i64 - offset.1 as i64;
+    if sx >= 0 && sy >= 0 && sx < img.width() as i64 && sy < img.height() as i64 {
+        *img.get_pixel(sx as u32, sy as u32)
+    } else {
+        image::Rgba([0, 0, 0, 0])
+    }
+}
 
 pub struct ImageCombiner {
+    /// 主窗口中的图像，重置时恢复为唯一的输入
     img1: RgbaImage,
-    img2: Rc<RefCell<Option<RgbaImage>>>,
+    /// 按顺序参与合成的图像，运算从第一张启用的图像开始依次累积
+    inputs: Vec<CombineInput>,
+    /// 当前选中（用于偏移调整和拖动）的输入
+    selected: usize,
     transform_num: Rc<RefCell<i32>>,
     texture: Option<TextureHandle>, // 存储合成图像的纹理
+    /// 拖动时尚未累计到整像素的位移
+    drag_remainder: egui::Vec2,
+    /// 与第一张启用图像尺寸不同时的处理方式
+    fit: SecondFit,
+    /// 自动对齐的搜索范围
+    align_range: i32,
+    /// 位平面运算参与的位（bit 0 为最低位），其余位保留第一张图像的值
+    bit_mask: u8,
+    /// 逐像素表达式及其编译结果
+    expression: String,
+    program: Result<PixelProgram, String>,
+    /// 视觉密码：黑色判定阈值、子像素扩展方式、还原后是否保持原尺寸
+    vc_threshold: u32,
+    vc_expansion: SubpixelExpansion,
+    vc_keep_size: bool,
+    /// 差异报告面板，以及对齐后参与比较的 (原图, 副本)
+    diff_panel: DiffPanel,
+    diff_pair: Option<(RgbaImage, RgbaImage)>,
+    paste_shortcut: clipboard::PasteShortcut,
 }
 
 impl ImageCombiner {
     pub fn new(img1: RgbaImage) -> Self {
         Self {
+            inputs: vec![CombineInput::new(img1.clone(), "主图像")],
             img1,
-            img2: Rc::new(RefCell::new(None)),
+            selected: 0,
             transform_num: Rc::new(RefCell::new(0)),
             texture: None,
+            drag_remainder: egui::Vec2::ZERO,
+            fit: SecondFit::None,
+            align_range: 32,
+            bit_mask: 0x01,
+            expression: DEFAULT_EXPRESSION.to_string(),
+            program: PixelProgram::parse(DEFAULT_EXPRESSION),
+            vc_threshold: 128,
+            vc_expansion: SubpixelExpansion::Square,
+            vc_keep_size: false,
+            diff_panel: DiffPanel::default(),
+            diff_pair: None,
+            paste_shortcut: clipboard::PasteShortcut::default(),
         }
     }
 
-    pub fn update(&mut self, ui: &mut egui::Ui) { 
-        // 检查拖放
-        if !ui.ctx().input(|i| i.raw.dropped_files.is_empty()) {
-            if let Some(dropped_file) = ui.ctx().input(|i| i.raw.dropped_files.first().cloned()) {
-                if let Some(path) = dropped_file.path {
-                    if let Ok(img) = image::open(&path) {
-                        *self.img2.borrow_mut() = Some(img.to_rgba8());
-                        self.update_image_with_context(ui.ctx());
-                    }
+    // 添加一张输入图像并选中它
+    fn add_input(&mut self, image: RgbaImage, name: String) {
+        self.inputs.push(CombineInput::new(image, name));
+        self.selected = self.inputs.len() - 1;
+    }
+
+    /// 从外部（例如其他标签页）添加一张输入图像
+    pub fn add_image(&mut self, ctx: &Context, image: RgbaImage, name: String) {
+        self.add_input(image, name);
+        self.update_image_with_context(ctx);
+    }
+
+    // 选中输入的偏移（第一张启用的图像作为画布原点，没有偏移）
+    fn selected_offset_mut(&mut self) -> Option<&mut (i32, i32)> {
+        let first = self.inputs.iter().position(|input| input.enabled)?;
+        if self.selected == first {
+            return None;
+        }
+        self.inputs.get_mut(self.selected).map(|input| &mut input.offset)
+    }
+
+    /// frames 为帧浏览器中的所有帧，可一次性加入合成列表
+    pub fn update(&mut self, ui: &mut egui::Ui, frames: &[&RgbaImage]) { 
+        // 检查拖放：拖入的所有图片按顺序加入列表
+        let dropped_files = ui.ctx().input(|i| i.raw.dropped_files.clone());
+        if !dropped_files.is_empty() {
+            for path in dropped_files.into_iter().filter_map(|file| file.path) {
+                if let Ok(img) = image::open(&path) {
+                    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                    self.add_input(img.to_rgba8(), name);
                 }
             }
+            self.update_image_with_context(ui.ctx());
         }
 
-        // 检查键盘输入
-        let left_key_pressed = ui.ctx().input(|i| i.key_pressed(egui::Key::ArrowLeft));
-        let right_key_pressed = ui.ctx().input(|i| i.key_pressed(egui::Key::ArrowRight));
+        // Ctrl+V 粘贴剪贴板中的图片（正在编辑表达式时不拦截）
+        if let Some(text) = self.paste_shortcut.poll(ui.ctx()) {
+            if !ui.ctx().wants_keyboard_input() {
+                self.paste_input(ui.ctx(), text.as_deref());
+            }
+        }
+
+        // 检查键盘输入：Shift + 方向键微调第二张图像的偏移，单独的左右键切换模式
+        let shift_held = ui.ctx().input(|i| i.modifiers.shift);
+        if shift_held {
+            let nudge = ui.ctx().input(|i| {
+                let key = |k: egui::Key| i.key_pressed(k) as i32;
+                (
+                    key(egui::Key::ArrowRight) - key(egui::Key::ArrowLeft),
+                    key(egui::Key::ArrowDown) - key(egui::Key::ArrowUp),
+                )
+            });
+            if nudge != (0, 0) {
+                if let Some(offset) = self.selected_offset_mut() {
+                    offset.0 += nudge.0;
+                    offset.1 += nudge.1;
+                    self.update_image_with_context(ui.ctx());
+                }
+            }
+        }
+        let left_key_pressed = !shift_held && ui.ctx().input(|i| i.key_pressed(egui::Key::ArrowLeft));
+        let right_key_pressed = !shift_held && ui.ctx().input(|i| i.key_pressed(egui::Key::ArrowRight));
         
         if left_key_pressed {
             self.backward(ui.ctx());
@@ -50,13 +394,41 @@ impl ImageCombiner {
         }
 
         ui.vertical(|ui| {
-            ui.label("请选择第二张图片（可以直接拖入图片）");
+            ui.label("请添加要合成的图片（可以直接拖入多张图片）");
     
             if let Some(texture) = &self.texture {
-                ui.image(texture);
+                // 拖动合成结果以移动第二张图像
+                let response = ui.add(egui::Image::new(texture).sense(egui::Sense::drag()));
+                if response.dragged() {
+                    self.drag_remainder += response.drag_delta();
+                    let (dx, dy) = (self.drag_remainder.x.trunc(), self.drag_remainder.y.trunc());
+                    if dx != 0.0 || dy != 0.0 {
+                        self.drag_remainder -= egui::vec2(dx, dy);
+                        if let Some(offset) = self.selected_offset_mut() {
+                            offset.0 += dx as i32;
+                            offset.1 += dy as i32;
+                            self.update_image_with_context(ui.ctx());
+                        }
+                    }
+                }
             }
     
             ui.separator();
+
+            self.inputs_ui(ui, frames);
+
+            ui.separator();
+
+            self.alignment_ui(ui);
+
+            egui::CollapsingHeader::new("差异报告")
+                .id_salt("combine_diff_report")
+                .show(ui, |ui| {
+                    let pair = self.diff_pair.as_ref().map(|(a, b)| (a, b));
+                    self.diff_panel.ui(ui, pair);
+                });
+
+            ui.separator();
     
             // 显示当前合成模式的文本
             ui.label(format!("当前合成模式: {}", self.get_transform_text()));
@@ -68,7 +440,9 @@ impl ImageCombiner {
             let mut current_transform = *self.transform_num.borrow();
             if ui.add(Slider::new(&mut current_transform, 0..=NUM_TRANSFORMS - 1).text("变换模式")).changed() {
                 *self.transform_num.borrow_mut() = current_transform;
+                self.update_image_with_context(ui.ctx());
             }
+            self.mode_options_ui(ui);
             ui.separator();
     
             // Buttons
@@ -82,6 +456,10 @@ impl ImageCombiner {
                 if ui.button("打开图片").clicked() {
                     self.open_second_image(ui.ctx());
                 }
+
+                if ui.button("粘贴").on_hover_text("添加剪贴板中的图片 (Ctrl+V)").clicked() {
+                    self.paste_input(ui.ctx(), None);
+                }
     
                 if ui.button("保存").clicked() {
                     self.save_image(ui.ctx());
@@ -100,8 +478,208 @@ impl ImageCombiner {
         });
     }
 
+    // 位平面运算的位选择与逐像素表达式
+    fn mode_options_ui(&mut self, ui: &mut egui::Ui) {
+        let transform_num = *self.transform_num.borrow();
+        let mut changed = false;
+        if (19..=21).contains(&transform_num) {
+            ui.horizontal(|ui| {
+                ui.label("参与运算的位平面:");
+                for bit in (0..8).rev() {
+                    let mut on = self.bit_mask & (1 << bit) != 0;
+                    if ui.checkbox(&mut on, bit.to_string()).changed() {
+                        self.bit_mask ^= 1 << bit;
+                        changed = true;
+                    }
+                }
+            });
+        }
+        if transform_num == EXPRESSION_MODE {
+            ui.label("逐像素表达式（a 为累积结果，b 为当前图像，用 ; 或换行分隔各通道）:");
+            let response = ui.add(
+                egui::TextEdit::multiline(&mut self.expression)
+                    .code_editor()
+                    .desired_rows(3)
+                    .desired_width(f32::INFINITY),
+            );
+            if response.changed() {
+                self.program = PixelProgram::parse(&self.expression);
+                changed = self.program.is_ok();
+            }
+            match &self.program {
+                Ok(_) => ui.label("变量: a.r a.g a.b a.a a.l(亮度) b.* x y；函数: min max abs；未赋值的 r/g/b 取 a，a 取 255"),
+                Err(e) => ui.colored_label(egui::Color32::RED, format!("表达式错误: {}", e)),
+            };
+        }
+        if transform_num == VISUAL_CRYPTO_MODE {
+            ui.horizontal_wrapped(|ui| {
+                ui.label("子像素扩展:");
+                for expansion in SubpixelExpansion::all() {
+                    changed |= ui.radio_value(&mut self.vc_expansion, expansion, expansion.name()).changed();
+                }
+                ui.separator();
+                ui.label("黑色阈值:");
+                changed |= ui.add(egui::DragValue::new(&mut self.vc_threshold).range(1..=255)).changed();
+                changed |= ui.checkbox(&mut self.vc_keep_size, "保持原尺寸").changed();
+            });
+            ui.label("所有启用的图像作为分享图叠加；可先用偏移或自动对齐将分享图对齐到子像素块");
+        }
+        if changed {
+            self.update_image_with_context(ui.ctx());
+        }
+    }
+
+    // 输入图像列表：启用开关、顺序调整、删除，以及从帧浏览器添加所有帧
+    fn inputs_ui(&mut self, ui: &mut egui::Ui, frames: &[&RgbaImage]) {
+        let mut changed = false;
+        let mut move_up = None;
+        let mut remove = None;
+        egui::CollapsingHeader::new(format!("输入图像: {} 张", self.inputs.len()))
+            .id_salt("combine_inputs")
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_salt("combine_inputs_scroll")
+                    .max_height(160.0)
+                    .show(ui, |ui| {
+                        for (i, input) in self.inputs.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                changed |= ui.checkbox(&mut input.enabled, "").changed();
+                                let label = format!(
+                                    "{}. {} ({}x{})  偏移 ({}, {})",
+                                    i + 1,
+                                    input.name,
+                                    input.image.width(),
+                                    input.image.height(),
+                                    input.offset.0,
+                                    input.offset.1
+                                );
+                                if ui.selectable_label(self.selected == i, label).clicked() {
+                                    self.selected = i;
+                                    changed = true;
+                                }
+                                if ui.add_enabled(i > 0, egui::Button::new("↑").small()).clicked() {
+                                    move_up = Some(i);
+                                }
+                                if ui.button("✕").clicked() {
+                                    remove = Some(i);
+                                }
+                            });
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!frames.is_empty(), egui::Button::new("添加帧浏览器中的所有帧")).clicked() {
+                        for (i, frame) in frames.iter().enumerate() {
+                            self.add_input((*frame).clone(), format!("帧 {}", i + 1));
+                        }
+                        changed = true;
+                    }
+                    if ui.button("全部启用").clicked() {
+                        self.inputs.iter_mut().for_each(|input| input.enabled = true);
+                        changed = true;
+                    }
+                    if ui.button("全部停用").clicked() {
+                        self.inputs.iter_mut().for_each(|input| input.enabled = false);
+                        changed = true;
+                    }
+                });
+            });
+
+        if let Some(i) = move_up {
+            self.inputs.swap(i - 1, i);
+            if self.selected == i {
+                self.selected = i - 1;
+            } else if self.selected == i - 1 {
+                self.selected = i;
+            }
+            changed = true;
+        }
+        if let Some(i) = remove {
+            self.inputs.remove(i);
+            self.selected = self.selected.min(self.inputs.len().saturating_sub(1));
+            changed = true;
+        }
+        if changed {
+            self.update_image_with_context(ui.ctx());
+        }
+    }
+
+    // 选中图像的偏移、尺寸处理与自动对齐
+    fn alignment_ui(&mut self, ui: &mut egui::Ui) {
+        let fit_before = self.fit;
+        let mut offset_changed = false;
+        let mut align_range = self.align_range;
+        ui.horizontal_wrapped(|ui| {
+            match self.selected_offset_mut() {
+                Some(offset) => {
+                    ui.label("选中图像偏移 X:");
+                    offset_changed |= ui.add(egui::DragValue::new(&mut offset.0)).changed();
+                    ui.label("Y:");
+                    offset_changed |= ui.add(egui::DragValue::new(&mut offset.1)).changed();
+                    if ui.button("归零").clicked() {
+                        *offset = (0, 0);
+                        offset_changed = true;
+                    }
+                    ui.label("（拖动图像或 Shift+方向键微调）");
+                }
+                None => {
+                    ui.label("选中的是第一张启用的图像（画布原点），请选择其他图像调整偏移");
+                }
+            }
+        });
+        ui.horizontal_wrapped(|ui| {
+            ui.label("尺寸:");
+            ui.radio_value(&mut self.fit, SecondFit::None, "保持原样");
+            ui.radio_value(&mut self.fit, SecondFit::Scale, "缩放到第一张尺寸");
+            ui.radio_value(&mut self.fit, SecondFit::Crop, "裁剪到第一张尺寸");
+            ui.separator();
+            ui.label("搜索范围 ±");
+            ui.add(egui::DragValue::new(&mut align_range).range(1..=256));
+            if ui.add_enabled(self.selected_offset_mut().is_some(), egui::Button::new("自动对齐")).clicked() {
+                let base = self.inputs.iter().find(|input| input.enabled).map(|input| input.image.clone());
+                if let Some(base) = base {
+                    let prepared = self.prepared(&self.inputs[self.selected].image, &base);
+                    let aligned = auto_align(&base, &prepared, align_range);
+                    if let Some(offset) = self.selected_offset_mut() {
+                        *offset = aligned;
+                        offset_changed = true;
+                    }
+                }
+            }
+        });
+        self.align_range = align_range;
+        if offset_changed || fit_before != self.fit {
+            self.update_image_with_context(ui.ctx());
+        }
+    }
+
+    /// 按尺寸处理方式将图像调整到 base 的尺寸
+    fn prepared(&self, img: &RgbaImage, base: &RgbaImage) -> RgbaImage {
+        let (width, height) = base.dimensions();
+        match self.fit {
+            SecondFit::None => img.clone(),
+            SecondFit::Scale => image::imageops::resize(img, width, height, image::imageops::FilterType::Nearest),
+            SecondFit::Crop => {
+                let mut cropped = RgbaImage::new(width, height);
+                image::imageops::replace(&mut cropped, img, 0, 0);
+                cropped
+            }
+        }
+    }
+
+    /// 启用的图像（第一张之后的已按尺寸处理方式调整）及其偏移
+    fn enabled_images(&self) -> Vec<(RgbaImage, (i32, i32))> {
+        let mut enabled = self.inputs.iter().filter(|input| input.enabled);
+        let Some(base) = enabled.next() else {
+            return Vec::new();
+        };
+        let mut images = vec![(base.image.clone(), (0, 0))];
+        images.extend(enabled.map(|input| (self.prepared(&input.image, &base.image), input.offset)));
+        images
+    }
+
     fn backward(&mut self, ctx: &Context) {
-        if self.img2.borrow().is_none() { return; }
+        if self.inputs.len() < 2 { return; }
         {
             let mut num = self.transform_num.borrow_mut();
             *num = if *num <= 0 {
@@ -114,7 +692,7 @@ impl ImageCombiner {
     }
 
     fn forward(&mut self, ctx: &Context) {
-        if self.img2.borrow().is_none() { return; }
+        if self.inputs.len() < 2 { return; }
         {
             let mut num = self.transform_num.borrow_mut();
             *num = (*num + 1) % NUM_TRANSFORMS;
@@ -123,19 +701,32 @@ impl ImageCombiner {
     }
 
     fn open_second_image(&mut self, ctx: &Context) {
-        let dialog = rfd::FileDialog::new().pick_file();
-        if let Some(path) = dialog {
-            if let Ok(img) = image::open(path) {
-                *self.img2.borrow_mut() = Some(img.to_rgba8());
+        let dialog = rfd::FileDialog::new().pick_files();
+        if let Some(paths) = dialog {
+            for path in paths {
+                if let Ok(img) = image::open(&path) {
+                    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                    self.add_input(img.to_rgba8(), name);
+                } else {
+                    println!("无法打开图片");
+                }
+            }
+            self.update_image_with_context(ctx);
+        }
+    }
+
+    fn paste_input(&mut self, ctx: &Context, text: Option<&str>) {
+        match clipboard::paste_image(text) {
+            Ok(img) => {
+                self.add_input(img, "剪贴板图像".to_string());
                 self.update_image_with_context(ctx);
-            } else {
-                println!("无法打开图片");
             }
+            Err(e) => eprintln!("粘贴图片失败: {}", e),
         }
     }
 
     fn save_image(&self, _ctx: &Context) {
-        if self.img2.borrow().is_none() { return; }
+        if self.inputs.len() < 2 { return; }
 
         let dialog = rfd::FileDialog::new().save_file();
         if let Some(path) = dialog {
@@ -148,37 +739,39 @@ impl ImageCombiner {
     }
 
     fn get_combined_image(&self) -> Option<RgbaImage> {
-        let img2 = self.img2.borrow();
-        let img2 = img2.as_ref()?;
+        let images = self.enabled_images();
+        if images.len() < 2 {
+            return None;
+        }
 
         let transform_num = *self.transform_num.borrow();
         
         match transform_num {
-            11 => self.horizontal_interlace(img2),
-            12 => self.vertical_interlace(img2),
-            _ => self.combine_pixels(img2, transform_num),
+            11 => Some(Self::horizontal_interlace(&images)),
+            12 => Some(Self::vertical_interlace(&images)),
+            VISUAL_CRYPTO_MODE => Some(stack_shares(&images, self.vc_threshold, self.vc_expansion, self.vc_keep_size)),
+            _ => {
+                // 从第一张图像开始依次累积运算
+                let mut iter = images.into_iter();
+                let (mut acc, _) = iter.next()?;
+                for (img, offset) in iter {
+                    acc = self.combine_pixels(&acc, &img, offset, transform_num);
+                }
+                Some(acc)
+            }
         }
     }
 
-    fn combine_pixels(&self, img2: &RgbaImage, transform_num: i32) -> Option<RgbaImage> {
-        let width = self.img1.width().max(img2.width());
-        let height = self.img1.height().max(img2.height());
+    fn combine_pixels(&self, img1: &RgbaImage, img2: &RgbaImage, offset: (i32, i32), transform_num: i32) -> RgbaImage {
+        let width = img1.width().max(img2.width());
+        let height = img1.height().max(img2.height());
         
         let mut result = ImageBuffer::new(width, height);
 
         for y in 0..height {
             for x in 0..width {
-                let p1 = if x < self.img1.width() && y < self.img1.height() {
-                    self.img1.get_pixel(x, y)
-                } else {
-                    &image::Rgba([0, 0, 0, 0])
-                };
-
-                let p2 = if x < img2.width() && y < img2.height() {
-                    img2.get_pixel(x, y)
-                } else {
-                    &image::Rgba([0, 0, 0, 0])
-                };
+                let p1 = &pixel_at(img1, (0, 0), x, y);
+                let p2 = &pixel_at(img2, offset, x, y);
 
                 let combined = match transform_num  {
                     0 => [p1[0]^p2[0], p1[1]^p2[1], p1[2]^p2[2], 255], // XOR
@@ -196,6 +789,73 @@ impl ImageCombiner {
                         ((p1[2] as u16 + p2[2] as u16) % 256) as u8,
                         255
                     ],
+                    5 => [ // SUB
+                        p1[0].saturating_sub(p2[0]),
+                        p1[1].saturating_sub(p2[1]),
+                        p1[2].saturating_sub(p2[2]),
+                        255
+                    ],
+                    6 => [ // SUB separate
+                        p1[0].wrapping_sub(p2[0]),
+                        p1[1].wrapping_sub(p2[1]),
+                        p1[2].wrapping_sub(p2[2]),
+                        255
+                    ],
+                    7 => [ // MUL
+                        p1[0].saturating_mul(p2[0]),
+                        p1[1].saturating_mul(p2[1]),
+                        p1[2].saturating_mul(p2[2]),
+                        255
+                    ],
+                    8 => [ // MUL separate
+                        p1[0].wrapping_mul(p2[0]),
+                        p1[1].wrapping_mul(p2[1]),
+                        p1[2].wrapping_mul(p2[2]),
+                        255
+                    ],
+                    9 => [p1[0].max(p2[0]), p1[1].max(p2[1]), p1[2].max(p2[2]), 255], // Lightest
+                    10 => [p1[0].min(p2[0]), p1[1].min(p2[1]), p1[2].min(p2[2]), 255], // Darkest
+                    13 => [p1[0].abs_diff(p2[0]), p1[1].abs_diff(p2[1]), p1[2].abs_diff(p2[2]), 255], // 绝对差
+                    14 => [ // 平均
+                        ((p1[0] as u16 + p2[0] as u16) / 2) as u8,
+                        ((p1[1] as u16 + p2[1] as u16) / 2) as u8,
+                        ((p1[2] as u16 + p2[2] as u16) / 2) as u8,
+                        255
+                    ],
+                    15 => { // 滤色: 255 - (255 - a)(255 - b) / 255
+                        let screen = |a: u8, b: u8| 255 - ((255 - a as u16) * (255 - b as u16) / 255) as u8;
+                        [screen(p1[0], p2[0]), screen(p1[1], p2[1]), screen(p1[2], p2[2]), 255]
+                    }
+                    16 => { // 取亮度较大的像素
+                        let p = if luminance(p2) > luminance(p1) { p2 } else { p1 };
+                        [p[0], p[1], p[2], 255]
+                    }
+                    17 => { // 取亮度较小的像素
+                        let p = if luminance(p2) < luminance(p1) { p2 } else { p1 };
+                        [p[0], p[1], p[2], 255]
+                    }
+                    18 => [ // 正片叠底: a * b / 255
+                        (p1[0] as u16 * p2[0] as u16 / 255) as u8,
+                        (p1[1] as u16 * p2[1] as u16 / 255) as u8,
+                        (p1[2] as u16 * p2[2] as u16 / 255) as u8,
+                        255
+                    ],
+                    19..=21 => { // 只在选中的位平面上做位运算
+                        let mask = self.bit_mask;
+                        let op = |a: u8, b: u8| {
+                            let v = match transform_num {
+                                19 => a ^ b,
+                                20 => a | b,
+                                _ => a & b,
+                            };
+                            (a & !mask) | (v & mask)
+                        };
+                        [op(p1[0], p2[0]), op(p1[1], p2[1]), op(p1[2], p2[2]), 255]
+                    }
+                    EXPRESSION_MODE => match &self.program {
+                        Ok(program) => program.eval(&PixelVars { a: p1.0, b: p2.0, x, y }),
+                        Err(_) => [p1[0], p1[1], p1[2], 255],
+                    },
                     _ => [p1[0], p1[1], p1[2], 255]
                 };
 
@@ -203,48 +863,67 @@ impl ImageCombiner {
             }
         }
 
-        Some(result)
+        result
     }
 
-    fn horizontal_interlace(&self, img2: &RgbaImage) -> Option<RgbaImage> {
-        let width = self.img1.width().min(img2.width());
-        let height = self.img1.height().min(img2.height());
+    // 按行交错：第 y 行依次取自每张图像的第 y 行
+    fn horizontal_interlace(images: &[(RgbaImage, (i32, i32))]) -> RgbaImage {
+        let width = images.iter().map(|(img, _)| img.width()).min().unwrap_or(0);
+        let height = images.iter().map(|(img, _)| img.height()).min().unwrap_or(0);
+        let n = images.len() as u32;
         
-        let mut result = ImageBuffer::new(width, height * 2);
+        let mut result = ImageBuffer::new(width, height * n);
 
         for y in 0..height {
             for x in 0..width {
-                let p1 = self.img1.get_pixel(x, y);
-                let p2 = img2.get_pixel(x, y);
-                
-                result.put_pixel(x, y*2, *p1);
-                result.put_pixel(x, y*2+1, *p2);
+                for (k, (img, offset)) in images.iter().enumerate() {
+                    result.put_pixel(x, y * n + k as u32, pixel_at(img, *offset, x, y));
+                }
             }
         }
 
-        Some(result)
+        result
     }
 
-    fn vertical_interlace(&self, img2: &RgbaImage) -> Option<RgbaImage> {
-        let width = self.img1.width().min(img2.width());
-        let height = self.img1.height().min(img2.height());
+    // 按列交错：第 x 列依次取自每张图像的第 x 列
+    fn vertical_interlace(images: &[(RgbaImage, (i32, i32))]) -> RgbaImage {
+        let width = images.iter().map(|(img, _)| img.width()).min().unwrap_or(0);
+        let height = images.iter().map(|(img, _)| img.height()).min().unwrap_or(0);
+        let n = images.len() as u32;
         
-        let mut result = ImageBuffer::new(width * 2, height);
+        let mut result = ImageBuffer::new(width * n, height);
 
         for y in 0..height {
             for x in 0..width {
-                let p1 = self.img1.get_pixel(x, y);
-                let p2 = img2.get_pixel(x, y);
-                
-                result.put_pixel(x*2, y, *p1);
-                result.put_pixel(x*2+1, y, *p2);
+                for (k, (img, offset)) in images.iter().enumerate() {
+                    result.put_pixel(x * n + k as u32, y, pixel_at(img, *offset, x, y));
+                }
             }
         }
 
-        Some(result)
+        result
+    }
+
+    /// 差异报告比较的两张图像：第一张启用的图像作为原图，
+    /// 选中的图像（未选中其他启用图像时取第二张）按偏移对齐到原图尺寸
+    fn comparison_pair(&self) -> Option<(RgbaImage, RgbaImage)> {
+        let first = self.inputs.iter().position(|input| input.enabled)?;
+        let other = if self.selected != first && self.inputs.get(self.selected).is_some_and(|input| input.enabled) {
+            self.selected
+        } else {
+            self.inputs.iter().enumerate().skip(first + 1).find(|(_, input)| input.enabled)?.0
+        };
+        let base = &self.inputs[first].image;
+        let input = &self.inputs[other];
+        let mut aligned = RgbaImage::new(base.width(), base.height());
+        image::imageops::replace(&mut aligned, &self.prepared(&input.image, base), input.offset.0 as i64, input.offset.1 as i64);
+        Some((base.clone(), aligned))
     }
 
     fn update_image_with_context(&mut self, ctx: &Context) {
+        self.texture = None;
+        self.diff_pair = self.comparison_pair();
+        self.diff_panel.clear();
         if let Some(combined) = self.get_combined_image() {
             let size = [combined.width() as usize, combined.height() as usize];
             let image_data = egui::ColorImage::from_rgba_unmultiplied(size, combined.as_raw());
@@ -271,14 +950,129 @@ impl ImageCombiner {
             10 => "Darkest (R,G,B separate)",
             11 => "Horizontal Interlace",
             12 => "Vertical Interlace",
+            13 => "Absolute Difference",
+            14 => "Average",
+            15 => "Screen",
+            16 => "Lighter Luminance",
+            17 => "Darker Luminance",
+            18 => "Multiply (a*b/255)",
+            19 => "XOR (selected bit planes)",
+            20 => "OR (selected bit planes)",
+            21 => "AND (selected bit planes)",
+            EXPRESSION_MODE => "Expression",
+            VISUAL_CRYPTO_MODE => "Visual Cryptography (stack shares)",
             _ => "???"
         }.to_string()
     }
 
     /// 重置状态到初始值
     pub fn reset(&mut self) {
-        *self.img2.borrow_mut() = None;
+        self.inputs = vec![CombineInput::new(self.img1.clone(), "主图像")];
+        self.selected = 0;
         *self.transform_num.borrow_mut() = 0;
         self.texture = None;
+        self.diff_pair = None;
+        self.diff_panel.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 平滑的纹理，缩小后仍保留可对齐的特征
+    fn texture(width: u32, height: u32, ox: i32, oy: i32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            let (x, y) = ((x as i32 + ox) as f32, (y as i32 + oy) as f32);
+            let v = 128.0 + 50.0 * (x / 7.0 + y / 31.0).sin()
+                + 40.0 * (y / 9.0 - x * x / 4000.0).cos()
+                + 30.0 * (x * y / 900.0).sin();
+            image::Rgba([v as u8, v as u8, v as u8, 255])
+        })
+    }
+
+    const BLACK: image::Rgba<u8> = image::Rgba([0, 0, 0, 255]);
+    const WHITE: image::Rgba<u8> = image::Rgba([255, 255, 255, 255]);
+
+    // (2, 2) 方案的两张分享图：白色像素两张相同，黑色像素互补
+    fn shares(secret: &[&[bool]]) -> (RgbaImage, RgbaImage) {
+        const PATTERNS: [[bool; 4]; 3] =
+            [[true, false, false, true], [true, true, false, false], [false, true, true, false]];
+        let (w, h) = (secret[0].len() as u32, secret.len() as u32);
+        let mut a = RgbaImage::new(w * 2, h * 2);
+        let mut b = RgbaImage::new(w * 2, h * 2);
+        for y in 0..h {
+            for x in 0..w {
+                let pattern = PATTERNS[((x + y * 2) % 3) as usize];
+                for (i, &black) in pattern.iter().enumerate() {
+                    let (px, py) = (x * 2 + i as u32 % 2, y * 2 + i as u32 / 2);
+                    let other = if secret[y as usize][x as usize] { !black } else { black };
+                    a.put_pixel(px, py, if black { BLACK } else { WHITE });
+                    b.put_pixel(px, py, if other { BLACK } else { WHITE });
+                }
+            }
+        }
+        (a, b)
+    }
+
+    fn is_black(img: &RgbaImage) -> Vec<bool> {
+        img.pixels().map(|p| p[0] == 0).collect()
+    }
+
+    #[test]
+    fn stacked_shares_reveal_secret() {
+        let secret: [&[bool]; 2] = [&[true, false, true], &[false, true, true]];
+        let (a, b) = shares(&secret);
+        let stacked = stack_shares(&[(a.clone(), (0, 0)), (b.clone(), (0, 0))], 128, SubpixelExpansion::Square, false);
+        assert_eq!(stacked.dimensions(), (3, 2));
+        assert_eq!(is_black(&stacked), secret.concat());
+
+        let full = stack_shares(&[(a.clone(), (0, 0)), (b, (0, 0))], 128, SubpixelExpansion::Square, true);
+        assert_eq!(full.dimensions(), (6, 4));
+        assert_eq!(full.get_pixel(1, 1), &BLACK);
+        assert_eq!(full.get_pixel(3, 0), &WHITE);
+
+        // 单张分享图每块恰好一半为黑，不泄露秘密
+        let single = stack_shares(&[(a, (0, 0))], 128, SubpixelExpansion::Square, false);
+        assert!(is_black(&single).iter().all(|&black| !black));
+    }
+
+    #[test]
+    fn stacking_applies_offsets_and_ignores_transparency() {
+        let secret: [&[bool]; 1] = [&[true, false, true, true]];
+        let (a, b) = shares(&secret);
+        // 第二张分享图整体右移 2 像素保存，偏移 (-2, 0) 后重新对齐
+        let mut shifted = RgbaImage::from_pixel(b.width() + 2, b.height(), image::Rgba([0, 0, 0, 0]));
+        image::imageops::overlay(&mut shifted, &b, 2, 0);
+        let stacked = stack_shares(&[(a.clone(), (0, 0)), (shifted, (-2, 0))], 128, SubpixelExpansion::Square, false);
+        assert_eq!(is_black(&stacked), [true, false, true, true, false]);
+
+        // 透明的黑色像素视为未遮挡
+        let transparent = RgbaImage::new(a.width(), a.height());
+        let stacked = stack_shares(&[(a, (0, 0)), (transparent, (0, 0))], 128, SubpixelExpansion::Square, false);
+        assert!(is_black(&stacked).iter().all(|&black| !black));
+    }
+
+    #[test]
+    fn multiply_modes() {
+        let a = RgbaImage::from_pixel(1, 1, image::Rgba([200, 16, 3, 255]));
+        let b = RgbaImage::from_pixel(1, 1, image::Rgba([128, 16, 255, 255]));
+        let combiner = ImageCombiner::new(a.clone());
+        let mode = |n: i32| combiner.combine_pixels(&a, &b, (0, 0), n).get_pixel(0, 0).0;
+        assert_eq!(mode(7), [255, 255, 255, 255]);
+        // 与 StegSolve 相同，逐通道相乘后取低 8 位
+        assert_eq!(mode(8), [0, 0, 253, 255]);
+        assert_eq!(mode(18), [100, 1, 3, 255]);
+    }
+
+    #[test]
+    fn auto_align_finds_shift() {
+        let base = texture(300, 240, 0, 0);
+        // 第二张图像的 (0, 0) 对应第一张的 (37, 21)
+        let shifted = texture(200, 160, 37, 21);
+        assert_eq!(auto_align(&base, &shifted, 64), (37, 21));
+        assert_eq!(auto_align(&base, &shifted, 40), (37, 21));
+        let shifted = texture(200, 160, 3, 90);
+        assert_eq!(auto_align(&base, &shifted, 128), (3, 90));
     }
 }
@@ -0,0 +1,259 @@
+use bzip2::read::BzDecoder;
+use flate2::read::{MultiGzDecoder, ZlibDecoder};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+// ──────────────────────────────
+// 提取结果的字节变换链：按顺序对提取出的数据进行异或、取反、逆序和解压
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub enum ByteTransform {
+    /// 与密钥循环异或，hex 为 true 时密钥按十六进制解析，否则按文本
+    Xor { key: String, hex: bool },
+    /// 所有位取反
+    InvertBits,
+    /// 字节顺序反转
+    ReverseBytes,
+    /// 每个字节内部的位顺序反转
+    ReverseBits,
+    /// zlib 解压
+    Zlib,
+    /// gzip 解压
+    Gzip,
+    /// bzip2 解压
+    Bzip2,
+}
+
+/// 变换链中单个步骤的执行结果
+pub struct StepResult {
+    pub data: Vec<u8>,
+    pub error: Option<String>,
+}
+
+impl ByteTransform {
+    /// 可添加到变换链中的所有步骤（使用默认参数）
+    pub fn all() -> Vec<ByteTransform> {
+        vec![
+            ByteTransform::Xor {
+                key: String::new(),
+                hex: true,
+            },
+            ByteTransform::InvertBits,
+            ByteTransform::ReverseBytes,
+            ByteTransform::ReverseBits,
+            ByteTransform::Zlib,
+            ByteTransform::Gzip,
+            ByteTransform::Bzip2,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ByteTransform::Xor { .. } => "XOR 密钥",
+            ByteTransform::InvertBits => "按位取反",
+            ByteTransform::ReverseBytes => "字节逆序",
+            ByteTransform::ReverseBits => "字节内位逆序",
+            ByteTransform::Zlib => "zlib 解压",
+            ByteTransform::Gzip => "gzip 解压",
+            ByteTransform::Bzip2 => "bzip2 解压",
+        }
+    }
+
+    /// 对输入数据执行当前步骤；失败时原样输出输入数据并记录错误
+    pub fn apply(&self, data: &[u8]) -> StepResult {
+        let result = match self {
+            ByteTransform::Xor { key, hex } => parse_key(key, *hex).map(|key| {
+                if key.is_empty() {
+                    data.to_vec()
+                } else {
+                    data.iter()
+                        .zip(key.iter().cycle())
+                        .map(|(b, k)| b ^ k)
+                        .collect()
+                }
+            }),
+            ByteTransform::InvertBits => Ok(data.iter().map(|b| !b).collect()),
+            ByteTransform::ReverseBytes => Ok(data.iter().rev().copied().collect()),
+            ByteTransform::ReverseBits => Ok(data.iter().map(|b| b.reverse_bits()).collect()),
+            ByteTransform::Zlib => decompress(ZlibDecoder::new(data)),
+            ByteTransform::Gzip => decompress(MultiGzDecoder::new(data)),
+            ByteTransform::Bzip2 => decompress(BzDecoder::new(data)),
+        };
+        match result {
+            Ok(data) => StepResult { data, error: None },
+            Err(e) => StepResult {
+                data: data.to_vec(),
+                error: Some(e),
+            },
+        }
+    }
+}
+
+/// 解析异或密钥，十六进制模式下忽略空白和可选的 0x 前缀
+fn parse_key(key: &str, hex: bool) -> Result<Vec<u8>, String> {
+    if !hex {
+        return Ok(key.as_bytes().to_vec());
+    }
+    let key = key.trim();
+    let key = key
+        .strip_prefix("0x")
+        .or_else(|| key.strip_prefix("0X"))
+        .unwrap_or(key);
+    let digits: String = key.chars().filter(|c| !c.is_whitespace()).collect();
+    // 先检查字符，之后才能按字节切片
+    if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(format!("无效的十六进制字符: {}", c));
+    }
+    if digits.len() % 2 != 0 {
+        return Err("十六进制密钥长度必须为偶数".to_string());
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| format!("无效的十六进制密钥: {}", &digits[i..i + 2]))
+        })
+        .collect()
+}
+
+/// 读取解压器的全部输出；数据被截断时保留已解出的部分
+fn decompress<R: Read>(mut reader: R) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    match reader.read_to_end(&mut out) {
+        Ok(_) => Ok(out),
+        Err(_) if !out.is_empty() => Ok(out),
+        Err(e) => Err(format!("解压失败: {}", e)),
+    }
+}
+
+/// 依次执行变换链，返回每一步的结果（第 i 项为第 i 步的输出）
+pub fn apply_chain(chain: &[ByteTransform], data: &[u8]) -> Vec<StepResult> {
+    let mut results: Vec<StepResult> = Vec::with_capacity(chain.len());
+    for step in chain {
+        let input = results.last().map(|r| r.data.as_slice()).unwrap_or(data);
+        let result = step.apply(input);
+        results.push(result);
+    }
+    results
+}
+
+/// 根据文件头魔数识别数据类型
+pub fn detect_signature(data: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "PNG 图像"),
+        (b"\xff\xd8\xff", "JPEG 图像"),
+        (b"GIF87a", "GIF 图像"),
+        (b"GIF89a", "GIF 图像"),
+        (b"BM", "BMP 图像"),
+        (b"II*\x00", "TIFF 图像 (小端)"),
+        (b"MM\x00*", "TIFF 图像 (大端)"),
+        (b"\x00\x00\x01\x00", "ICO 图标"),
+        (b"PK\x03\x04", "ZIP 压缩包"),
+        (b"PK\x05\x06", "ZIP 压缩包 (空)"),
+        (b"Rar!\x1a\x07", "RAR 压缩包"),
+        (b"7z\xbc\xaf\x27\x1c", "7z 压缩包"),
+        (b"\x1f\x8b", "gzip 压缩数据"),
+        (b"BZh", "bzip2 压缩数据"),
+        (b"\xfd7zXZ\x00", "xz 压缩数据"),
+        (b"%PDF", "PDF 文档"),
+        (b"\x7fELF", "ELF 可执行文件"),
+        (b"MZ", "PE/DOS 可执行文件"),
+        (b"ID3", "MP3 音频 (ID3)"),
+        (b"OggS", "Ogg 容器"),
+        (b"fLaC", "FLAC 音频"),
+        (b"SQLite format 3\x00", "SQLite 数据库"),
+        (b"-----BEGIN", "PEM 文本"),
+        (b"<?xml", "XML 文本"),
+    ];
+
+    for (magic, name) in SIGNATURES {
+        if data.starts_with(magic) {
+            return Some(name);
+        }
+    }
+    if data.len() >= 12 && &data[0..4] == b"RIFF" {
+        return match &data[8..12] {
+            b"WEBP" => Some("WebP 图像"),
+            b"WAVE" => Some("WAV 音频"),
+            b"AVI " => Some("AVI 视频"),
+            _ => Some("RIFF 容器"),
+        };
+    }
+    // zlib 头：CMF=0x78 且 (CMF*256+FLG) 可被 31 整除
+    if data.len() >= 2 && data[0] == 0x78 && u16::from_be_bytes([data[0], data[1]]) % 31 == 0 {
+        return Some("zlib 压缩数据");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    #[test]
+    fn parse_key_accepts_prefix_and_whitespace() {
+        assert_eq!(parse_key("0xDE ad\tBE ef", true).unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(parse_key(" 0X0102 ", true).unwrap(), [1, 2]);
+        assert!(parse_key("0x", true).unwrap().is_empty());
+        assert_eq!(parse_key("key", false).unwrap(), b"key");
+    }
+
+    #[test]
+    fn parse_key_rejects_invalid_input() {
+        assert!(parse_key("abc", true).is_err());
+        assert!(parse_key("zz", true).is_err());
+        // 只去掉一个前缀
+        assert!(parse_key("0x0x12", true).is_err());
+        // 多字节字符不能导致按字节切片时越界
+        assert!(parse_key("a中", true).is_err());
+        assert!(parse_key("中文", true).is_err());
+    }
+
+    #[test]
+    fn chain_feeds_each_step_with_previous_output() {
+        let plain = b"hidden message";
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(plain).unwrap();
+        let key = [0x13u8, 0x37];
+        // 压缩 → 异或 → 字节逆序，变换链按相反顺序还原
+        let mut data: Vec<u8> = encoder
+            .finish()
+            .unwrap()
+            .iter()
+            .zip(key.iter().cycle())
+            .map(|(b, k)| b ^ k)
+            .collect();
+        data.reverse();
+
+        let chain = [
+            ByteTransform::ReverseBytes,
+            ByteTransform::Xor {
+                key: "1337".to_string(),
+                hex: true,
+            },
+            ByteTransform::Zlib,
+        ];
+        let results = apply_chain(&chain, &data);
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r.error.is_none()));
+        assert_eq!(results[2].data, plain);
+    }
+
+    #[test]
+    fn failed_step_passes_input_through() {
+        let chain = [ByteTransform::Gzip, ByteTransform::InvertBits];
+        let results = apply_chain(&chain, &[1, 2, 3]);
+        assert!(results[0].error.is_some());
+        assert_eq!(results[0].data, [1, 2, 3]);
+        assert_eq!(results[1].data, [0xfe, 0xfd, 0xfc]);
+    }
+
+    #[test]
+    fn reverse_bits_within_bytes() {
+        let result = ByteTransform::ReverseBits.apply(&[0b0000_0001, 0b1100_0000]);
+        assert_eq!(result.data, [0b1000_0000, 0b0000_0011]);
+    }
+}
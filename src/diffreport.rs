@@ -0,0 +1,370 @@
+use eframe::egui;
+use image::RgbaImage;
+use std::fmt::Write as _;
+
+// ──────────────────────────────
+// 差异报告：比较原图与疑似隐写副本，统计差异像素、位平面、PSNR/MSE 并提取差异处的最低位
+
+const CHANNEL_NAMES: [&str; 4] = ["R", "G", "B", "A"];
+
+/// 一个差异像素
+pub struct PixelDiff {
+    pub x: u32,
+    pub y: u32,
+    pub a: [u8; 4],
+    pub b: [u8; 4],
+}
+
+/// 两张图像的差异统计
+pub struct DiffReport {
+    pub width: u32,
+    pub height: u32,
+    /// 至少一个通道不同的像素数
+    pub differing: u64,
+    /// 每个通道不同的像素数
+    pub channel_counts: [u64; 4],
+    /// 差异区域的外接矩形 (x0, y0, x1, y1)，含端点
+    pub bbox: Option<(u32, u32, u32, u32)>,
+    /// 每个通道每个位平面（bit 0 为最低位）不同的像素数
+    pub bit_counts: [[u64; 8]; 4],
+    /// 每个通道的均方误差
+    pub mse: [f64; 4],
+    /// 按行扫描顺序的前若干个差异像素
+    pub diffs: Vec<PixelDiff>,
+}
+
+/// 比较两张图像的重叠区域，最多记录 max_diffs 个差异像素
+pub fn compare_images(a: &RgbaImage, b: &RgbaImage, max_diffs: usize) -> DiffReport {
+    let width = a.width().min(b.width());
+    let height = a.height().min(b.height());
+    let mut report = DiffReport {
+        width,
+        height,
+        differing: 0,
+        channel_counts: [0; 4],
+        bbox: None,
+        bit_counts: [[0; 8]; 4],
+        mse: [0.0; 4],
+        diffs: Vec::new(),
+    };
+    let mut squared = [0u64; 4];
+
+    for y in 0..height {
+        for x in 0..width {
+            let pa = a.get_pixel(x, y).0;
+            let pb = b.get_pixel(x, y).0;
+            if pa == pb {
+                continue;
+            }
+            report.differing += 1;
+            for c in 0..4 {
+                let xor = pa[c] ^ pb[c];
+                if xor != 0 {
+                    report.channel_counts[c] += 1;
+                }
+                for bit in 0..8 {
+                    report.bit_counts[c][bit] += (xor >> bit & 1) as u64;
+                }
+                let d = pa[c].abs_diff(pb[c]) as u64;
+                squared[c] += d * d;
+            }
+            report.bbox = Some(match report.bbox {
+                None => (x, y, x, y),
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            });
+            if report.diffs.len() < max_diffs {
+                report.diffs.push(PixelDiff { x, y, a: pa, b: pb });
+            }
+        }
+    }
+
+    let total = (width as u64 * height as u64).max(1) as f64;
+    for (mse, squared) in report.mse.iter_mut().zip(squared) {
+        *mse = squared as f64 / total;
+    }
+    report
+}
+
+/// 由均方误差计算 PSNR（dB），完全相同时为无穷大
+pub fn psnr(mse: f64) -> f64 {
+    if mse == 0.0 {
+        f64::INFINITY
+    } else {
+        10.0 * (255.0 * 255.0 / mse).log10()
+    }
+}
+
+/// 按行扫描顺序，在有差异的像素处依次取第二张图像所选通道的最低位，高位在前打包为字节
+pub fn differing_lsbs(a: &RgbaImage, b: &RgbaImage, channels: [bool; 4]) -> Vec<u8> {
+    let width = a.width().min(b.width());
+    let height = a.height().min(b.height());
+    let mut bytes = Vec::new();
+    let mut current = 0u8;
+    let mut count = 0;
+    for y in 0..height {
+        for x in 0..width {
+            let pa = a.get_pixel(x, y);
+            let pb = b.get_pixel(x, y);
+            if pa == pb {
+                continue;
+            }
+            for c in (0..4).filter(|&c| channels[c]) {
+                current = current << 1 | (pb[c] & 1);
+                count += 1;
+                if count == 8 {
+                    bytes.push(current);
+                    current = 0;
+                    count = 0;
+                }
+            }
+        }
+    }
+    bytes
+}
+
+fn format_pixel(p: &[u8; 4]) -> String {
+    format!("({}, {}, {}, {})", p[0], p[1], p[2], p[3])
+}
+
+impl DiffReport {
+    /// 生成文本报告
+    pub fn to_text(&self) -> String {
+        let total = self.width as u64 * self.height as u64;
+        let mut text = String::new();
+        let _ = writeln!(text, "比较区域: {}x{} ({} 像素)", self.width, self.height, total);
+        let _ = writeln!(
+            text,
+            "差异像素: {} ({:.4}%)",
+            self.differing,
+            self.differing as f64 * 100.0 / total.max(1) as f64
+        );
+        for (name, count) in CHANNEL_NAMES.iter().zip(self.channel_counts) {
+            let _ = writeln!(text, "  {} 通道: {}", name, count);
+        }
+        match self.bbox {
+            Some((x0, y0, x1, y1)) => {
+                let _ = writeln!(text, "差异范围: ({}, {}) - ({}, {}), {}x{}", x0, y0, x1, y1, x1 - x0 + 1, y1 - y0 + 1);
+            }
+            None => {
+                let _ = writeln!(text, "差异范围: 无");
+            }
+        }
+
+        let _ = writeln!(text, "\n位平面差异数 (bit 7 … bit 0):");
+        for (name, bits) in CHANNEL_NAMES.iter().zip(&self.bit_counts) {
+            let counts: Vec<String> = bits.iter().rev().map(|n| format!("{:>8}", n)).collect();
+            let _ = writeln!(text, "  {}: {}", name, counts.join(""));
+        }
+
+        let _ = writeln!(text, "\nMSE / PSNR:");
+        for (name, mse) in CHANNEL_NAMES.iter().zip(self.mse) {
+            let _ = writeln!(text, "  {}: {:.6} / {:.2} dB", name, mse, psnr(mse));
+        }
+        let rgb_mse = (self.mse[0] + self.mse[1] + self.mse[2]) / 3.0;
+        let _ = writeln!(text, "  RGB: {:.6} / {:.2} dB", rgb_mse, psnr(rgb_mse));
+
+        let _ = writeln!(text, "\n前 {} 个差异像素:", self.diffs.len());
+        for d in &self.diffs {
+            let _ = writeln!(text, "  ({}, {}): {} -> {}", d.x, d.y, format_pixel(&d.a), format_pixel(&d.b));
+        }
+        text
+    }
+
+    /// 生成 CSV：每行一个差异像素
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("x,y,a_r,a_g,a_b,a_a,b_r,b_g,b_b,b_a\n");
+        for d in &self.diffs {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{},{}",
+                d.x, d.y, d.a[0], d.a[1], d.a[2], d.a[3], d.b[0], d.b[1], d.b[2], d.b[3]
+            );
+        }
+        csv
+    }
+}
+
+/// 差异报告面板
+pub struct DiffPanel {
+    /// 最多列出的差异像素数
+    pub max_diffs: usize,
+    /// 提取最低位时使用的通道
+    pub lsb_channels: [bool; 4],
+    report: Option<(DiffReport, String)>,
+    lsb_bytes: Vec<u8>,
+}
+
+impl Default for DiffPanel {
+    fn default() -> Self {
+        Self {
+            max_diffs: 100,
+            lsb_channels: [true, true, true, false],
+            report: None,
+            lsb_bytes: Vec::new(),
+        }
+    }
+}
+
+impl DiffPanel {
+    /// 清除上次的结果（输入图像变化时调用）
+    pub fn clear(&mut self) {
+        self.report = None;
+        self.lsb_bytes.clear();
+    }
+
+    /// images 为 (原图, 副本)，为 None 时提示需要两张图像
+    pub fn ui(&mut self, ui: &mut egui::Ui, images: Option<(&RgbaImage, &RgbaImage)>) {
+        let Some((a, b)) = images else {
+            ui.label("需要至少两张启用的图像：第一张作为原图，选中的图像（或第二张）作为比较对象");
+            return;
+        };
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("列出差异像素:");
+            ui.add(egui::DragValue::new(&mut self.max_diffs).range(0..=100000));
+            if ui.button("计算差异").clicked() {
+                let report = compare_images(a, b, self.max_diffs);
+                let text = report.to_text();
+                self.report = Some((report, text));
+                self.lsb_bytes = differing_lsbs(a, b, self.lsb_channels);
+            }
+        });
+
+        let Some((report, text)) = &self.report else {
+            return;
+        };
+
+        let mut text_view = text.as_str();
+        egui::ScrollArea::vertical()
+            .id_salt("diff_report_scroll")
+            .max_height(240.0)
+            .show(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut text_view)
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(f32::INFINITY),
+                );
+            });
+
+        ui.horizontal_wrapped(|ui| {
+            if ui.button("复制报告").clicked() {
+                ui.ctx().copy_text(text.clone());
+            }
+            if ui.button("导出文本").clicked() {
+                save_text(text, "diff_report.txt");
+            }
+            if ui.button("导出 CSV").clicked() {
+                save_text(&report.to_csv(), "diff_report.csv");
+            }
+        });
+
+        ui.separator();
+        ui.horizontal_wrapped(|ui| {
+            ui.label("差异处的最低位:");
+            let mut changed = false;
+            for (enabled, name) in self.lsb_channels.iter_mut().zip(CHANNEL_NAMES) {
+                changed |= ui.checkbox(enabled, name).changed();
+            }
+            if changed {
+                self.lsb_bytes = differing_lsbs(a, b, self.lsb_channels);
+            }
+            ui.label(format!("{} 字节", self.lsb_bytes.len()));
+            if ui.button("复制十六进制").clicked() {
+                let hex: String = self.lsb_bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                ui.ctx().copy_text(hex);
+            }
+            if ui.button("保存二进制").clicked() {
+                if let Some(path) = rfd::FileDialog::new().set_file_name("diff_lsb.bin").save_file() {
+                    if let Err(e) = std::fs::write(path, &self.lsb_bytes) {
+                        eprintln!("保存文件失败: {}", e);
+                    }
+                }
+            }
+        });
+        let preview: String = self
+            .lsb_bytes
+            .iter()
+            .take(64)
+            .map(|&b| {
+                let c = b as char;
+                if c.is_ascii_graphic() || c == ' ' { c } else { '.' }
+            })
+            .collect();
+        ui.monospace(preview);
+    }
+}
+
+fn save_text(text: &str, file_name: &str) {
+    if let Some(path) = rfd::FileDialog::new().set_file_name(file_name).save_file() {
+        if let Err(e) = std::fs::write(path, text) {
+            eprintln!("保存文件失败: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn identical_images_have_no_differences() {
+        let img = RgbaImage::from_fn(4, 3, |x, y| Rgba([x as u8, y as u8, 7, 255]));
+        let report = compare_images(&img, &img, 10);
+        assert_eq!(report.differing, 0);
+        assert_eq!(report.bbox, None);
+        assert!(report.diffs.is_empty());
+        assert_eq!(report.mse, [0.0; 4]);
+        assert_eq!(psnr(report.mse[0]), f64::INFINITY);
+    }
+
+    #[test]
+    fn counts_bits_channels_and_bbox() {
+        let a = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+        let mut b = a.clone();
+        b.put_pixel(1, 2, Rgba([101, 100, 100, 255]));
+        b.put_pixel(3, 1, Rgba([100, 100, 96, 255]));
+        let report = compare_images(&a, &b, 1);
+        assert_eq!((report.width, report.height), (4, 4));
+        assert_eq!(report.differing, 2);
+        assert_eq!(report.channel_counts, [1, 0, 1, 0]);
+        assert_eq!(report.bbox, Some((1, 1, 3, 2)));
+        // 101 ^ 100 = 0b1，100 ^ 96 = 0b100
+        assert_eq!(report.bit_counts[0], [1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(report.bit_counts[2], [0, 0, 1, 0, 0, 0, 0, 0]);
+        assert_eq!(report.mse, [1.0 / 16.0, 0.0, 16.0 / 16.0, 0.0]);
+        // 按行扫描顺序记录，且不超过上限
+        assert_eq!(report.diffs.len(), 1);
+        assert_eq!((report.diffs[0].x, report.diffs[0].y), (3, 1));
+    }
+
+    #[test]
+    fn compares_only_the_overlap() {
+        let a = RgbaImage::from_pixel(5, 2, Rgba([0, 0, 0, 255]));
+        let b = RgbaImage::from_pixel(3, 4, Rgba([0, 0, 0, 255]));
+        let report = compare_images(&a, &b, 10);
+        assert_eq!((report.width, report.height), (3, 2));
+        assert_eq!(report.differing, 0);
+    }
+
+    #[test]
+    fn psnr_of_known_mse() {
+        assert!((psnr(255.0 * 255.0) - 0.0).abs() < 1e-9);
+        assert!((psnr(1.0) - 48.1308).abs() < 1e-3);
+    }
+
+    #[test]
+    fn lsbs_of_differing_pixels() {
+        let a = RgbaImage::from_pixel(8, 1, Rgba([0, 0, 0, 0]));
+        let b = RgbaImage::from_fn(8, 1, |x, _| {
+            if x == 2 {
+                Rgba([0, 0, 0, 0])
+            } else {
+                Rgba([(x % 2) as u8, 1, 0, 0])
+            }
+        });
+        // x = 2 的像素相同被跳过，其余 7 个像素各取 R、G 两位：01 11 11 01 | 11 01 11，不足一字节的位丢弃
+        assert_eq!(differing_lsbs(&a, &b, [true, true, false, false]), [0b0111_1101]);
+        assert_eq!(differing_lsbs(&a, &b, [false, true, false, false]), Vec::<u8>::new());
+    }
+}
@@ -0,0 +1,379 @@
+use crate::extractanlysis::{pixel_to_u32, ExtractSettings};
+use crate::presets::PresetManager;
+use crate::stereo::save_rgba_image;
+use eframe::egui;
+use egui::{Align, Layout, ScrollArea, Ui};
+use image::{Rgba, RgbaImage};
+use std::path::PathBuf;
+
+// ──────────────────────────────
+// 数据嵌入：使用与数据提取完全相同的参数，将文件或文本写入图像的指定位平面
+
+#[derive(PartialEq)]
+pub enum EmbedInput {
+    Text,
+    File,
+}
+
+pub struct EmbedDialog {
+    /// 嵌入设置，与数据提取对话框的选项一一对应
+    pub settings: ExtractSettings,
+    /// 内置及用户保存的提取预设
+    pub presets: PresetManager,
+    /// 输入来源：文本 / 文件
+    pub input: EmbedInput,
+    /// 待嵌入的文本
+    pub text: String,
+    /// 待嵌入的文件路径
+    pub file_path: Option<PathBuf>,
+    /// 待嵌入的文件内容
+    file_data: Vec<u8>,
+    /// 嵌入后的图像
+    result: Option<RgbaImage>,
+    /// 状态提示
+    status: String,
+}
+
+impl Default for EmbedDialog {
+    fn default() -> Self {
+        Self::with_presets(PresetManager::default())
+    }
+}
+
+impl EmbedDialog {
+    /// 使用给定的预设管理器创建对话框
+    pub fn with_presets(presets: PresetManager) -> Self {
+        Self {
+            settings: ExtractSettings::default(),
+            presets,
+            input: EmbedInput::Text,
+            text: String::new(),
+            file_path: None,
+            file_data: Vec::new(),
+            result: None,
+            status: String::new(),
+        }
+    }
+
+    /// 在 egui 的 UI 内绘制对话框，image 为载体图像（原始图像）
+    /// 返回值：true 表示对话框应该关闭
+    pub fn ui(&mut self, ui: &mut Ui, image: &RgbaImage) -> bool {
+        let mut should_close = false;
+        ScrollArea::vertical().id_salt("embed_dialog_scroll").show(ui, |ui| {
+            // ── 待嵌入数据 ─────────────────────────────
+            ui.group(|ui| {
+                ui.label("待嵌入数据");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.input, EmbedInput::Text, "文本");
+                    ui.radio_value(&mut self.input, EmbedInput::File, "文件");
+                });
+                match self.input {
+                    EmbedInput::Text => {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut self.text)
+                                .desired_rows(4)
+                                .desired_width(f32::INFINITY),
+                        );
+                    }
+                    EmbedInput::File => {
+                        ui.horizontal(|ui| {
+                            if ui.button("选择文件").clicked() {
+                                if let Some(path) = rfd::FileDialog::new().pick_file() {
+                                    match std::fs::read(&path) {
+                                        Ok(data) => {
+                                            self.file_data = data;
+                                            self.file_path = Some(path);
+                                        }
+                                        Err(e) => self.status = format!("读取文件失败: {}", e),
+                                    }
+                                }
+                            }
+                            match &self.file_path {
+                                Some(path) => ui.label(format!(
+                                    "{} ({} 字节)",
+                                    path.display(),
+                                    self.file_data.len()
+                                )),
+                                None => ui.label("未选择文件"),
+                            };
+                        });
+                    }
+                }
+            });
+
+            ui.separator();
+
+            self.presets.ui(ui, "embed", &mut self.settings);
+
+            self.settings.options_ui(ui, "embed");
+
+            ui.separator();
+
+            // ── 容量信息 ─────────────────────────────
+            let capacity = self.capacity_bytes(image);
+            let needed = self.payload().len() + self.settings.length_header.size();
+            let fits = needed <= capacity;
+            ui.group(|ui| {
+                ui.label(format!(
+                    "图像尺寸: {}x{}，每像素 {} 位",
+                    image.width(),
+                    image.height(),
+                    self.settings.pixel_bit_masks().len()
+                ));
+                ui.label(format!("可用容量: {} 字节", capacity));
+                let text = format!("需要: {} 字节（含长度头）", needed);
+                if fits {
+                    ui.label(text);
+                } else {
+                    ui.colored_label(egui::Color32::RED, format!("{}，超出容量", text));
+                }
+                if !self.status.is_empty() {
+                    ui.label(&self.status);
+                }
+            });
+
+            ui.separator();
+
+            ui.allocate_space(egui::vec2(0.0, 10.0));
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                let button_size = egui::vec2(60.0, 24.0);
+
+                if ui.add_sized(button_size, egui::Button::new("关闭")).clicked() {
+                    should_close = true;
+                }
+                ui.add_space(5.0);
+
+                let can_save = self.result.is_some();
+                if ui
+                    .add_enabled(can_save, egui::Button::new("保存图像").min_size(button_size))
+                    .clicked()
+                {
+                    self.save_result();
+                }
+                ui.add_space(5.0);
+
+                if ui
+                    .add_enabled(fits, egui::Button::new("嵌入").min_size(button_size))
+                    .clicked()
+                {
+                    match self.embed(image) {
+                        Ok(img) => {
+                            self.result = Some(img);
+                            self.status = format!("已嵌入 {} 字节，请保存为 PNG 或 BMP", needed);
+                        }
+                        Err(e) => {
+                            self.result = None;
+                            self.status = e;
+                        }
+                    }
+                }
+            });
+            ui.allocate_space(egui::vec2(0.0, 10.0));
+        });
+        should_close
+    }
+
+    // 当前输入的原始数据
+    fn payload(&self) -> &[u8] {
+        match self.input {
+            EmbedInput::Text => self.text.as_bytes(),
+            EmbedInput::File => &self.file_data,
+        }
+    }
+
+    /// 在当前设置下图像可容纳的数据字节数（已扣除起始偏移，未扣除长度头）
+    pub fn capacity_bytes(&self, image: &RgbaImage) -> usize {
+        let bits = image.width() as usize
+            * image.height() as usize
+            * self.settings.pixel_bit_masks().len();
+        (bits / 8).saturating_sub(self.settings.offset)
+    }
+
+    /// 按提取顺序将数据写入图像，超出容量时拒绝
+    pub fn embed(&self, image: &RgbaImage) -> Result<RgbaImage, String> {
+        let bit_masks = self.settings.pixel_bit_masks();
+        if bit_masks.is_empty() {
+            return Err("请至少选择一个位平面".to_string());
+        }
+        let payload = self.settings.wrap_payload(self.payload())?;
+        if payload.len() > self.capacity_bytes(image) {
+            return Err(format!(
+                "数据大小 {} 字节超出容量 {} 字节",
+                payload.len(),
+                self.capacity_bytes(image)
+            ));
+        }
+
+        let skip_bits = self.settings.offset * 8;
+        let total_bits = skip_bits + payload.len() * 8;
+        let mut result = image.clone();
+        let mut bit_index = 0usize;
+
+        'outer: for (x, y) in self.settings.pixel_coords(image.width(), image.height()) {
+            let mut rgba = pixel_to_u32(result.get_pixel(x, y));
+            for &bit in &bit_masks {
+                if bit_index >= total_bits {
+                    result.put_pixel(x, y, Rgba(rgba.to_be_bytes()));
+                    break 'outer;
+                }
+                if bit_index >= skip_bits {
+                    // 与提取一致，字节内按 MSB 优先写入
+                    let data_bit = bit_index - skip_bits;
+                    let byte = payload[data_bit / 8];
+                    if byte & (0x80 >> (data_bit % 8)) != 0 {
+                        rgba |= bit;
+                    } else {
+                        rgba &= !bit;
+                    }
+                }
+                bit_index += 1;
+            }
+            result.put_pixel(x, y, Rgba(rgba.to_be_bytes()));
+        }
+        Ok(result)
+    }
+
+    // 保存嵌入结果，只允许无损格式
+    fn save_result(&mut self) {
+        let Some(img) = &self.result else {
+            return;
+        };
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("PNG", &["png"])
+            .add_filter("BMP", &["bmp"])
+            .set_file_name("embedded.png")
+            .save_file()
+        {
+            let ext = path
+                .extension()
+                .and_then(|s| s.to_str())
+                .map(|s| s.to_lowercase());
+            match ext.as_deref() {
+                Some("png") | Some("bmp") => {
+                    save_rgba_image(img, path);
+                    self.status = "已保存".to_string();
+                }
+                _ => self.status = "只能保存为 PNG 或 BMP 等无损格式".to_string(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extractanlysis::{BitOrder, ExtractDialog, ExtractDirection, LengthHeader, RgbOrder};
+
+    fn carrier() -> RgbaImage {
+        RgbaImage::from_fn(23, 17, |x, y| {
+            let v = x * 31 + y * 97;
+            Rgba([v as u8, (v >> 3) as u8, (v * 7) as u8, 255 - (v % 5) as u8])
+        })
+    }
+
+    fn select(settings: &mut ExtractSettings, channel: usize, bits: &[usize]) {
+        for &bit in bits {
+            // 数组索引 0 对应通道的第 7 位
+            settings.channel_selections[channel].bits[7 - bit] = true;
+        }
+    }
+
+    // 按给定设置嵌入文本，再用提取对话框取回
+    fn round_trip(settings: ExtractSettings, text: &str) -> (Vec<u8>, RgbaImage) {
+        let dialog = EmbedDialog {
+            settings: settings.clone(),
+            text: text.to_string(),
+            ..EmbedDialog::with_presets(PresetManager::new(None))
+        };
+        let embedded = dialog.embed(&carrier()).unwrap();
+        let mut extract = ExtractDialog::with_presets(PresetManager::new(None));
+        extract.settings = settings;
+        extract.generate_extract(&embedded);
+        (extract.extract_data, embedded)
+    }
+
+    #[test]
+    fn bit_masks_follow_stegsolve_order() {
+        let mut settings = ExtractSettings::default();
+        select(&mut settings, 0, &[0]);
+        select(&mut settings, 1, &[1, 0]);
+        select(&mut settings, 3, &[7]);
+        assert_eq!(settings.pixel_bit_masks(), [1 << 7, 1 << 24, 1 << 17, 1 << 16]);
+
+        settings.bit_order = BitOrder::LSBFirst;
+        assert_eq!(settings.pixel_bit_masks(), [1 << 7, 1 << 16, 1 << 17, 1 << 24]);
+
+        settings.bit_order = BitOrder::MSBFirst;
+        settings.rgb_order = RgbOrder::GRB;
+        assert_eq!(settings.pixel_bit_masks(), [1 << 7, 1 << 17, 1 << 16, 1 << 24]);
+    }
+
+    #[test]
+    fn payload_header_round_trip() {
+        let settings = ExtractSettings {
+            length_header: LengthHeader::U32,
+            offset: 3,
+            ..ExtractSettings::default()
+        };
+        let wrapped = settings.wrap_payload(b"abc").unwrap();
+        assert_eq!(wrapped, [0, 0, 0, 3, b'a', b'b', b'c']);
+        let mut extracted = vec![0xee; 3];
+        extracted.extend_from_slice(&wrapped);
+        extracted.extend_from_slice(b"trailing");
+        assert_eq!(settings.unwrap_payload(&extracted), b"abc");
+        // 长度头超出数据时截取到末尾
+        assert_eq!(settings.unwrap_payload(&[0, 0, 0, 0, 0, 0, 9, b'x']), b"x");
+
+        let u16_header = ExtractSettings {
+            length_header: LengthHeader::U16,
+            ..ExtractSettings::default()
+        };
+        assert!(u16_header.wrap_payload(&vec![0; 70000]).is_err());
+    }
+
+    #[test]
+    fn embed_then_extract_recovers_payload() {
+        let text = "隐写 round trip: 0123456789";
+        let orders = [RgbOrder::RGB, RgbOrder::RBG, RgbOrder::GRB, RgbOrder::GBR, RgbOrder::BRG, RgbOrder::BGR];
+        for (i, rgb_order) in orders.into_iter().enumerate() {
+            let mut settings = ExtractSettings {
+                rgb_order,
+                bit_order: if i % 2 == 0 { BitOrder::MSBFirst } else { BitOrder::LSBFirst },
+                extract_direction: if i % 3 == 0 { ExtractDirection::Row } else { ExtractDirection::Column },
+                offset: i,
+                length_header: LengthHeader::U16,
+                ..ExtractSettings::default()
+            };
+            select(&mut settings, 0, &[0, 1]);
+            select(&mut settings, 2, &[0]);
+            select(&mut settings, 3, &[0]);
+            let (extracted, _) = round_trip(settings, text);
+            assert_eq!(extracted, text.as_bytes(), "设置 {}", i);
+        }
+    }
+
+    #[test]
+    fn embed_only_touches_selected_bits() {
+        let mut settings = ExtractSettings::default();
+        select(&mut settings, 1, &[0]);
+        let (extracted, embedded) = round_trip(settings, "hi");
+        assert_eq!(&extracted[..2], b"hi");
+        for (a, b) in carrier().pixels().zip(embedded.pixels()) {
+            assert_eq!(pixel_to_u32(a) & !(1 << 16), pixel_to_u32(b) & !(1 << 16));
+        }
+    }
+
+    #[test]
+    fn embed_rejects_oversized_payload() {
+        let mut settings = ExtractSettings::default();
+        select(&mut settings, 0, &[0]);
+        let dialog = EmbedDialog {
+            settings,
+            text: "x".repeat(100),
+            ..EmbedDialog::with_presets(PresetManager::new(None))
+        };
+        // 23x17 像素、每像素 1 位只能容纳 48 字节
+        assert_eq!(dialog.capacity_bytes(&carrier()), 48);
+        assert!(dialog.embed(&carrier()).is_err());
+    }
+}
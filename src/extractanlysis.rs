@@ -1,6 +1,12 @@
+use crate::bitview::{bit_rows, bits_to_image, bits_to_image_rows, bits_to_string, suggest_widths};
+use crate::datatransform::{apply_chain, detect_signature, ByteTransform, StepResult};
+use crate::presets::PresetManager;
+use crate::selection::Selection;
+use crate::stereo::save_rgba_image;
 use eframe::egui;
 use egui::{Align, Layout, ScrollArea, Ui};
 use image::RgbaImage;
+use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::Write;
 
@@ -10,19 +16,61 @@ use std::io::Write;
 // ──────────────────────────────
 // 定义提取选项的枚举
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ExtractDirection {
     Row,
     Column,
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum BitOrder {
     MSBFirst,
     LSBFirst,
 }
 
-#[derive(PartialEq)]
+/// 数据前的长度头（大端字节序）
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum LengthHeader {
+    #[default]
+    None,
+    U16,
+    U32,
+    U64,
+}
+
+impl LengthHeader {
+    /// 长度头所占字节数
+    pub fn size(&self) -> usize {
+        match self {
+            LengthHeader::None => 0,
+            LengthHeader::U16 => 2,
+            LengthHeader::U32 => 4,
+            LengthHeader::U64 => 8,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LengthHeader::None => "无",
+            LengthHeader::U16 => "16位长度 (大端)",
+            LengthHeader::U32 => "32位长度 (大端)",
+            LengthHeader::U64 => "64位长度 (大端)",
+        }
+    }
+}
+
+/// 提取数据的来源图像
+#[derive(Clone, Copy, PartialEq)]
+pub enum FrameSource {
+    /// 主窗口当前显示的图像
+    Current,
+    /// 动画中的指定帧
+    Frame,
+    /// 按帧顺序拼接所有帧
+    AllFrames,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum RgbOrder {
     RGB,
     RBG,
@@ -34,25 +82,25 @@ pub enum RgbOrder {
 
 // ──────────────────────────────
 // 每个通道的位选择状态，数组顺序约定：索引0对应通道最高位（7），索引7对应最低位（0）
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ChannelSelection {
-    pub name: &'static str,
+    pub name: String,
     pub bits: [bool; 8],
 }
 
 impl ChannelSelection {
-    pub fn new(name: &'static str) -> Self {
+    pub fn new(name: &str) -> Self {
         Self {
-            name,
+            name: name.to_string(),
             bits: [false; 8],
         }
     }
 }
 
 // ──────────────────────────────
-// ExtractDialog 保存了所有的 UI 状态和提取数据
-pub struct ExtractDialog {
-    /// 是否显示此对话框，调用者可根据该值决定是否移除此对话框
-    pub open: bool,
+// 可保存的提取设置（位平面、提取选项和变换链）
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ExtractSettings {
     /// 通道选择（Red, Green, Blue, Alpha）
     pub channel_selections: Vec<ChannelSelection>,
     /// 提取方向：按行 / 按列
@@ -61,18 +109,20 @@ pub struct ExtractDialog {
     pub bit_order: BitOrder,
     /// RGB 通道的顺序
     pub rgb_order: RgbOrder,
-    /// 预览中是否包含十六进制转储
-    pub preview_hex_dump: bool,
-    /// 预览文本（只读）
-    pub preview_text: String,
-    /// 提取后的二进制数据
-    pub extract_data: Vec<u8>,
+    /// 数据起始偏移（字节），提取时跳过、嵌入时保留
+    #[serde(default)]
+    pub offset: usize,
+    /// 数据前的长度头（大端）
+    #[serde(default)]
+    pub length_header: LengthHeader,
+    /// 对提取数据依次执行的变换链
+    #[serde(default)]
+    pub transform_chain: Vec<ByteTransform>,
 }
 
-impl Default for ExtractDialog {
+impl Default for ExtractSettings {
     fn default() -> Self {
         Self {
-            open: true,
             channel_selections: vec![
                 ChannelSelection::new("Red"),
                 ChannelSelection::new("Green"),
@@ -82,20 +132,299 @@ impl Default for ExtractDialog {
             extract_direction: ExtractDirection::Row,
             bit_order: BitOrder::MSBFirst,
             rgb_order: RgbOrder::RGB,
+            offset: 0,
+            length_header: LengthHeader::None,
+            transform_chain: Vec::new(),
+        }
+    }
+}
+
+impl ExtractSettings {
+    /// 绘制位平面选择和提取选项（提取与嵌入对话框共用），id 用于区分不同窗口中的控件
+    pub fn options_ui(&mut self, ui: &mut Ui, id: &str) {
+        // ── 选项区域：左侧为位平面选择，右侧为提取选项 ─────────────────────────────
+        ui.horizontal(|ui| {
+            // 位平面选择·
+            ui.group(|ui| {
+                ui.vertical(|ui| {
+                    ui.label("位平面选择");
+                    ui.add_space(4.0); // 标题和选项之间添加间距
+
+                    egui::Grid::new((id, "channel_selection_grid"))
+                        .spacing([8.0, 4.0]) // 设置水平和垂直间距
+                        .show(ui, |ui| {
+                            for channel in self.channel_selections.iter_mut() {
+                                // 通道名称（固定宽度）
+                                ui.add_sized([30.0, 20.0], egui::Label::new(channel.name.as_str()));
+
+                                // "全选"复选框（固定宽度）
+                                let all_selected = channel.bits.iter().all(|&b| b);
+                                let mut all_sel = all_selected;
+                                if ui
+                                    .add_sized(
+                                        [40.0, 20.0],
+                                        egui::Checkbox::new(&mut all_sel, "全选"),
+                                    )
+                                    .changed()
+                                {
+                                    for b in channel.bits.iter_mut() {
+                                        *b = all_sel;
+                                    }
+                                }
+
+                                // 显示位复选框（从高位到低位，固定宽度）
+                                for (i, bit) in channel.bits.iter_mut().enumerate() {
+                                    ui.add_sized(
+                                        [24.0, 20.0],
+                                        egui::Checkbox::new(bit, (7 - i).to_string()),
+                                    );
+                                }
+
+                                ui.end_row(); // 结束当前行，开始新行
+                            }
+                        });
+                });
+            });
+
+            // 提取选项
+            ui.group(|ui| {
+                ui.vertical(|ui| {
+                    ui.label("提取选项");
+                    ui.add_space(4.0); // 只保留一个小间距
+
+                    egui::Grid::new((id, "extract_options_grid"))
+                        .spacing([8.0, 2.0]) // 减小垂直间距
+                        .show(ui, |ui| {
+                            // 提取方向：按行 / 按列
+                            ui.label("提取方向:");
+                            ui.radio_value(
+                                &mut self.extract_direction,
+                                ExtractDirection::Row,
+                                "按行",
+                            );
+                            ui.radio_value(
+                                &mut self.extract_direction,
+                                ExtractDirection::Column,
+                                "按列",
+                            );
+                            ui.end_row();
+
+                            // 位顺序：MSB优先 / LSB优先
+                            ui.label("位顺序:");
+                            ui.radio_value(&mut self.bit_order, BitOrder::MSBFirst, "MSB优先");
+                            ui.radio_value(&mut self.bit_order, BitOrder::LSBFirst, "LSB优先");
+                            ui.end_row();
+
+                            // RGB 顺序（分成两行显示）
+                            ui.label("RGB顺序:");
+                            egui::Grid::new((id, "rgb_order_grid")).show(ui, |ui| {
+                                ui.radio_value(&mut self.rgb_order, RgbOrder::RGB, "RGB");
+                                ui.radio_value(&mut self.rgb_order, RgbOrder::RBG, "RBG");
+                                ui.radio_value(&mut self.rgb_order, RgbOrder::GRB, "GRB");
+                                ui.end_row();
+                                ui.radio_value(&mut self.rgb_order, RgbOrder::GBR, "GBR");
+                                ui.radio_value(&mut self.rgb_order, RgbOrder::BRG, "BRG");
+                                ui.radio_value(&mut self.rgb_order, RgbOrder::BGR, "BGR");
+                            });
+                            ui.end_row();
+
+                            // 起始偏移与长度头
+                            ui.label("偏移(字节):");
+                            ui.add(egui::DragValue::new(&mut self.offset));
+                            ui.end_row();
+
+                            ui.label("长度头:");
+                            egui::ComboBox::from_id_salt((id, "length_header"))
+                                .selected_text(self.length_header.name())
+                                .show_ui(ui, |ui| {
+                                    for header in [LengthHeader::None, LengthHeader::U16, LengthHeader::U32, LengthHeader::U64] {
+                                        ui.selectable_value(&mut self.length_header, header, header.name());
+                                    }
+                                });
+                            ui.end_row();
+                        });
+                });
+            });
+        });
+    }
+
+    /// 每个像素内按提取顺序排列的单比特掩码（像素按 R<<24 | G<<16 | B<<8 | A 打包）
+    /// 顺序与原版 StegSolve 一致：先 Alpha，再按 RGB 顺序；LSB 优先时通道内从低位到高位，且 RGB 顺序反向
+    pub fn pixel_bit_masks(&self) -> Vec<u32> {
+        let lsb_first = self.bit_order == BitOrder::LSBFirst;
+        // 各通道在打包值中的最低位位置
+        let (r, g, b, a) = (24, 16, 8, 0);
+        let channels = if lsb_first {
+            match self.rgb_order {
+                RgbOrder::RGB => [a, b, g, r],
+                RgbOrder::RBG => [a, b, r, g],
+                RgbOrder::GRB => [a, g, b, r],
+                RgbOrder::GBR => [a, g, r, b],
+                RgbOrder::BRG => [a, r, b, g],
+                RgbOrder::BGR => [a, r, g, b],
+            }
+        } else {
+            match self.rgb_order {
+                RgbOrder::RGB => [a, r, g, b],
+                RgbOrder::RBG => [a, r, b, g],
+                RgbOrder::GRB => [a, g, r, b],
+                RgbOrder::GBR => [a, g, b, r],
+                RgbOrder::BRG => [a, b, r, g],
+                RgbOrder::BGR => [a, b, g, r],
+            }
+        };
+
+        let selected = self.get_mask();
+        let mut masks = Vec::new();
+        for shift in channels {
+            for i in 0..8 {
+                let bit = if lsb_first { i } else { 7 - i };
+                let m = 1u32 << (shift + bit);
+                if selected & m != 0 {
+                    masks.push(m);
+                }
+            }
+        }
+        masks
+    }
+
+    // 根据通道选择生成掩码
+    fn get_mask(&self) -> u32 {
+        let mut mask = 0u32;
+        // 按通道顺序（Red, Green, Blue, Alpha），每个通道内按顺序（数组索引0对应位7）
+        for (channel_index, channel) in self.channel_selections.iter().enumerate() {
+            for (bit_index, &selected) in channel.bits.iter().enumerate() {
+                let flat_index = channel_index * 8 + bit_index; // 0..32
+                if selected {
+                    mask |= 1 << (31 - flat_index);
+                }
+            }
+        }
+        mask
+    }
+
+    /// 按提取方向遍历图像像素坐标
+    pub fn pixel_coords(&self, width: u32, height: u32) -> impl Iterator<Item = (u32, u32)> {
+        let row_first = self.extract_direction == ExtractDirection::Row;
+        let (outer, inner) = if row_first { (height, width) } else { (width, height) };
+        (0..outer).flat_map(move |o| {
+            (0..inner).map(move |i| if row_first { (i, o) } else { (o, i) })
+        })
+    }
+
+    /// 去掉提取数据的起始偏移，并根据长度头截取有效数据
+    pub fn unwrap_payload(&self, data: &[u8]) -> Vec<u8> {
+        let data = data.get(self.offset..).unwrap_or(&[]);
+        let size = self.length_header.size();
+        if size == 0 {
+            return data.to_vec();
+        }
+        if data.len() < size {
+            return Vec::new();
+        }
+        let length = data[..size]
+            .iter()
+            .fold(0u64, |acc, &b| (acc << 8) | b as u64);
+        let body = &data[size..];
+        body[..(length.min(body.len() as u64) as usize)].to_vec()
+    }
+
+    /// 为待嵌入的数据加上长度头（偏移由嵌入时跳过的位数体现）
+    pub fn wrap_payload(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let size = self.length_header.size();
+        if size == 2 && data.len() > u16::MAX as usize {
+            return Err("数据超过 16 位长度头的上限".to_string());
+        }
+        if size == 4 && data.len() > u32::MAX as usize {
+            return Err("数据超过 32 位长度头的上限".to_string());
+        }
+        let mut out = Vec::with_capacity(size + data.len());
+        out.extend_from_slice(&(data.len() as u64).to_be_bytes()[8 - size..]);
+        out.extend_from_slice(data);
+        Ok(out)
+    }
+}
+
+/// 像素打包为 u32：[r, g, b, a] 按大端顺序
+pub fn pixel_to_u32(pixel: &image::Rgba<u8>) -> u32 {
+    u32::from_be_bytes([pixel[0], pixel[1], pixel[2], pixel[3]])
+}
+
+// ──────────────────────────────
+// ExtractDialog 保存了所有的 UI 状态和提取数据
+pub struct ExtractDialog {
+    /// 是否显示此对话框，调用者可根据该值决定是否移除此对话框
+    pub open: bool,
+    /// 提取设置：位平面选择、提取选项和变换链
+    pub settings: ExtractSettings,
+    /// 内置及用户保存的提取预设
+    pub presets: PresetManager,
+    /// 预览中是否包含十六进制转储
+    pub preview_hex_dump: bool,
+    /// 预览文本（只读）
+    pub preview_text: String,
+    /// 提取后的二进制数据
+    pub extract_data: Vec<u8>,
+    /// 变换链每一步的输出
+    pub chain_results: Vec<StepResult>,
+    /// 位图视图的宽度（每行比特数）
+    pub bit_view_width: u32,
+    /// 位图视图是否反色（比特 1 显示为白色）
+    pub bit_view_invert: bool,
+    /// 位图视图的放大倍数
+    pub bit_view_scale: f32,
+    /// 位图视图显示的起始行（行数超过纹理尺寸限制时分页显示）
+    pub bit_view_row: u32,
+    /// 位串导出时每组的比特数（0 表示不分组）
+    pub bit_group: usize,
+    /// 位串导出时每行的组数（0 表示不换行）
+    pub bit_groups_per_line: usize,
+    /// 提取来源：当前图像 / 指定帧 / 所有帧
+    pub frame_source: FrameSource,
+    /// 提取来源为指定帧时的帧序号（从 0 开始）
+    pub frame_index: usize,
+    /// 只从该区域提取（由主视图的选区设置），为 None 时使用整张图像
+    pub region: Option<Selection>,
+    /// 位图视图的纹理缓存
+    bit_texture: Option<egui::TextureHandle>,
+}
+
+impl Default for ExtractDialog {
+    fn default() -> Self {
+        Self::with_presets(PresetManager::default())
+    }
+}
+
+impl ExtractDialog {
+    /// 使用给定的预设管理器创建对话框
+    pub fn with_presets(presets: PresetManager) -> Self {
+        Self {
+            open: true,
+            settings: ExtractSettings::default(),
+            presets,
             preview_hex_dump: true,
             preview_text: String::new(),
             extract_data: Vec::new(),
+            chain_results: Vec::new(),
+            bit_view_width: 64,
+            bit_view_invert: false,
+            bit_view_scale: 2.0,
+            bit_view_row: 0,
+            bit_group: 8,
+            bit_groups_per_line: 8,
+            frame_source: FrameSource::Current,
+            frame_index: 0,
+            region: None,
+            bit_texture: None,
         }
     }
-}
 
-impl ExtractDialog {
-    /// 在 egui 的 UI 内绘制对话框，image 为待提取数据的图像
+    /// 在 egui 的 UI 内绘制对话框，image 为待提取数据的图像，frames 为帧浏览器中解码出的所有帧
     /// 返回值：true 表示对话框应该关闭
-    pub fn ui(&mut self, ui: &mut Ui, image: &RgbaImage) -> bool {
+    pub fn ui(&mut self, ui: &mut Ui, image: &RgbaImage, frames: &[&RgbaImage]) -> bool {
         let mut should_close = false;
-        // 外层采用垂直布局
-        ui.vertical(|ui| {
+        // 外层采用可滚动的垂直布局
+        ScrollArea::vertical().id_salt("extract_dialog_scroll").show(ui, |ui| {
             // ── 预览设置 ─────────────────────────────
             ui.group(|ui| {
                 ui.label("预览设置");
@@ -103,99 +432,48 @@ impl ExtractDialog {
                     ui.label("在预览中包含十六进制转储");
                     ui.checkbox(&mut self.preview_hex_dump, "");
                 });
+                ui.horizontal(|ui| {
+                    ui.label("提取来源:");
+                    ui.radio_value(&mut self.frame_source, FrameSource::Current, "当前图像");
+                    ui.add_enabled_ui(!frames.is_empty(), |ui| {
+                        ui.radio_value(&mut self.frame_source, FrameSource::Frame, "指定帧");
+                        let mut frame_number = self.frame_index + 1;
+                        if ui
+                            .add(egui::DragValue::new(&mut frame_number).range(1..=frames.len().max(1)))
+                            .changed()
+                        {
+                            self.frame_index = frame_number - 1;
+                            self.frame_source = FrameSource::Frame;
+                        }
+                        ui.radio_value(&mut self.frame_source, FrameSource::AllFrames, "所有帧拼接");
+                    });
+                    ui.label(format!("(共 {} 帧)", frames.len()));
+                });
+                ui.horizontal(|ui| {
+                    match self.region {
+                        Some(region) => {
+                            ui.label(format!("提取区域: {}", region.text()));
+                            if ui.button("使用整张图像").clicked() {
+                                self.region = None;
+                            }
+                        }
+                        None => {
+                            ui.label("提取区域: 整张图像（可在主视图中 Shift+拖动框选后设置）");
+                        }
+                    }
+                });
             });
 
             ui.separator();
 
-            // ── 选项区域：左侧为位平面选择，右侧为提取选项 ─────────────────────────────
-            ui.horizontal(|ui| {
-                // 位平面选择·
-                ui.group(|ui| {
-                    ui.vertical(|ui| {
-                        ui.label("位平面选择");
-                        ui.add_space(4.0); // 标题和选项之间添加间距
-
-                        egui::Grid::new("channel_selection_grid")
-                            .spacing([8.0, 4.0]) // 设置水平和垂直间距
-                            .show(ui, |ui| {
-                                for channel in self.channel_selections.iter_mut() {
-                                    // 通道名称（固定宽度）
-                                    ui.add_sized([30.0, 20.0], egui::Label::new(channel.name));
-
-                                    // "全选"复选框（固定宽度）
-                                    let all_selected = channel.bits.iter().all(|&b| b);
-                                    let mut all_sel = all_selected;
-                                    if ui
-                                        .add_sized(
-                                            [40.0, 20.0],
-                                            egui::Checkbox::new(&mut all_sel, "全选"),
-                                        )
-                                        .changed()
-                                    {
-                                        for b in channel.bits.iter_mut() {
-                                            *b = all_sel;
-                                        }
-                                    }
-
-                                    // 显示位复选框（从高位到低位，固定宽度）
-                                    for (i, bit) in channel.bits.iter_mut().enumerate() {
-                                        ui.add_sized(
-                                            [24.0, 20.0],
-                                            egui::Checkbox::new(bit, (7 - i).to_string()),
-                                        );
-                                    }
-
-                                    ui.end_row(); // 结束当前行，开始新行
-                                }
-                            });
-                        });
-                });
-
-                // 提取选项
-                ui.group(|ui| {
-                    ui.vertical(|ui| {
-                        ui.label("提取选项");
-                        ui.add_space(4.0); // 只保留一个小间距
-
-                        egui::Grid::new("extract_options_grid")
-                            .spacing([8.0, 2.0]) // 减小垂直间距
-                            .show(ui, |ui| {
-                                // 提取方向：按行 / 按列
-                                ui.label("提取方向:");
-                                ui.radio_value(
-                                    &mut self.extract_direction,
-                                    ExtractDirection::Row,
-                                    "按行",
-                                );
-                                ui.radio_value(
-                                    &mut self.extract_direction,
-                                    ExtractDirection::Column,
-                                    "按列",
-                                );
-                                ui.end_row();
+            if self.presets.ui(ui, "extract", &mut self.settings) {
+                self.run_chain();
+                if !self.extract_data.is_empty() {
+                    self.generate_preview();
+                }
+            }
 
-                                // 位顺序：MSB优先 / LSB优先
-                                ui.label("位顺序:");
-                                ui.radio_value(&mut self.bit_order, BitOrder::MSBFirst, "MSB优先");
-                                ui.radio_value(&mut self.bit_order, BitOrder::LSBFirst, "LSB优先");
-                                ui.end_row();
-
-                                // RGB 顺序（分成两行显示）
-                                ui.label("RGB顺序:");
-                                egui::Grid::new("rgb_order_grid").show(ui, |ui| {
-                                    ui.radio_value(&mut self.rgb_order, RgbOrder::RGB, "RGB");
-                                    ui.radio_value(&mut self.rgb_order, RgbOrder::RBG, "RBG");
-                                    ui.radio_value(&mut self.rgb_order, RgbOrder::GRB, "GRB");
-                                    ui.end_row();
-                                    ui.radio_value(&mut self.rgb_order, RgbOrder::GBR, "GBR");
-                                    ui.radio_value(&mut self.rgb_order, RgbOrder::BRG, "BRG");
-                                    ui.radio_value(&mut self.rgb_order, RgbOrder::BGR, "BGR");
-                                });
-                                ui.end_row();
-                            });
-                    });
-                });
-            });
+            self.settings.options_ui(ui, "extract");
 
             ui.separator();
 
@@ -217,6 +495,121 @@ impl ExtractDialog {
 
             ui.separator();
 
+            // ── 变换链 ─────────────────────────────
+            ui.group(|ui| {
+                let mut chain_changed = false;
+                ui.horizontal(|ui| {
+                    ui.label("变换链");
+                    ui.menu_button("添加步骤", |ui| {
+                        for step in ByteTransform::all() {
+                            if ui.button(step.name()).clicked() {
+                                self.settings.transform_chain.push(step);
+                                chain_changed = true;
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    if ui.button("清空").clicked() {
+                        self.settings.transform_chain.clear();
+                        chain_changed = true;
+                    }
+                });
+
+                let mut move_up = None;
+                let mut move_down = None;
+                let mut remove = None;
+                egui::Grid::new("transform_chain_grid")
+                    .spacing([8.0, 4.0])
+                    .show(ui, |ui| {
+                        // 第 0 行为原始提取结果
+                        ui.label("0.");
+                        ui.label("提取结果");
+                        ui.label("");
+                        ui.label("");
+                        ui.label(Self::describe_data(&self.extract_data));
+                        ui.end_row();
+
+                        let count = self.settings.transform_chain.len();
+                        for (i, step) in self.settings.transform_chain.iter_mut().enumerate() {
+                            ui.label(format!("{}.", i + 1));
+                            ui.label(step.name());
+
+                            // 步骤参数
+                            ui.horizontal(|ui| {
+                                if let ByteTransform::Xor { key, hex } = step {
+                                    if ui
+                                        .add(egui::TextEdit::singleline(key).desired_width(120.0))
+                                        .changed()
+                                    {
+                                        chain_changed = true;
+                                    }
+                                    if ui.checkbox(hex, "十六进制").changed() {
+                                        chain_changed = true;
+                                    }
+                                }
+                            });
+
+                            ui.horizontal(|ui| {
+                                if ui.add_enabled(i > 0, egui::Button::new("↑")).clicked() {
+                                    move_up = Some(i);
+                                }
+                                if ui.add_enabled(i + 1 < count, egui::Button::new("↓")).clicked() {
+                                    move_down = Some(i);
+                                }
+                                if ui.button("✕").clicked() {
+                                    remove = Some(i);
+                                }
+                            });
+
+                            // 该步骤的输出与文件签名
+                            match self.chain_results.get(i) {
+                                Some(result) => match &result.error {
+                                    Some(e) => {
+                                        ui.colored_label(egui::Color32::RED, e);
+                                    }
+                                    None => {
+                                        ui.label(Self::describe_data(&result.data));
+                                    }
+                                },
+                                None => {
+                                    ui.label("");
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                if let Some(i) = move_up {
+                    self.settings.transform_chain.swap(i, i - 1);
+                    chain_changed = true;
+                }
+                if let Some(i) = move_down {
+                    self.settings.transform_chain.swap(i, i + 1);
+                    chain_changed = true;
+                }
+                if let Some(i) = remove {
+                    self.settings.transform_chain.remove(i);
+                    chain_changed = true;
+                }
+                if chain_changed {
+                    self.run_chain();
+                    if !self.extract_data.is_empty() {
+                        self.generate_preview();
+                    }
+                }
+            });
+
+            ui.separator();
+
+            // ── 位图视图与位串导出 ─────────────────────────────
+            egui::CollapsingHeader::new("位图视图 / 位串导出")
+                .default_open(false)
+                .show(ui, |ui| {
+                    self.bit_view_ui(ui);
+                });
+
+            ui.separator();
+
             // ── 按钮区域 ─────────────────────────────
             ui.allocate_space(egui::vec2(0.0, 10.0)); // 添加固定间距
             
@@ -231,20 +624,20 @@ impl ExtractDialog {
                 ui.add_space(5.0);
                 
                 if ui.add_sized(button_size, egui::Button::new("保存二进制")).clicked() {
-                    self.generate_extract(image);
+                    self.generate_extract_from(image, frames);
                     self.save_binary();
                 }
                 ui.add_space(5.0);
                 
                 if ui.add_sized(button_size, egui::Button::new("保存文本")).clicked() {
-                    self.generate_extract(image);
+                    self.generate_extract_from(image, frames);
                     self.generate_preview();
                     self.save_preview();
                 }
                 ui.add_space(5.0);
                 
                 if ui.add_sized(button_size, egui::Button::new("预览")).clicked() {
-                    self.generate_extract(image);
+                    self.generate_extract_from(image, frames);
                     self.generate_preview();
                 }
                 ui.add_space(5.0);
@@ -252,6 +645,16 @@ impl ExtractDialog {
                 if ui.add_sized(button_size, egui::Button::new("关闭")).clicked() {
                     should_close = true;
                 }
+                ui.add_space(5.0);
+
+                if ui.add_sized(button_size, egui::Button::new("加载设置")).clicked() {
+                    self.load_settings();
+                }
+                ui.add_space(5.0);
+
+                if ui.add_sized(button_size, egui::Button::new("保存设置")).clicked() {
+                    self.save_settings();
+                }
             });
             
             ui.allocate_space(egui::vec2(0.0, 10.0)); // 底部添加固定间距
@@ -260,39 +663,6 @@ impl ExtractDialog {
     }
 
     // ─────────────────────────────
-    // 内部方法：根据通道选择生成掩码，返回 (mask, maskbits)
-    fn get_mask(&self) -> (u32, u32) {
-        let mut mask = 0u32;
-        let mut maskbits = 0u32;
-        // 按通道顺序（Red, Green, Blue, Alpha），每个通道内按顺序（数组索引0对应位7）
-        for (channel_index, channel) in self.channel_selections.iter().enumerate() {
-            for (bit_index, &selected) in channel.bits.iter().enumerate() {
-                let flat_index = channel_index * 8 + bit_index; // 0..32
-                if selected {
-                    let shift = 31 - flat_index;
-                    mask |= 1 << shift;
-                    maskbits += 1;
-                }
-            }
-        }
-        (mask, maskbits)
-    }
-
-    // 获取提取顺序选项：返回 (row_first, lsb_first, rgb_order)
-    fn get_bit_order_options(&self) -> (bool, bool, u8) {
-        let row_first = self.extract_direction == ExtractDirection::Row;
-        let lsb_first = self.bit_order == BitOrder::LSBFirst;
-        let rgb_order = match self.rgb_order {
-            RgbOrder::RGB => 1,
-            RgbOrder::RBG => 2,
-            RgbOrder::GRB => 3,
-            RgbOrder::GBR => 4,
-            RgbOrder::BRG => 5,
-            RgbOrder::BGR => 6,
-        };
-        (row_first, lsb_first, rgb_order)
-    }
-
     // 向提取缓冲区添加一个位
     fn add_bit(extract: &mut Vec<u8>, bit_pos: &mut u8, byte_pos: &mut usize, num: u8) {
         if num != 0 {
@@ -311,141 +681,213 @@ impl ExtractDialog {
         }
     }
 
-    // 提取 8 位
-    fn extract_8bits(
-        extract: &mut Vec<u8>,
-        bit_pos: &mut u8,
-        byte_pos: &mut usize,
-        next_byte: u32,
-        mut current_mask: u32,
-        mask: u32,
-        lsb_first: bool,
-    ) {
-        for _ in 0..8 {
-            if mask & current_mask != 0 {
-                let bit_val = if next_byte & current_mask != 0 { 1 } else { 0 };
-                Self::add_bit(extract, bit_pos, byte_pos, bit_val);
-            }
-            if lsb_first {
-                current_mask <<= 1;
-            } else {
-                current_mask >>= 1;
-            }
+    /// 根据当前设置和图像生成提取数据
+    pub fn generate_extract(&mut self, image: &RgbaImage) {
+        self.generate_extract_images(&[image]);
+    }
+
+    /// 按提取来源选择图像（当前图像 / 指定帧 / 所有帧）并生成提取数据，设置了区域时只提取区域内的像素
+    pub fn generate_extract_from(&mut self, image: &RgbaImage, frames: &[&RgbaImage]) {
+        if let Some(region) = self.region {
+            let image = region.crop(image);
+            let frames: Vec<RgbaImage> = frames.iter().map(|frame| region.crop(frame)).collect();
+            let frames: Vec<&RgbaImage> = frames.iter().collect();
+            self.extract_by_source(&image, &frames);
+        } else {
+            self.extract_by_source(image, frames);
         }
     }
 
-    // 根据当前选项，从单个像素中提取位
-    fn extract_bits(
-        extract: &mut Vec<u8>,
-        bit_pos: &mut u8,
-        byte_pos: &mut usize,
-        next_byte: u32,
-        mask: u32,
-        lsb_first: bool,
-        rgb_order: u8,
-    ) {
-        if lsb_first {
-            // LSB 优先：Alpha 通道（从最低位开始）
-            Self::extract_8bits(
-                extract,
-                bit_pos,
-                byte_pos,
-                next_byte,
-                1 << 0,
-                mask,
-                lsb_first,
-            );
-            // RGB 通道，按选定顺序
-            let channels = match rgb_order {
-                1 => [1 << 8, 1 << 16, 1 << 24], // RGB
-                2 => [1 << 8, 1 << 24, 1 << 16], // RBG
-                3 => [1 << 16, 1 << 8, 1 << 24], // GRB
-                4 => [1 << 16, 1 << 24, 1 << 8], // GBR
-                5 => [1 << 24, 1 << 8, 1 << 16], // BRG
-                _ => [1 << 24, 1 << 16, 1 << 8], // BGR
-            };
-            for &shift in channels.iter() {
-                Self::extract_8bits(
-                    extract, bit_pos, byte_pos, next_byte, shift, mask, lsb_first,
-                );
+    fn extract_by_source(&mut self, image: &RgbaImage, frames: &[&RgbaImage]) {
+        match self.frame_source {
+            FrameSource::Frame if !frames.is_empty() => {
+                let index = self.frame_index.min(frames.len() - 1);
+                self.generate_extract_images(&[frames[index]]);
             }
-        } else {
-            // MSB 优先：Alpha 通道（从最高位开始）
-            Self::extract_8bits(
-                extract,
-                bit_pos,
-                byte_pos,
-                next_byte,
-                1 << 7,
-                mask,
-                lsb_first,
-            );
-            let channels = match rgb_order {
-                1 => [1 << 31, 1 << 23, 1 << 15], // RGB
-                2 => [1 << 31, 1 << 15, 1 << 23], // RBG
-                3 => [1 << 23, 1 << 31, 1 << 15], // GRB
-                4 => [1 << 23, 1 << 15, 1 << 31], // GBR
-                5 => [1 << 15, 1 << 31, 1 << 23], // BRG
-                _ => [1 << 15, 1 << 23, 1 << 31], // BGR
-            };
-            for &shift in channels.iter() {
-                Self::extract_8bits(
-                    extract, bit_pos, byte_pos, next_byte, shift, mask, lsb_first,
-                );
+            FrameSource::AllFrames if !frames.is_empty() => {
+                self.generate_extract_images(frames);
             }
+            _ => self.generate_extract(image),
         }
     }
 
-    /// 根据当前设置和图像生成提取数据
-    pub fn generate_extract(&mut self, image: &RgbaImage) {
-        let (mask, maskbits) = self.get_mask();
-        let (row_first, lsb_first, rgb_order) = self.get_bit_order_options();
+    /// 依次从多张图像中提取数据，比特流在图像之间连续拼接
+    pub fn generate_extract_images(&mut self, images: &[&RgbaImage]) {
+        let bit_masks = self.settings.pixel_bit_masks();
 
-        let total_bits = (image.width() * image.height()) as u32 * maskbits;
-        let len = ((total_bits + 7) / 8) as usize;
-        self.extract_data = vec![0u8; len];
+        let total_pixels: usize = images
+            .iter()
+            .map(|image| image.width() as usize * image.height() as usize)
+            .sum();
+        let total_bits = total_pixels * bit_masks.len();
+        let mut extract = vec![0u8; total_bits.div_ceil(8)];
         let mut bit_pos = 128u8;
         let mut byte_pos = 0usize;
 
-        if row_first {
-            for y in 0..image.height() {
-                for x in 0..image.width() {
-                    let pixel = image.get_pixel(x, y);
-                    // 将 [r, g, b, a] 按大端顺序转换为 u32
-                    let rgba = u32::from_be_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
-                    Self::extract_bits(
-                        &mut self.extract_data,
-                        &mut bit_pos,
-                        &mut byte_pos,
-                        rgba,
-                        mask,
-                        lsb_first,
-                        rgb_order,
-                    );
+        for image in images {
+            for (x, y) in self.settings.pixel_coords(image.width(), image.height()) {
+                let rgba = pixel_to_u32(image.get_pixel(x, y));
+                for &bit in &bit_masks {
+                    let bit_val = if rgba & bit != 0 { 1 } else { 0 };
+                    Self::add_bit(&mut extract, &mut bit_pos, &mut byte_pos, bit_val);
                 }
             }
-        } else {
-            for x in 0..image.width() {
-                for y in 0..image.height() {
-                    let pixel = image.get_pixel(x, y);
-                    let rgba = u32::from_be_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
-                    Self::extract_bits(
-                        &mut self.extract_data,
-                        &mut bit_pos,
-                        &mut byte_pos,
-                        rgba,
-                        mask,
-                        lsb_first,
-                        rgb_order,
-                    );
+        }
+        self.extract_data = self.settings.unwrap_payload(&extract);
+        self.run_chain();
+    }
+
+    /// 对提取数据重新执行变换链
+    pub fn run_chain(&mut self) {
+        self.chain_results = apply_chain(&self.settings.transform_chain, &self.extract_data);
+        self.bit_texture = None;
+    }
+
+    // 绘制位图视图：将输出数据按比特渲染为黑白图像，并支持导出 0/1 位串
+    fn bit_view_ui(&mut self, ui: &mut Ui) {
+        let bit_count = self.output_data().len() * 8;
+        if bit_count == 0 {
+            ui.label("请先点击预览以提取数据");
+            return;
+        }
+
+        // 宽度和每页行数都受纹理尺寸限制
+        let max_side = ui.ctx().input(|i| i.max_texture_side) as u32;
+        let max_width = (bit_count.min(4096) as u32).min(max_side);
+        let mut params_changed = false;
+        if self.bit_view_width > max_width {
+            self.bit_view_width = max_width;
+            params_changed = true;
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .add(egui::Slider::new(&mut self.bit_view_width, 1..=max_width).text("宽度"))
+                .changed()
+            {
+                params_changed = true;
+            }
+            if ui.checkbox(&mut self.bit_view_invert, "反色").changed() {
+                params_changed = true;
+            }
+            ui.add(egui::Slider::new(&mut self.bit_view_scale, 1.0..=8.0).text("放大"));
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label(format!("共 {} 位，推荐宽度:", bit_count));
+            for width in suggest_widths(bit_count, 12).into_iter().filter(|&w| w <= max_width) {
+                if ui.small_button(width.to_string()).clicked() {
+                    self.bit_view_width = width;
+                    params_changed = true;
+                }
+            }
+        });
+
+        if params_changed {
+            self.bit_texture = None;
+        }
+        // 行数超过纹理尺寸限制时只显示从起始行开始的一页
+        let max_rows = max_side;
+        let total_rows = bit_rows(bit_count, self.bit_view_width);
+        if total_rows > max_rows {
+            ui.horizontal(|ui| {
+                ui.label(format!("共 {} 行，每页显示 {} 行，起始行:", total_rows, max_rows));
+                if ui
+                    .add(egui::DragValue::new(&mut self.bit_view_row).range(0..=total_rows - 1))
+                    .changed()
+                {
+                    self.bit_texture = None;
+                }
+            });
+        } else if self.bit_view_row != 0 {
+            self.bit_view_row = 0;
+            self.bit_texture = None;
+        }
+        if self.bit_texture.is_none() {
+            let img = bits_to_image_rows(
+                self.output_data(),
+                self.bit_view_width,
+                self.bit_view_invert,
+                self.bit_view_row,
+                max_rows,
+            );
+            let color_img = egui::ColorImage::from_rgba_unmultiplied(
+                [img.width() as usize, img.height() as usize],
+                img.as_raw(),
+            );
+            self.bit_texture = Some(ui.ctx().load_texture(
+                "extract_bit_view",
+                color_img,
+                egui::TextureOptions::NEAREST,
+            ));
+        }
+
+        if let Some(texture) = &self.bit_texture {
+            ScrollArea::both()
+                .id_salt("bit_view_scroll")
+                .max_height(240.0)
+                .show(ui, |ui| {
+                    ui.image((texture.id(), texture.size_vec2() * self.bit_view_scale));
+                });
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("保存位图").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("图片", &["png", "bmp"])
+                    .set_file_name("bits.png")
+                    .save_file()
+                {
+                    let img = bits_to_image(self.output_data(), self.bit_view_width, self.bit_view_invert);
+                    save_rgba_image(&img, path);
+                }
+            }
+            ui.separator();
+            ui.label("每组位数:");
+            ui.add(egui::DragValue::new(&mut self.bit_group).range(0..=64));
+            ui.label("每行组数:");
+            ui.add(egui::DragValue::new(&mut self.bit_groups_per_line).range(0..=256));
+            if ui.button("复制位串").clicked() {
+                ui.ctx().copy_text(bits_to_string(
+                    self.output_data(),
+                    self.bit_group,
+                    self.bit_groups_per_line,
+                ));
+            }
+            if ui.button("导出位串").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .set_title("导出位串")
+                    .add_filter("文本文件", &["txt"])
+                    .set_file_name("bits.txt")
+                    .save_file()
+                {
+                    let text = bits_to_string(self.output_data(), self.bit_group, self.bit_groups_per_line);
+                    if let Err(e) = std::fs::write(path, text) {
+                        eprintln!("保存文件失败: {}", e);
+                    }
                 }
             }
+        });
+    }
+
+    /// 经过变换链处理后的最终数据
+    pub fn output_data(&self) -> &[u8] {
+        self.chain_results
+            .last()
+            .map(|r| r.data.as_slice())
+            .unwrap_or(&self.extract_data)
+    }
+
+    // 数据长度及文件签名的简要描述
+    fn describe_data(data: &[u8]) -> String {
+        match detect_signature(data) {
+            Some(signature) => format!("{} 字节, 签名: {}", data.len(), signature),
+            None => format!("{} 字节", data.len()),
         }
     }
 
     /// 生成预览文本，并更新内部的 preview_text 字段
     pub fn generate_preview(&mut self) {
-        let extract = &self.extract_data;
+        let extract = self.output_data();
         let mut preview = String::new();
         let hex_dump = self.preview_hex_dump;
         // 每 16 字节一行
@@ -497,10 +939,60 @@ impl ExtractDialog {
             .save_file()
         {
             if let Ok(mut file) = File::create(path) {
-                if let Err(e) = file.write_all(&self.extract_data) {
+                if let Err(e) = file.write_all(self.output_data()) {
                     eprintln!("保存文件失败: {}", e);
                 }
             }
         }
     }
+
+    /// 当前的提取设置
+    pub fn settings(&self) -> ExtractSettings {
+        self.settings.clone()
+    }
+
+    /// 应用提取设置，并对已有的提取数据重新执行变换链
+    pub fn apply_settings(&mut self, settings: ExtractSettings) {
+        self.settings = settings;
+        self.run_chain();
+        if !self.extract_data.is_empty() {
+            self.generate_preview();
+        }
+    }
+
+    /// 调用文件对话框将提取设置保存为 JSON 文件
+    pub fn save_settings(&self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .set_title("保存提取设置")
+            .add_filter("JSON", &["json"])
+            .set_file_name("extract_settings.json")
+            .save_file()
+        {
+            match serde_json::to_string_pretty(&self.settings()) {
+                Ok(json) => {
+                    if let Err(e) = std::fs::write(path, json) {
+                        eprintln!("保存文件失败: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("序列化设置失败: {}", e),
+            }
+        }
+    }
+
+    /// 调用文件对话框从 JSON 文件加载提取设置
+    pub fn load_settings(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .set_title("加载提取设置")
+            .add_filter("JSON", &["json"])
+            .pick_file()
+        {
+            match std::fs::read_to_string(path) {
+                Ok(json) => match serde_json::from_str::<ExtractSettings>(&json) {
+                    Ok(settings) => self.apply_settings(settings),
+                    Err(e) => eprintln!("解析设置失败: {}", e),
+                },
+                Err(e) => eprintln!("读取文件失败: {}", e),
+            }
+        }
+    }
 }
@@ -3,6 +3,8 @@ use eframe::egui::{Align, CentralPanel, Frame, Layout, ScrollArea, TopBottomPane
 use rfd::FileDialog;
 use std::fs::File;
 use std::io::Read;
+use crate::webp_decoder::{get_u24_le, is_webp, riff_chunks, RiffChunk};
+use crate::ico_decoder::{ico_entries, ico_header};
 pub struct FileAnalysis {
     report: Vec<String>,
     scroll_to_bottom: bool,
@@ -114,6 +116,14 @@ fn get_dword_be(data: &[u8], offset: usize) -> u32 {
     }
 }
 
+fn get_word_be(data: &[u8], offset: usize) -> u16 {
+    if offset + 1 >= data.len() {
+        0
+    } else {
+        u16::from_be_bytes([data[offset], data[offset + 1]])
+    }
+}
+
 // 十六进制转储
 fn hex_dump(data: &[u8], from: usize, to: usize, report: &mut Vec<String>) {
     if from >= data.len() {
@@ -555,6 +565,434 @@ fn analyse_jpg(data: &[u8], report: &mut Vec<String>) {
     }
 }
 
+// WebP 分析：遍历 RIFF 块
+fn analyse_webp(data: &[u8], report: &mut Vec<String>) {
+    if !is_webp(data) {
+        report.push("无效的 WebP 文件头".to_string());
+        return;
+    }
+
+    let riff_size = get_dword_le(data, 4) as usize;
+    report.push(format!("RIFF 大小: {} 字节", riff_size));
+    let riff_end = riff_size + 8;
+    if riff_end > data.len() {
+        report.push(format!("警告: RIFF 大小超出文件长度 {} 字节", riff_end - data.len()));
+    }
+
+    for chunk in riff_chunks(data, 12, riff_end) {
+        analyse_webp_chunk(data, &chunk, report, "");
+    }
+
+    if riff_end < data.len() {
+        report.push(format!("\nRIFF 结束后的附加字节数: {}", data.len() - riff_end));
+        hex_dump(data, riff_end, (riff_end + 127).min(data.len() - 1), report);
+    }
+}
+
+// 输出单个 WebP 块的信息，indent 用于 ANMF 内的子块
+fn analyse_webp_chunk(data: &[u8], chunk: &RiffChunk, report: &mut Vec<String>, indent: &str) {
+    let start = chunk.data_start();
+    report.push(format!("\n{}块类型: {}", indent, chunk.name));
+    report.push(format!("{}偏移: {}  数据长度: {} 字节", indent, chunk.offset, chunk.size));
+
+    match chunk.name.as_str() {
+        "VP8X" if chunk.size >= 10 => {
+            let flags = data[start];
+            let flag = |mask: u8| if flags & mask != 0 { "是" } else { "否" };
+            report.push(format!(
+                "{}扩展格式: ICC={} Alpha={} EXIF={} XMP={} 动画={}",
+                indent,
+                flag(0x20),
+                flag(0x10),
+                flag(0x08),
+                flag(0x04),
+                flag(0x02)
+            ));
+            report.push(format!(
+                "{}画布尺寸: {}x{}",
+                indent,
+                get_u24_le(data, start + 4) + 1,
+                get_u24_le(data, start + 7) + 1
+            ));
+        }
+        "VP8 " if chunk.size >= 10 => {
+            let frame_tag = get_u24_le(data, start);
+            let keyframe = frame_tag & 1 == 0;
+            report.push(format!("{}有损图像数据 (VP8)  关键帧: {}", indent, if keyframe { "是" } else { "否" }));
+            if keyframe && data[start + 3..start + 6] == [0x9D, 0x01, 0x2A] {
+                report.push(format!(
+                    "{}尺寸: {}x{}",
+                    indent,
+                    get_word_le(data, start + 6) & 0x3FFF,
+                    get_word_le(data, start + 8) & 0x3FFF
+                ));
+            }
+        }
+        "VP8L" if chunk.size >= 5 => {
+            if data[start] != 0x2F {
+                report.push(format!("{}无损图像数据 (VP8L)  签名错误: {:02X}", indent, data[start]));
+            } else {
+                let bits = get_dword_le(data, start + 1);
+                report.push(format!("{}无损图像数据 (VP8L)", indent));
+                report.push(format!(
+                    "{}尺寸: {}x{}  Alpha提示: {}  版本: {}",
+                    indent,
+                    (bits & 0x3FFF) + 1,
+                    ((bits >> 14) & 0x3FFF) + 1,
+                    (bits >> 28) & 1,
+                    bits >> 29
+                ));
+            }
+        }
+        "ALPH" if chunk.size >= 1 => {
+            let header = data[start];
+            let compression = match header & 0x03 {
+                0 => "无压缩",
+                1 => "无损压缩",
+                _ => "保留值",
+            };
+            let filter = match (header >> 2) & 0x03 {
+                0 => "无",
+                1 => "水平",
+                2 => "垂直",
+                _ => "梯度",
+            };
+            report.push(format!(
+                "{}Alpha 数据  压缩: {}  过滤: {}  预处理: {}",
+                indent,
+                compression,
+                filter,
+                (header >> 4) & 0x03
+            ));
+        }
+        "ANIM" if chunk.size >= 6 => {
+            report.push(format!(
+                "{}动画参数  背景色(BGRA): {:02X}{:02X}{:02X}{:02X}  循环次数: {} (0=无限循环)",
+                indent,
+                data[start],
+                data[start + 1],
+                data[start + 2],
+                data[start + 3],
+                get_word_le(data, start + 4)
+            ));
+        }
+        "ANMF" if chunk.size >= 16 => {
+            let flags = data[start + 15];
+            report.push(format!(
+                "{}动画帧  偏移: ({}, {})  尺寸: {}x{}  持续时间: {} 毫秒",
+                indent,
+                get_u24_le(data, start) * 2,
+                get_u24_le(data, start + 3) * 2,
+                get_u24_le(data, start + 6) + 1,
+                get_u24_le(data, start + 9) + 1,
+                get_u24_le(data, start + 12)
+            ));
+            report.push(format!(
+                "{}混合: {}  处置: {}",
+                indent,
+                if flags & 0x02 != 0 { "不混合" } else { "Alpha 混合" },
+                if flags & 0x01 != 0 { "清除为背景" } else { "保留" }
+            ));
+            let sub_indent = format!("{}    ", indent);
+            for sub in riff_chunks(data, start + 16, chunk.data_end()) {
+                analyse_webp_chunk(data, &sub, report, &sub_indent);
+            }
+        }
+        "ICCP" => report.push(format!("{}ICC 颜色配置文件", indent)),
+        "EXIF" => {
+            report.push(format!("{}EXIF 元数据", indent));
+            if chunk.size > 0 {
+                hex_dump(data, start, (start + chunk.size - 1).min(start + 127), report);
+            }
+        }
+        "XMP " => {
+            report.push(format!("{}XMP 元数据", indent));
+            let text = String::from_utf8_lossy(&data[start..chunk.data_end().min(start + 512)]);
+            report.extend(text.lines().map(|line| format!("{}{}", indent, line)));
+        }
+        _ => {
+            report.push(format!("{}未知或不完整的块", indent));
+            if chunk.size > 0 {
+                hex_dump(data, start, (start + chunk.size - 1).min(start + 127), report);
+                if chunk.size > 128 {
+                    report.push(format!("... (剩余 {} 字节已省略)", chunk.size - 128));
+                }
+            }
+        }
+    }
+}
+
+// TIFF 标签名称
+fn tiff_tag_name(tag: u16) -> &'static str {
+    match tag {
+        254 => "NewSubfileType",
+        255 => "SubfileType",
+        256 => "ImageWidth",
+        257 => "ImageLength",
+        258 => "BitsPerSample",
+        259 => "Compression",
+        262 => "PhotometricInterpretation",
+        266 => "FillOrder",
+        269 => "DocumentName",
+        270 => "ImageDescription",
+        271 => "Make",
+        272 => "Model",
+        273 => "StripOffsets",
+        274 => "Orientation",
+        277 => "SamplesPerPixel",
+        278 => "RowsPerStrip",
+        279 => "StripByteCounts",
+        282 => "XResolution",
+        283 => "YResolution",
+        284 => "PlanarConfiguration",
+        285 => "PageName",
+        296 => "ResolutionUnit",
+        297 => "PageNumber",
+        305 => "Software",
+        306 => "DateTime",
+        315 => "Artist",
+        317 => "Predictor",
+        320 => "ColorMap",
+        322 => "TileWidth",
+        323 => "TileLength",
+        324 => "TileOffsets",
+        325 => "TileByteCounts",
+        330 => "SubIFDs",
+        338 => "ExtraSamples",
+        339 => "SampleFormat",
+        513 => "JPEGInterchangeFormat",
+        514 => "JPEGInterchangeFormatLength",
+        700 => "XMP",
+        33432 => "Copyright",
+        34665 => "ExifIFD",
+        34675 => "ICCProfile",
+        34853 => "GPSIFD",
+        _ => "未知",
+    }
+}
+
+// TIFF 数据类型的单个值字节数
+fn tiff_type_size(field_type: u16) -> usize {
+    match field_type {
+        1 | 2 | 6 | 7 => 1,
+        3 | 8 => 2,
+        4 | 9 | 11 | 13 => 4,
+        5 | 10 | 12 => 8,
+        _ => 0,
+    }
+}
+
+// 分析 TIFF 文件：遍历所有 IFD（含 SubIFD / EXIF IFD），并找出没有被任何结构引用的数据
+fn analyse_tiff(data: &[u8], report: &mut Vec<String>) {
+    if data.len() < 8 {
+        report.push("文件太短，无法解析TIFF头".to_string());
+        return;
+    }
+    let le = &data[0..2] == b"II";
+    let word = |o: usize| if le { get_word_le(data, o) } else { get_word_be(data, o) };
+    let dword = |o: usize| if le { get_dword_le(data, o) } else { get_dword_be(data, o) };
+
+    report.push(format!("字节序: {}", if le { "小端 (II)" } else { "大端 (MM)" }));
+    let magic = word(2);
+    if magic != 42 {
+        report.push(format!("版本号: {} (仅支持标准 TIFF 42，BigTIFF 为 43)", magic));
+        return;
+    }
+
+    // 已引用的区域：(起始, 结束, 说明)
+    let mut used: Vec<(usize, usize, String)> = vec![(0, 8, "文件头".to_string())];
+    let mut queue: Vec<(usize, String)> = vec![(dword(4) as usize, "IFD".to_string())];
+    let mut visited = Vec::new();
+    let mut page = 0;
+
+    while let Some((ifd, kind)) = queue.pop() {
+        if ifd == 0 || visited.contains(&ifd) || visited.len() >= 256 {
+            continue;
+        }
+        visited.push(ifd);
+        if ifd + 2 > data.len() {
+            report.push(format!("\n警告: {} 偏移 {} 超出文件范围", kind, ifd));
+            continue;
+        }
+
+        let count = word(ifd) as usize;
+        let ifd_end = ifd + 2 + count * 12 + 4;
+        if kind == "IFD" {
+            page += 1;
+            report.push(format!("\n=== IFD #{} (页 {}) ===", visited.len(), page));
+        } else {
+            report.push(format!("\n=== {} ===", kind));
+        }
+        report.push(format!("偏移: {}  标签数: {}", ifd, count));
+        used.push((ifd, ifd_end.min(data.len()), kind.clone()));
+
+        // 数据块偏移与长度，成对记录
+        let mut offsets: Vec<(u16, Vec<u64>)> = Vec::new();
+        for i in 0..count {
+            let entry = ifd + 2 + i * 12;
+            if entry + 12 > data.len() {
+                report.push("警告: IFD 被截断".to_string());
+                break;
+            }
+            let tag = word(entry);
+            let field_type = word(entry + 2);
+            let value_count = dword(entry + 4) as usize;
+            let size = tiff_type_size(field_type).saturating_mul(value_count);
+            // 超过 4 字节的值存放在别处，字段中为偏移
+            let value_pos = if size > 4 { dword(entry + 8) as usize } else { entry + 8 };
+            if size > 4 {
+                used.push((value_pos, value_pos.saturating_add(size).min(data.len()), format!("标签 {} 的值", tag)));
+            }
+
+            let values: Vec<u64> = (0..value_count.min(4096))
+                .map(|j| match field_type {
+                    1 | 7 => data.get(value_pos + j).copied().unwrap_or(0) as u64,
+                    3 => word(value_pos + j * 2) as u64,
+                    4 | 13 => dword(value_pos + j * 4) as u64,
+                    _ => 0,
+                })
+                .collect();
+            let shown = match field_type {
+                2 => {
+                    let end = value_pos.saturating_add(value_count).min(data.len());
+                    let text = String::from_utf8_lossy(data.get(value_pos..end).unwrap_or(&[]));
+                    format!("\"{}\"", text.trim_end_matches('\0').chars().take(64).collect::<String>())
+                }
+                1 | 3 | 4 | 13 => {
+                    let list: Vec<String> = values.iter().take(8).map(|v| v.to_string()).collect();
+                    let more = if value_count > 8 { ", ..." } else { "" };
+                    format!("{}{}", list.join(", "), more)
+                }
+                _ if size > 4 => format!("偏移 {}", value_pos),
+                _ => format!("{:02X?}", &data[entry + 8..entry + 8 + size.min(4)]),
+            };
+            report.push(format!(
+                "标签 {} ({}) 类型 {} 数量 {}: {}",
+                tag,
+                tiff_tag_name(tag),
+                field_type,
+                value_count,
+                shown
+            ));
+
+            match tag {
+                273 | 279 | 324 | 325 | 513 | 514 => offsets.push((tag, values)),
+                330 => queue.extend(values.iter().map(|&v| (v as usize, "SubIFD".to_string()))),
+                34665 => queue.extend(values.iter().map(|&v| (v as usize, "EXIF IFD".to_string()))),
+                34853 => queue.extend(values.iter().map(|&v| (v as usize, "GPS IFD".to_string()))),
+                _ => {}
+            }
+        }
+
+        // 将偏移与长度配对为图像数据区域
+        for (offset_tag, length_tag, name) in [(273, 279, "条带"), (324, 325, "图块"), (513, 514, "JPEG 缩略图")] {
+            let find = |t: u16| offsets.iter().find(|(tag, _)| *tag == t).map(|(_, v)| v.clone());
+            if let (Some(starts), Some(lengths)) = (find(offset_tag), find(length_tag)) {
+                let total: u64 = lengths.iter().sum();
+                report.push(format!("{}: {} 个, 共 {} 字节", name, starts.len(), total));
+                for (start, length) in starts.iter().zip(&lengths) {
+                    let start = *start as usize;
+                    used.push((start, start.saturating_add(*length as usize).min(data.len()), name.to_string()));
+                }
+            }
+        }
+
+        if ifd_end <= data.len() && kind == "IFD" {
+            queue.push((dword(ifd_end - 4) as usize, "IFD".to_string()));
+        }
+    }
+
+    report.push(format!("\n共 {} 页", page));
+    report_unreferenced(data, used, report);
+}
+
+// 输出没有被任何已知结构引用的区域
+fn report_unreferenced(data: &[u8], mut used: Vec<(usize, usize, String)>, report: &mut Vec<String>) {
+    // 偏移超出文件末尾的引用不覆盖任何数据
+    used.retain(|(start, _, _)| *start < data.len());
+    used.sort_by_key(|(start, _, _)| *start);
+    let mut gaps = Vec::new();
+    let mut pos = 0;
+    for (start, end, _) in &used {
+        if *start > pos {
+            gaps.push((pos, *start));
+        }
+        pos = pos.max((*end).min(data.len()));
+    }
+    if pos < data.len() {
+        gaps.push((pos, data.len()));
+    }
+    // 单字节的间隔通常是字对齐填充
+    let gaps: Vec<(usize, usize)> = gaps.into_iter().filter(|(start, end)| end > start && end - start > 1).collect();
+
+    report.push("\n=== 未被引用的数据 ===".to_string());
+    if gaps.is_empty() {
+        report.push("无".to_string());
+        return;
+    }
+    let total: usize = gaps.iter().map(|(start, end)| end - start).sum();
+    report.push(format!("{} 处, 共 {} 字节", gaps.len(), total));
+    for (i, (start, end)) in gaps.iter().enumerate() {
+        report.push(format!("偏移 {} - {} ({} 字节)", start, end - 1, end - start));
+        if i < 10 {
+            hex_dump(data, *start, (*end - 1).min(start + 63), report);
+        }
+    }
+}
+
+// 分析 ICO / CUR 文件
+fn analyse_ico(data: &[u8], report: &mut Vec<String>) {
+    let Some((kind, count)) = ico_header(data) else {
+        report.push("无效的 ICO/CUR 文件头".to_string());
+        return;
+    };
+    report.push(format!("类型: {}  目录项数: {}", if kind == 2 { "光标 (CUR)" } else { "图标 (ICO)" }, count));
+
+    let entries = ico_entries(data);
+    if entries.len() < count as usize {
+        report.push(format!("警告: 目录被截断，只读取到 {} 项", entries.len()));
+    }
+    let mut used = vec![(0, 6 + entries.len() * 16, "文件头与目录".to_string())];
+
+    for (i, entry) in entries.iter().enumerate() {
+        report.push(format!("\n目录项 #{}", i + 1));
+        report.push(format!("尺寸: {}x{}  调色板颜色数: {}", entry.width, entry.height, entry.color_count));
+        if kind == 2 {
+            report.push(format!("热点: ({}, {})", entry.planes_or_hotspot_x, entry.bit_count_or_hotspot_y));
+        } else {
+            report.push(format!("平面数: {}  每像素位数: {}", entry.planes_or_hotspot_x, entry.bit_count_or_hotspot_y));
+        }
+        report.push(format!("数据偏移: {}  数据长度: {} 字节", entry.offset, entry.size));
+        match entry.data(data) {
+            None => report.push("警告: 数据超出文件范围".to_string()),
+            Some(_) if entry.is_png(data) => {
+                let width = get_dword_be(data, entry.offset as usize + 16);
+                let height = get_dword_be(data, entry.offset as usize + 20);
+                report.push(format!("编码: PNG ({}x{})", width, height));
+            }
+            Some(_) => {
+                let start = entry.offset as usize;
+                let header_size = get_dword_le(data, start);
+                let width = get_dword_le(data, start + 4);
+                // DIB 高度包含 AND 掩码，为图像高度的两倍
+                let height = get_dword_le(data, start + 8) as i32;
+                let bits = get_word_le(data, start + 14);
+                report.push(format!(
+                    "编码: BMP (头长度 {}, {}x{}, {} 位)",
+                    header_size,
+                    width,
+                    height / 2,
+                    bits
+                ));
+            }
+        }
+        let start = entry.offset as usize;
+        used.push((start, start.saturating_add(entry.size as usize).min(data.len()), format!("目录项 {}", i + 1)));
+    }
+
+    report_unreferenced(data, used, report);
+}
+
 /// 分析文件格式
 pub fn analyse_file_format(file_path: &str) -> Vec<String> {
     let mut report = vec!["文件格式报告".to_string()];
@@ -581,6 +1019,15 @@ pub fn analyse_file_format(file_path: &str) -> Vec<String> {
             } else if data.len() >= 6 && data[0] == b'G' && data[1] == b'I' && data[2] == b'F' {
                 report.push("文件格式: GIF".to_string());
                 analyse_gif(&data, &mut report);
+            } else if data.len() >= 4 && (&data[0..4] == b"II*\0" || &data[0..4] == b"MM\0*") {
+                report.push("文件格式: TIFF".to_string());
+                analyse_tiff(&data, &mut report);
+            } else if ico_header(&data).is_some() {
+                report.push("文件格式: ICO/CUR".to_string());
+                analyse_ico(&data, &mut report);
+            } else if is_webp(&data) {
+                report.push("文件格式: WebP".to_string());
+                analyse_webp(&data, &mut report);
             } else if data.len() >= 2 && data[0] == 0xFF && data[1] == 0xD8 {
                 report.push("文件格式: JPEG".to_string());
                 analyse_jpg(&data, &mut report);
@@ -596,3 +1043,24 @@ pub fn analyse_file_format(file_path: &str) -> Vec<String> {
 
     report
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreferenced_ignores_offsets_past_eof() {
+        let data = [0u8; 100];
+        let used = vec![
+            (0, 10, "文件头".to_string()),
+            (200, 300, "条带".to_string()),
+            (150, 100, "图块".to_string()),
+            (40, 60, "条带".to_string()),
+        ];
+        let mut report = Vec::new();
+        report_unreferenced(&data, used, &mut report);
+        assert!(report.contains(&"2 处, 共 70 字节".to_string()));
+        assert!(report.contains(&"偏移 10 - 39 (30 字节)".to_string()));
+        assert!(report.contains(&"偏移 60 - 99 (40 字节)".to_string()));
+    }
+}
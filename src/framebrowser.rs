@@ -1,17 +1,212 @@
 use eframe::egui;
 use egui::{ColorImage, TextureHandle, Ui};
-use image::codecs::gif::GifDecoder;
-use image::AnimationDecoder;
-use image::{ImageError, ImageFormat, RgbaImage};
+use image::{DynamicImage, ImageError, ImageFormat, Rgba, RgbaImage};
 use std::path::Path;
-use std::io::BufReader;
 use crate::apng_decoder::{check_apng, ApngDecoder};
+use crate::gif_decoder::GifDecoder;
+use crate::webp_decoder::WebpDecoder;
+use crate::tiff_decoder::TiffDecoder;
+use crate::ico_decoder::IcoDecoder;
+use crate::timing::{FrameDelay, TimingPanel};
+use crate::transform::{Transform, TRANS_COUNT};
+use crate::frameexport::{encode_apng, encode_gif, export_frames, frame_sheet};
+
+/// 帧区域的处置方式（APNG dispose_op / GIF disposal method）
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum FrameDispose {
+    /// 保留当前画布
+    #[default]
+    None,
+    /// 将帧区域清为背景
+    Background,
+    /// 恢复到绘制该帧之前的画布
+    Previous,
+}
+
+impl FrameDispose {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FrameDispose::None => "保留",
+            FrameDispose::Background => "清除为背景",
+            FrameDispose::Previous => "恢复上一状态",
+        }
+    }
+}
+
+/// 帧绘制到画布时的混合方式（APNG blend_op）
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum FrameBlend {
+    /// 直接覆盖
+    #[default]
+    Source,
+    /// 按 alpha 混合
+    Over,
+}
+
+impl FrameBlend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FrameBlend::Source => "覆盖",
+            FrameBlend::Over => "Alpha 混合",
+        }
+    }
+}
+
+/// 单帧数据：文件中按原样存储的子帧，以及按动画规则合成后的完整画布
+#[derive(Clone, Default)]
+pub struct Frame {
+    /// 合成后的完整画布
+    pub composed: RgbaImage,
+    /// 按原样存储的子帧（未合成）
+    pub raw: RgbaImage,
+    /// 子帧在画布中的偏移
+    pub x: u32,
+    pub y: u32,
+    /// 帧延迟 = delay_num / delay_den 秒
+    pub delay_num: u32,
+    pub delay_den: u32,
+    /// 处置方式
+    pub dispose: FrameDispose,
+    /// 混合方式
+    pub blend: FrameBlend,
+    /// 索引色帧的原始调色板索引（灰度值即索引值）
+    pub index_map: Option<RgbaImage>,
+    /// 索引色帧使用的调色板（RGBA）
+    pub palette: Vec<[u8; 4]>,
+    /// 16 位帧每个样本的低字节（显示的 8 位图像只保留了高字节）
+    pub low_bytes: Option<RgbaImage>,
+    /// 附加说明（例如不属于动画的默认图像）
+    pub note: Option<String>,
+}
+
+impl Frame {
+    /// 由完整图像构造的单帧（无偏移、无延迟）
+    pub fn from_image(img: RgbaImage) -> Self {
+        Self {
+            raw: img.clone(),
+            composed: img,
+            ..Self::default()
+        }
+    }
+
+    /// 帧延迟（秒）
+    pub fn delay_secs(&self) -> f32 {
+        if self.delay_den == 0 {
+            0.0
+        } else {
+            self.delay_num as f32 / self.delay_den as f32
+        }
+    }
+}
+
+/// 帧浏览器中的显示视图
+#[derive(Clone, Copy, PartialEq)]
+pub enum FrameView {
+    /// 合成后的完整画布
+    Composed,
+    /// 文件中存储的原始子帧
+    Raw,
+    /// 原始调色板索引（仅索引色帧）
+    Indices,
+    /// 16 位样本的低字节（仅 16 位帧）
+    LowBytes,
+}
+
+/// 帧差分模式
+#[derive(Clone, Copy, PartialEq)]
+pub enum FrameDiff {
+    /// 不做差分
+    Off,
+    /// RGB 各通道差的绝对值（乘以增益）
+    AbsDiff,
+    /// RGB 各通道按位异或
+    Xor,
+    /// 任一通道（含 alpha）变化的像素显示为白色
+    Mask,
+}
+
+impl FrameDiff {
+    pub fn all() -> [FrameDiff; 4] {
+        [FrameDiff::Off, FrameDiff::AbsDiff, FrameDiff::Xor, FrameDiff::Mask]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FrameDiff::Off => "关闭",
+            FrameDiff::AbsDiff => "绝对差",
+            FrameDiff::Xor => "异或",
+            FrameDiff::Mask => "变化掩码",
+        }
+    }
+}
+
+/// 差分的参考帧
+#[derive(Clone, Copy, PartialEq)]
+pub enum DiffReference {
+    /// 上一帧（第一帧以最后一帧为参考）
+    Previous,
+    /// 指定帧
+    Frame,
+}
+
+/// 计算两帧的差分图像，尺寸与 image 相同，超出 reference 范围的像素视为全 0
+pub fn frame_diff(image: &RgbaImage, reference: &RgbaImage, mode: FrameDiff, gain: u32) -> RgbaImage {
+    let mut out = RgbaImage::new(image.width(), image.height());
+    for (x, y, a) in image.enumerate_pixels() {
+        let b = if x < reference.width() && y < reference.height() {
+            *reference.get_pixel(x, y)
+        } else {
+            Rgba([0, 0, 0, 0])
+        };
+        let pixel = match mode {
+            FrameDiff::Off => *a,
+            FrameDiff::AbsDiff => {
+                let d = |i: usize| (a[i].abs_diff(b[i]) as u32 * gain).min(255) as u8;
+                Rgba([d(0), d(1), d(2), 255])
+            }
+            FrameDiff::Xor => Rgba([a[0] ^ b[0], a[1] ^ b[1], a[2] ^ b[2], 255]),
+            FrameDiff::Mask => {
+                let v = if *a != b { 255 } else { 0 };
+                Rgba([v, v, v, 255])
+            }
+        };
+        out.put_pixel(x, y, pixel);
+    }
+    out
+}
 
 /// 帧浏览器：用于浏览、切换和保存图片帧
 pub struct FrameBrowser {
-    frames: Vec<RgbaImage>,
+    frames: Vec<Frame>,
     textures: Vec<Option<TextureHandle>>,
     current_frame: usize,
+    /// 当前显示视图
+    view: FrameView,
+    /// 帧延迟时间通道
+    timing: TimingPanel,
+    /// 差分模式与参考帧
+    diff: FrameDiff,
+    reference: DiffReference,
+    reference_frame: usize,
+    /// 绝对差的放大倍数
+    diff_gain: u32,
+    /// 应用到显示帧的变换编号（与主窗口的通道一致，0 为原图）
+    plane: i32,
+    /// 播放状态、速度倍率、是否循环，以及当前帧开始显示的时间
+    playing: bool,
+    speed: f32,
+    looping: bool,
+    frame_started: f64,
+    /// 批量导出的文件名模板与帧号位数
+    export_pattern: String,
+    export_digits: usize,
+    /// 拼图列数（0 为单行精灵图）与格间距
+    sheet_columns: usize,
+    sheet_spacing: u32,
+    /// 参与重新编码的帧
+    selected: Vec<bool>,
+    /// 导出状态提示
+    status: String,
 }
 
 impl FrameBrowser {
@@ -21,6 +216,23 @@ impl FrameBrowser {
             frames: Vec::new(),
             textures: Vec::new(),
             current_frame: 0,
+            view: FrameView::Composed,
+            timing: TimingPanel::default(),
+            diff: FrameDiff::Off,
+            reference: DiffReference::Previous,
+            reference_frame: 0,
+            diff_gain: 1,
+            plane: 0,
+            playing: false,
+            speed: 1.0,
+            looping: true,
+            frame_started: 0.0,
+            export_pattern: "frame_{n}.png".to_string(),
+            export_digits: 3,
+            sheet_columns: 0,
+            sheet_spacing: 0,
+            selected: Vec::new(),
+            status: String::new(),
         }
     }
 
@@ -29,23 +241,24 @@ impl FrameBrowser {
         // 清空现有帧
         self.frames.clear();
         self.textures.clear();
+        self.selected.clear();
         self.current_frame = 0;
+        self.playing = false;
 
         let path = path.as_ref();
-        
+
         // 首先检查是否为APNG格式（避免卡死）
         if let Ok(apng_info) = check_apng(path) {
             if apng_info.is_apng {
                 println!("检测到APNG格式，帧数: {}", apng_info.frame_count);
-                
+
                 // 使用专门的APNG解码器
                 match ApngDecoder::from_path(path) {
                     Ok(decoder) => {
                         let frames = decoder.into_frames();
                         println!("成功解码 {} 帧", frames.len());
                         for frame in frames {
-                            self.frames.push(frame);
-                            self.textures.push(None);
+                            self.push_frame(frame);
                         }
                         return Ok(());
                     }
@@ -53,59 +266,307 @@ impl FrameBrowser {
                         eprintln!("APNG解码失败: {}, 尝试作为静态PNG加载", e);
                         // 失败时回退到加载第一帧
                         let img = image::open(path)?.to_rgba8();
-                        self.frames.push(img);
-                        self.textures.push(None);
+                        self.push_frame(Frame::from_image(img));
                         return Ok(());
                     }
                 }
             }
         }
-        
+
         // 非APNG格式，使用常规方式加载
         let file = std::fs::File::open(path)?;
         let buf_reader = std::io::BufReader::new(file);
         let reader = image::ImageReader::new(buf_reader).with_guessed_format()?;
-        
+
         if let Some(format) = reader.format() {
             match format {
                 ImageFormat::Gif => {
-                    // GIF动画处理
-                    let file = std::fs::File::open(path)?;
-                    let buffered = BufReader::new(file);
-                    let decoder = GifDecoder::new(buffered)?;
-                    let frames = decoder.into_frames().collect_frames()?;
-                    
-                    println!("GIF帧数: {}", frames.len());
-                    for frame in frames {
-                        self.frames.push(frame.into_buffer());
-                        self.textures.push(None);
+                    // GIF动画处理：按索引色解码，保留子帧矩形、调色板和处置方式
+                    match GifDecoder::from_path(path) {
+                        Ok(decoder) => {
+                            let frames = decoder.into_frames();
+                            println!("GIF帧数: {}", frames.len());
+                            for frame in frames {
+                                self.push_frame(frame);
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("GIF解码失败: {}, 尝试作为静态图像加载", e);
+                            let img = image::open(path)?.to_rgba8();
+                            self.push_frame(Frame::from_image(img));
+                        }
                     }
                     return Ok(());
                 }
                 ImageFormat::WebP => {
-                    // WebP处理（动画支持有限）
-                    let img = reader.decode()?.to_rgba8();
-                    self.frames.push(img);
-                    self.textures.push(None);
+                    // WebP处理：动画 WebP 按 ANMF 块逐帧解码
+                    match WebpDecoder::from_path(path) {
+                        Ok(decoder) => {
+                            for frame in decoder.into_frames() {
+                                self.push_frame(frame);
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("WebP解码失败: {}, 尝试作为静态图像加载", e);
+                            let img = reader.decode()?.to_rgba8();
+                            self.push_frame(Frame::from_image(img));
+                        }
+                    }
+                    return Ok(());
+                }
+                ImageFormat::Tiff | ImageFormat::Ico => {
+                    // 多页 TIFF 的每个 IFD、ICO/CUR 的每个目录项各作为一帧
+                    let decoded = if format == ImageFormat::Tiff {
+                        TiffDecoder::from_path(path).map(|d| d.into_frames())
+                    } else {
+                        IcoDecoder::from_path(path).map(|d| d.into_frames())
+                    };
+                    match decoded {
+                        Ok(frames) => {
+                            for frame in frames {
+                                self.push_frame(frame);
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("{:?} 解码失败: {}, 尝试作为静态图像加载", format, e);
+                            let img = reader.decode()?.to_rgba8();
+                            self.push_frame(Frame::from_image(img));
+                        }
+                    }
                     return Ok(());
                 }
                 _ => {
                     // 其他格式作为静态图像加载
                     let img = reader.decode()?.to_rgba8();
-                    self.frames.push(img);
-                    self.textures.push(None);
+                    self.push_frame(Frame::from_image(img));
                     return Ok(());
                 }
             }
         } else {
+            // CUR 文件没有可识别的图像魔数，按 ICO 目录解析
+            if let Ok(decoder) = IcoDecoder::from_path(path) {
+                for frame in decoder.into_frames() {
+                    self.push_frame(frame);
+                }
+                return Ok(());
+            }
             // 无法判断格式时，尝试按静态图像加载
             let img = reader.decode()?.to_rgba8();
-            self.frames.push(img);
-            self.textures.push(None);
+            self.push_frame(Frame::from_image(img));
             return Ok(());
         }
     }
 
+    // 添加一帧并为其预留纹理位置
+    fn push_frame(&mut self, frame: Frame) {
+        self.frames.push(frame);
+        self.textures.push(None);
+        self.selected.push(true);
+    }
+
+    /// 第一帧的合成图像（用于主窗口无法直接打开的文件，例如 CUR）
+    pub fn first_image(&self) -> Option<&RgbaImage> {
+        self.frames.first().map(|frame| &frame.composed)
+    }
+
+    /// 按当前视图（合成画布 / 原始子帧 / 调色板索引）获取所有帧图像
+    pub fn frame_images(&self) -> Vec<&RgbaImage> {
+        self.frames.iter().map(|frame| self.view_image(frame)).collect()
+    }
+
+    /// 所有动画帧的延迟序列（跳过没有延迟信息的帧，例如 APNG 中不属于动画的默认图像）
+    pub fn delays(&self) -> Vec<FrameDelay> {
+        self.frames
+            .iter()
+            .filter(|frame| frame.delay_den != 0)
+            .map(|frame| (frame.delay_num, frame.delay_den))
+            .collect()
+    }
+
+    // 当前视图下某帧对应的图像
+    fn view_image<'a>(&self, frame: &'a Frame) -> &'a RgbaImage {
+        match self.view {
+            FrameView::Composed => &frame.composed,
+            FrameView::Raw => &frame.raw,
+            // 非索引色帧没有调色板索引，退回显示原始子帧
+            FrameView::Indices => frame.index_map.as_ref().unwrap_or(&frame.raw),
+            FrameView::LowBytes => frame.low_bytes.as_ref().unwrap_or(&frame.raw),
+        }
+    }
+
+    /// 实际显示的图像：当前视图 → 差分 → 位平面变换
+    pub fn display_image(&self, idx: usize) -> RgbaImage {
+        let mut img = self.view_image(&self.frames[idx]).clone();
+        if self.diff != FrameDiff::Off {
+            let ref_idx = match self.reference {
+                DiffReference::Previous => (idx + self.frames.len() - 1) % self.frames.len(),
+                DiffReference::Frame => self.reference_frame.min(self.frames.len() - 1),
+            };
+            img = frame_diff(&img, self.view_image(&self.frames[ref_idx]), self.diff, self.diff_gain);
+        }
+        if self.plane != 0 {
+            let mut transform = Transform::new(DynamicImage::ImageRgba8(img));
+            transform.set_trans_num(self.plane);
+            img = transform.get_image().clone();
+        }
+        img
+    }
+
+    // 差分与位平面选项，选项变化时清空纹理缓存
+    fn display_options_ui(&mut self, ui: &mut Ui) {
+        let before = (self.diff, self.reference, self.reference_frame, self.diff_gain, self.plane);
+        ui.horizontal_wrapped(|ui| {
+            ui.label("差分:");
+            egui::ComboBox::from_id_salt("frame_diff_mode")
+                .selected_text(self.diff.name())
+                .show_ui(ui, |ui| {
+                    for mode in FrameDiff::all() {
+                        ui.selectable_value(&mut self.diff, mode, mode.name());
+                    }
+                });
+            if self.diff != FrameDiff::Off {
+                ui.label("参考:");
+                ui.radio_value(&mut self.reference, DiffReference::Previous, "上一帧");
+                ui.radio_value(&mut self.reference, DiffReference::Frame, "指定帧");
+                if self.reference == DiffReference::Frame {
+                    // 界面中帧号从 1 开始
+                    let mut number = self.reference_frame + 1;
+                    ui.add(egui::DragValue::new(&mut number).range(1..=self.frames.len()));
+                    self.reference_frame = number - 1;
+                    if ui.button("设为当前帧").clicked() {
+                        self.reference_frame = self.current_frame;
+                    }
+                }
+                if self.diff == FrameDiff::AbsDiff {
+                    ui.label("增益:");
+                    ui.add(egui::DragValue::new(&mut self.diff_gain).range(1..=255));
+                }
+            }
+            ui.separator();
+            ui.label("通道:");
+            if ui.button("<").clicked() {
+                self.plane = (self.plane - 1).rem_euclid(TRANS_COUNT);
+            }
+            ui.label(Transform::text_for(self.plane));
+            if ui.button(">").clicked() {
+                self.plane = (self.plane + 1).rem_euclid(TRANS_COUNT);
+            }
+        });
+        if before != (self.diff, self.reference, self.reference_frame, self.diff_gain, self.plane) {
+            self.textures.iter_mut().for_each(|t| *t = None);
+        }
+    }
+
+    // 按真实帧延迟推进播放，延迟为 0 的帧按 0.1 秒处理（与常见浏览器一致）
+    fn advance_playback(&mut self, ctx: &egui::Context) {
+        if !self.playing || self.frames.is_empty() {
+            return;
+        }
+        let now = ctx.input(|i| i.time);
+        let mut delay = self.frames[self.current_frame].delay_secs() as f64;
+        if delay <= 0.0 {
+            delay = 0.1;
+        }
+        delay /= self.speed.max(0.01) as f64;
+
+        let elapsed = now - self.frame_started;
+        if elapsed >= delay {
+            if self.current_frame + 1 < self.frames.len() {
+                self.current_frame += 1;
+            } else if self.looping {
+                self.current_frame = 0;
+            } else {
+                self.playing = false;
+                return;
+            }
+            self.frame_started = now;
+            ctx.request_repaint();
+        } else {
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(delay - elapsed));
+        }
+    }
+
+    // 批量导出、拼图与重新编码
+    fn export_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("文件名模板:");
+            ui.add(egui::TextEdit::singleline(&mut self.export_pattern).desired_width(140.0));
+            ui.label("帧号位数:");
+            ui.add(egui::DragValue::new(&mut self.export_digits).range(1..=8));
+            if ui.button("导出全部帧…").clicked() {
+                if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                    let images: Vec<RgbaImage> = (0..self.frames.len()).map(|i| self.display_image(i)).collect();
+                    self.status = match export_frames(&images, &dir, &self.export_pattern, self.export_digits) {
+                        Ok(count) => format!("已导出 {} 帧到 {}", count, dir.display()),
+                        Err(e) => e,
+                    };
+                }
+            }
+        });
+        ui.horizontal_wrapped(|ui| {
+            ui.label("拼图列数 (0=单行):");
+            ui.add(egui::DragValue::new(&mut self.sheet_columns).range(0..=self.frames.len()));
+            ui.label("间距:");
+            ui.add(egui::DragValue::new(&mut self.sheet_spacing).range(0..=64));
+            if ui.button("保存拼图…").clicked() {
+                if let Some(path) = rfd::FileDialog::new().set_file_name("frames_sheet.png").save_file() {
+                    let images: Vec<RgbaImage> = (0..self.frames.len()).map(|i| self.display_image(i)).collect();
+                    self.status = match frame_sheet(&images, self.sheet_columns, self.sheet_spacing) {
+                        Ok(sheet) => match sheet.save(&path) {
+                            Ok(()) => format!("已保存 {}x{} 拼图", sheet.width(), sheet.height()),
+                            Err(e) => format!("保存拼图失败: {}", e),
+                        },
+                        Err(e) => e,
+                    };
+                }
+            }
+        });
+        ui.horizontal_wrapped(|ui| {
+            let idx = self.current_frame;
+            ui.checkbox(&mut self.selected[idx], "编码时包含当前帧");
+            if ui.button("全选").clicked() {
+                self.selected.iter_mut().for_each(|s| *s = true);
+            }
+            if ui.button("全不选").clicked() {
+                self.selected.iter_mut().for_each(|s| *s = false);
+            }
+            let count = self.selected.iter().filter(|s| **s).count();
+            ui.label(format!("已选 {} 帧", count));
+            // 重新编码使用合成帧，保证尺寸一致
+            if ui.add_enabled(count > 0, egui::Button::new("编码为 GIF…")).clicked() {
+                if let Some(path) = rfd::FileDialog::new().set_file_name("frames.gif").save_file() {
+                    let frames: Vec<(&RgbaImage, f32)> = self.selected_frames().map(|f| (&f.composed, f.delay_secs())).collect();
+                    self.status = match encode_gif(&frames, &path) {
+                        Ok(()) => format!("已编码 {} 帧 GIF", frames.len()),
+                        Err(e) => format!("编码 GIF 失败: {}", e),
+                    };
+                }
+            }
+            if ui.add_enabled(count > 0, egui::Button::new("编码为 APNG…")).clicked() {
+                if let Some(path) = rfd::FileDialog::new().set_file_name("frames.png").save_file() {
+                    let frames: Vec<(&RgbaImage, u32, u32)> = self
+                        .selected_frames()
+                        .map(|f| {
+                            // 没有延迟信息的帧按 1/10 秒编码
+                            if f.delay_den == 0 { (&f.composed, 1, 10) } else { (&f.composed, f.delay_num, f.delay_den) }
+                        })
+                        .collect();
+                    self.status = match encode_apng(&frames, &path) {
+                        Ok(()) => format!("已编码 {} 帧 APNG", frames.len()),
+                        Err(e) => format!("编码 APNG 失败: {}", e),
+                    };
+                }
+            }
+        });
+        if !self.status.is_empty() {
+            ui.label(&self.status);
+        }
+    }
+
+    // 勾选参与重新编码的帧
+    fn selected_frames(&self) -> impl Iterator<Item = &Frame> {
+        self.frames.iter().zip(&self.selected).filter(|(_, s)| **s).map(|(f, _)| f)
+    }
 
     /// 将 RgbaImage 转换为 egui 所需的 ColorImage
     fn image_to_color_image(img: &RgbaImage) -> ColorImage {
@@ -133,18 +594,90 @@ impl FrameBrowser {
             }
         }
 
+        self.advance_playback(ui.ctx());
+
         ui.vertical(|ui| {
             // 如果没有加载帧，则提示
             if self.frames.is_empty() {
                 ui.label("No frames loaded");
             } else {
-                ui.label(format!("Frame: {} of {}", self.current_frame + 1, self.frames.len()));
+                ui.horizontal(|ui| {
+                    ui.label(format!("Frame: {} of {}", self.current_frame + 1, self.frames.len()));
+                    ui.separator();
+                    let mut view = self.view;
+                    ui.radio_value(&mut view, FrameView::Composed, "合成帧");
+                    ui.radio_value(&mut view, FrameView::Raw, "原始子帧");
+                    let has_indices = self.frames.iter().any(|f| f.index_map.is_some());
+                    ui.add_enabled_ui(has_indices, |ui| {
+                        ui.radio_value(&mut view, FrameView::Indices, "调色板索引");
+                    });
+                    let has_low_bytes = self.frames.iter().any(|f| f.low_bytes.is_some());
+                    ui.add_enabled_ui(has_low_bytes, |ui| {
+                        ui.radio_value(&mut view, FrameView::LowBytes, "16 位低字节");
+                    });
+                    if view != self.view {
+                        self.view = view;
+                        // 视图切换后纹理需要重新生成
+                        self.textures.iter_mut().for_each(|t| *t = None);
+                    }
+                });
+
+                self.display_options_ui(ui);
+
+                // 当前帧的元数据
+                let frame = &self.frames[self.current_frame];
+                ui.label(format!(
+                    "偏移: ({}, {})  子帧尺寸: {}x{}  延迟: {}/{} ({:.3}秒)  处置: {}  混合: {}{}",
+                    frame.x,
+                    frame.y,
+                    frame.raw.width(),
+                    frame.raw.height(),
+                    frame.delay_num,
+                    frame.delay_den,
+                    frame.delay_secs(),
+                    frame.dispose.name(),
+                    frame.blend.name(),
+                    frame.note.as_ref().map(|n| format!("  [{}]", n)).unwrap_or_default(),
+                ));
+                if !frame.palette.is_empty() {
+                    egui::CollapsingHeader::new(format!("调色板: {} 色", frame.palette.len()))
+                        .id_salt("frame_palette")
+                        .show(ui, |ui| {
+                            ui.horizontal_wrapped(|ui| {
+                                ui.spacing_mut().item_spacing = egui::vec2(1.0, 1.0);
+                                for (i, c) in frame.palette.iter().enumerate() {
+                                    let (rect, response) = ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                                    ui.painter().rect_filled(rect, 0.0, egui::Color32::from_rgba_unmultiplied(c[0], c[1], c[2], c[3]));
+                                    response.on_hover_text(format!(
+                                        "索引 {}: R={:02X} G={:02X} B={:02X} A={:02X}",
+                                        i, c[0], c[1], c[2], c[3]
+                                    ));
+                                }
+                            });
+                        });
+                }
+
+                let delays = self.delays();
+                if !delays.is_empty() {
+                    egui::CollapsingHeader::new(format!("时间通道: {} 个延迟", delays.len()))
+                        .id_salt("frame_timing")
+                        .show(ui, |ui| {
+                            self.timing.ui(ui, &delays);
+                        });
+                }
+
+                egui::CollapsingHeader::new("导出")
+                    .id_salt("frame_export")
+                    .show(ui, |ui| {
+                        self.export_ui(ui);
+                    });
+
                 // 使用 ScrollArea 显示图片
                 egui::ScrollArea::both().show(ui, |ui| {
                     let idx = self.current_frame;
                     // 若纹理尚未加载，则转换并缓存
                     if self.textures[idx].is_none() {
-                        let color_img = Self::image_to_color_image(&self.frames[idx]);
+                        let color_img = Self::image_to_color_image(&self.display_image(idx));
                         let texture = ui.ctx().load_texture(
                             format!("frame_{}", idx),
                             color_img,
@@ -179,11 +712,22 @@ impl FrameBrowser {
                             .set_file_name(&format!("frame{}.png", self.current_frame + 1))
                             .save_file()
                         {
-                            if let Err(e) = self.frames[self.current_frame].save(&path) {
+                            let img = self.display_image(self.current_frame);
+                            if let Err(e) = img.save(&path) {
                                 eprintln!("保存帧失败: {:?}", e);
                             }
                         }
                     }
+
+                    ui.separator();
+                    let play_text = if self.playing { "⏸ 暂停" } else { "▶ 播放" };
+                    if ui.button(play_text).clicked() {
+                        self.playing = !self.playing;
+                        self.frame_started = ui.ctx().input(|i| i.time);
+                    }
+                    ui.label("速度:");
+                    ui.add(egui::DragValue::new(&mut self.speed).range(0.1..=10.0).speed(0.05).suffix("x"));
+                    ui.checkbox(&mut self.looping, "循环");
                 });
             }
         });
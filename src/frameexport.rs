@@ -0,0 +1,145 @@
+use image::{Rgba, RgbaImage};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+// ──────────────────────────────
+// 帧导出：批量保存、拼图（精灵图 / 联系表）以及重新编码为 GIF / APNG
+
+/// 按文件名模板生成第 index 帧（从 0 开始）的文件名
+/// 模板中的 {n} 替换为从 1 开始、补零到 digits 位的帧号；没有 {n} 时帧号加在扩展名之前
+pub fn frame_file_name(pattern: &str, index: usize, digits: usize) -> String {
+    let number = format!("{:0width$}", index + 1, width = digits);
+    if pattern.contains("{n}") {
+        return pattern.replace("{n}", &number);
+    }
+    match pattern.rfind('.') {
+        Some(dot) => format!("{}{}{}", &pattern[..dot], number, &pattern[dot..]),
+        None => format!("{}{}.png", pattern, number),
+    }
+}
+
+/// 将所有帧按模板保存到目录，返回保存的帧数
+pub fn export_frames(images: &[RgbaImage], dir: &Path, pattern: &str, digits: usize) -> Result<usize, String> {
+    for (i, img) in images.iter().enumerate() {
+        let path = dir.join(frame_file_name(pattern, i, digits));
+        img.save(&path)
+            .map_err(|e| format!("保存 {} 失败: {}", path.display(), e))?;
+    }
+    Ok(images.len())
+}
+
+/// 拼图尺寸：count 帧、每行 columns 格（不为 0）、每格 cell_w x cell_h、格间距 spacing
+/// 尺寸超出 u32 时返回 None
+fn sheet_size(count: usize, columns: usize, cell: (u32, u32), spacing: u32) -> Option<(u32, u32)> {
+    let rows = u32::try_from(count.div_ceil(columns)).ok()?;
+    let columns = u32::try_from(columns).ok()?;
+    let extent = |n: u32, cell: u32| n.checked_mul(cell)?.checked_add((n - 1).checked_mul(spacing)?);
+    Some((extent(columns, cell.0)?, extent(rows, cell.1)?))
+}
+
+/// 将帧拼接为一张图：columns 为 0 时排成一行（精灵图），否则按列数平铺（联系表）
+/// 每格大小取所有帧的最大宽高，格间留 spacing 像素的透明间隔
+pub fn frame_sheet(images: &[RgbaImage], columns: usize, spacing: u32) -> Result<RgbaImage, String> {
+    if images.is_empty() {
+        return Ok(RgbaImage::new(1, 1));
+    }
+    let columns = if columns == 0 { images.len() } else { columns.min(images.len()) };
+    let cell_w = images.iter().map(|img| img.width()).max().unwrap_or(1);
+    let cell_h = images.iter().map(|img| img.height()).max().unwrap_or(1);
+    let (width, height) = sheet_size(images.len(), columns, (cell_w, cell_h), spacing)
+        .ok_or_else(|| "拼图尺寸过大，请增加列数或减少帧数".to_string())?;
+
+    let mut sheet = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 0]));
+    for (i, img) in images.iter().enumerate() {
+        // 分开相乘，每一项都不超过已检查过的拼图尺寸
+        let (col, row) = ((i % columns) as u32, (i / columns) as u32);
+        let (x, y) = (col * cell_w + col * spacing, row * cell_h + row * spacing);
+        image::imageops::replace(&mut sheet, img, x as i64, y as i64);
+    }
+    Ok(sheet)
+}
+
+/// 编码为无限循环的 GIF，frames 为 (图像, 延迟秒数)，所有帧按第一帧的尺寸写入
+pub fn encode_gif(frames: &[(&RgbaImage, f32)], path: &Path) -> Result<(), String> {
+    let (first, _) = frames.first().ok_or("没有选中的帧")?;
+    let (width, height) = (first.width(), first.height());
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err("图像尺寸超出 GIF 的限制".to_string());
+    }
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder =
+        gif::Encoder::new(BufWriter::new(file), width as u16, height as u16, &[]).map_err(|e| e.to_string())?;
+    encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| e.to_string())?;
+
+    for (img, delay) in frames {
+        if img.width() != width || img.height() != height {
+            return Err("所有帧的尺寸必须一致".to_string());
+        }
+        let mut pixels = img.as_raw().clone();
+        let mut frame = gif::Frame::from_rgba_speed(width as u16, height as u16, &mut pixels, 10);
+        // GIF 延迟单位为 1/100 秒
+        frame.delay = (delay * 100.0).round().min(u16::MAX as f32) as u16;
+        frame.dispose = gif::DisposalMethod::Background;
+        encoder.write_frame(&frame).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 编码为无限循环的 APNG，frames 为 (图像, 延迟分子, 延迟分母)
+pub fn encode_apng(frames: &[(&RgbaImage, u32, u32)], path: &Path) -> Result<(), String> {
+    let (first, _, _) = frames.first().ok_or("没有选中的帧")?;
+    let (width, height) = (first.width(), first.height());
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, 0).map_err(|e| e.to_string())?;
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+
+    for (img, num, den) in frames {
+        if img.width() != width || img.height() != height {
+            return Err("所有帧的尺寸必须一致".to_string());
+        }
+        // fcTL 中的延迟为 16 位，超出时按比例缩小
+        let scale = (*num.max(den) as f64 / u16::MAX as f64).max(1.0);
+        let num = (*num as f64 / scale).round() as u16;
+        let den = (*den as f64 / scale).round() as u16;
+        writer.set_frame_delay(num, den).map_err(|e| e.to_string())?;
+        writer.write_image_data(img.as_raw()).map_err(|e| e.to_string())?;
+    }
+    writer.finish().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_name_with_and_without_placeholder() {
+        assert_eq!(frame_file_name("frame_{n}.png", 0, 3), "frame_001.png");
+        assert_eq!(frame_file_name("{n}-{n}.bmp", 11, 2), "12-12.bmp");
+        // 没有 {n} 时帧号插在扩展名前，没有扩展名时补 .png
+        assert_eq!(frame_file_name("frame.v2.png", 4, 2), "frame.v205.png");
+        assert_eq!(frame_file_name("frame", 9, 1), "frame10.png");
+    }
+
+    #[test]
+    fn sheet_size_includes_spacing_between_cells() {
+        let images: Vec<RgbaImage> = (0..5).map(|i| RgbaImage::new(3 + i, 2)).collect();
+        // 3 列 2 行，每格 7x2，间距 4
+        let sheet = frame_sheet(&images, 3, 4).unwrap();
+        assert_eq!(sheet.dimensions(), (3 * 7 + 2 * 4, 2 * 2 + 4));
+        // 列数为 0 时排成一行
+        let strip = frame_sheet(&images, 0, 1).unwrap();
+        assert_eq!(strip.dimensions(), (5 * 7 + 4, 2));
+    }
+
+    #[test]
+    fn sheet_size_overflow_is_an_error() {
+        assert_eq!(sheet_size(4, 2, (10, 20), 3), Some((23, 43)));
+        assert_eq!(sheet_size(70_000, 70_000, (70_000, 1), 0), None);
+        assert_eq!(sheet_size(2, 2, (u32::MAX - 5, 1), 10), None);
+        assert_eq!(sheet_size(1, 1, (u32::MAX, 1), 10), Some((u32::MAX, 1)));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::apng_decoder::{clear_region, composite, index_map};
+use crate::framebrowser::{Frame, FrameBlend, FrameDispose};
+use image::{Rgba, RgbaImage};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// GIF解码器 - 使用gif crate按索引色解码每一帧，保留子帧矩形、局部调色板、透明索引，
+/// 并按处置方式合成画布
+pub struct GifDecoder {
+    frames: Vec<Frame>,
+}
+
+impl GifDecoder {
+    /// 从文件路径解码GIF的所有帧
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = File::open(&path)?;
+        Self::from_reader(BufReader::new(file))
+    }
+
+    /// 从任意数据源解码GIF的所有帧
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(reader)?;
+
+        let width = decoder.width() as u32;
+        let height = decoder.height() as u32;
+        let global_palette = decoder.global_palette().map(|p| p.to_vec());
+
+        let mut frames = Vec::new();
+        let mut canvas = RgbaImage::new(width, height);
+
+        loop {
+            let frame = match decoder.read_next_frame() {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err(e) if frames.is_empty() => return Err(e.into()),
+                // 截断的文件：保留已解码的帧
+                Err(_) => break,
+            };
+
+            let (fw, fh) = (frame.width as u32, frame.height as u32);
+            let (x, y) = (frame.left as u32, frame.top as u32);
+            let is_local = frame.palette.is_some();
+            let colors = frame
+                .palette
+                .as_deref()
+                .or(global_palette.as_deref())
+                .unwrap_or(&[]);
+            let palette = gif_palette(colors, frame.transparent);
+
+            // 按调色板还原子帧，越界索引显示为透明
+            let mut raw = RgbaImage::new(fw, fh);
+            for (pixel, &index) in raw.pixels_mut().zip(frame.buffer.iter()) {
+                *pixel = Rgba(palette.get(index as usize).copied().unwrap_or([0, 0, 0, 0]));
+            }
+            let indices = index_map(&frame.buffer, fw, fh);
+
+            let dispose = match frame.dispose {
+                gif::DisposalMethod::Any | gif::DisposalMethod::Keep => FrameDispose::None,
+                gif::DisposalMethod::Background => FrameDispose::Background,
+                gif::DisposalMethod::Previous => FrameDispose::Previous,
+            };
+
+            let previous = if dispose == FrameDispose::Previous {
+                Some(canvas.clone())
+            } else {
+                None
+            };
+            // 透明索引对应的像素不覆盖画布
+            composite(&mut canvas, &raw, x, y, FrameBlend::Over);
+            let composed = canvas.clone();
+
+            match dispose {
+                FrameDispose::None => {}
+                FrameDispose::Background => clear_region(&mut canvas, x, y, fw, fh),
+                FrameDispose::Previous => {
+                    if let Some(previous) = previous {
+                        canvas = previous;
+                    }
+                }
+            }
+
+            let mut notes = vec![if is_local { "局部调色板" } else { "全局调色板" }.to_string()];
+            if let Some(t) = frame.transparent {
+                notes.push(format!("透明索引 {}", t));
+            }
+            if frame.interlaced {
+                notes.push("隔行".to_string());
+            }
+            if frame.needs_user_input {
+                notes.push("等待用户输入".to_string());
+            }
+
+            frames.push(Frame {
+                composed,
+                raw,
+                x,
+                y,
+                // GIF 延迟单位为 1/100 秒
+                delay_num: frame.delay as u32,
+                delay_den: 100,
+                dispose,
+                blend: FrameBlend::Over,
+                index_map: Some(indices),
+                palette,
+                low_bytes: None,
+                note: Some(notes.join("，")),
+            });
+        }
+
+        Ok(Self { frames })
+    }
+
+    /// 获取所有帧
+    pub fn into_frames(self) -> Vec<Frame> {
+        self.frames
+    }
+}
+
+/// 将 GIF 的 RGB 调色板转换为 RGBA，透明索引的 alpha 为 0
+fn gif_palette(colors: &[u8], transparent: Option<u8>) -> Vec<[u8; 4]> {
+    colors
+        .chunks_exact(3)
+        .enumerate()
+        .map(|(i, c)| {
+            let alpha = if transparent == Some(i as u8) { 0 } else { 255 };
+            [c[0], c[1], c[2], alpha]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    fn gif_frame(
+        rect: (u16, u16, u16, u16),
+        indices: &[u8],
+        palette: Option<Vec<u8>>,
+        transparent: Option<u8>,
+        dispose: gif::DisposalMethod,
+    ) -> gif::Frame<'static> {
+        let (left, top, width, height) = rect;
+        gif::Frame {
+            left,
+            top,
+            width,
+            height,
+            buffer: Cow::Owned(indices.to_vec()),
+            palette,
+            transparent,
+            dispose,
+            delay: 7,
+            ..gif::Frame::default()
+        }
+    }
+
+    // 2x2 画布：全白帧 → 局部调色板的 1x1 绿色子帧（处置为背景）→ 全透明子帧
+    fn sample_gif() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = gif::Encoder::new(&mut bytes, 2, 2, &[0, 0, 0, 255, 255, 255]).unwrap();
+            let frames = [
+                gif_frame((0, 0, 2, 2), &[1, 1, 1, 1], None, None, gif::DisposalMethod::Keep),
+                gif_frame(
+                    (1, 1, 1, 1),
+                    &[1],
+                    Some(vec![255, 0, 0, 0, 255, 0]),
+                    None,
+                    gif::DisposalMethod::Background,
+                ),
+                gif_frame((0, 0, 2, 1), &[0, 0], None, Some(0), gif::DisposalMethod::Keep),
+            ];
+            for frame in &frames {
+                encoder.write_frame(frame).unwrap();
+            }
+        }
+        bytes
+    }
+
+    fn pixels(img: &RgbaImage) -> Vec<[u8; 4]> {
+        img.pixels().map(|p| p.0).collect()
+    }
+
+    #[test]
+    fn decodes_rectangles_palettes_and_disposal() {
+        let frames = GifDecoder::from_reader(sample_gif().as_slice()).unwrap().into_frames();
+        assert_eq!(frames.len(), 3);
+        const W: [u8; 4] = [255, 255, 255, 255];
+        const G: [u8; 4] = [0, 255, 0, 255];
+
+        assert_eq!(pixels(&frames[0].composed), vec![W; 4]);
+        assert_eq!((frames[0].delay_num, frames[0].delay_den), (7, 100));
+        assert_eq!(frames[0].note.as_deref(), Some("全局调色板"));
+
+        let second = &frames[1];
+        assert_eq!((second.x, second.y), (1, 1));
+        assert_eq!(pixels(&second.raw), vec![G]);
+        assert_eq!(pixels(&second.composed), vec![W, W, W, G]);
+        assert_eq!(second.palette, vec![[255, 0, 0, 255], [0, 255, 0, 255]]);
+        assert_eq!(second.index_map.as_ref().unwrap().get_pixel(0, 0).0, [1, 1, 1, 255]);
+        assert_eq!(second.note.as_deref(), Some("局部调色板"));
+
+        // 第二帧处置为背景后其区域变透明，第三帧的透明像素不覆盖画布
+        let third = &frames[2];
+        assert_eq!(pixels(&third.raw), vec![[0, 0, 0, 0]; 2]);
+        assert_eq!(pixels(&third.composed), vec![W, W, W, [0, 0, 0, 0]]);
+        assert_eq!(third.note.as_deref(), Some("全局调色板，透明索引 0"));
+    }
+
+    #[test]
+    fn truncated_file_keeps_decoded_frames() {
+        let bytes = sample_gif();
+        let frames = GifDecoder::from_reader(&bytes[..bytes.len() - 12]).unwrap().into_frames();
+        assert!(!frames.is_empty() && frames.len() < 3);
+        assert!(GifDecoder::from_reader(&bytes[..10]).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use crate::framebrowser::Frame;
+use image::{ImageFormat, RgbaImage};
+use std::path::Path;
+
+/// ICO/CUR 目录中的一项
+pub struct IcoEntry {
+    /// 目录中声明的宽高（0 表示 256）
+    pub width: u32,
+    pub height: u32,
+    /// 调色板颜色数（0 表示无调色板或 256 色）
+    pub color_count: u8,
+    /// ICO 为颜色平面数，CUR 为热点 X
+    pub planes_or_hotspot_x: u16,
+    /// ICO 为每像素位数，CUR 为热点 Y
+    pub bit_count_or_hotspot_y: u16,
+    /// 图像数据长度与偏移
+    pub size: u32,
+    pub offset: u32,
+}
+
+impl IcoEntry {
+    /// 图像数据是否为 PNG
+    pub fn is_png(&self, data: &[u8]) -> bool {
+        self.data(data).is_some_and(|d| d.starts_with(b"\x89PNG\r\n\x1a\n"))
+    }
+
+    /// 图像数据（超出文件范围时返回 None）
+    pub fn data<'a>(&self, data: &'a [u8]) -> Option<&'a [u8]> {
+        let start = self.offset as usize;
+        let end = start.checked_add(self.size as usize)?;
+        data.get(start..end)
+    }
+}
+
+/// 检查文件头，返回类型（1 = ICO，2 = CUR）与项数
+pub fn ico_header(data: &[u8]) -> Option<(u16, u16)> {
+    if data.len() < 6 {
+        return None;
+    }
+    let reserved = u16::from_le_bytes([data[0], data[1]]);
+    let kind = u16::from_le_bytes([data[2], data[3]]);
+    let count = u16::from_le_bytes([data[4], data[5]]);
+    if reserved != 0 || !(kind == 1 || kind == 2) || count == 0 {
+        return None;
+    }
+    Some((kind, count))
+}
+
+/// 读取目录项，目录被截断时只返回完整的项
+pub fn ico_entries(data: &[u8]) -> Vec<IcoEntry> {
+    let Some((_, count)) = ico_header(data) else {
+        return Vec::new();
+    };
+    (0..count as usize)
+        .map(|i| 6 + i * 16)
+        .take_while(|&pos| pos + 16 <= data.len())
+        .map(|pos| {
+            let e = &data[pos..pos + 16];
+            let dim = |v: u8| if v == 0 { 256 } else { v as u32 };
+            IcoEntry {
+                width: dim(e[0]),
+                height: dim(e[1]),
+                color_count: e[2],
+                planes_or_hotspot_x: u16::from_le_bytes([e[4], e[5]]),
+                bit_count_or_hotspot_y: u16::from_le_bytes([e[6], e[7]]),
+                size: u32::from_le_bytes([e[8], e[9], e[10], e[11]]),
+                offset: u32::from_le_bytes([e[12], e[13], e[14], e[15]]),
+            }
+        })
+        .collect()
+}
+
+/// ICO/CUR解码器 - 目录中的每一项（含 PNG 压缩项）作为一帧
+pub struct IcoDecoder {
+    frames: Vec<Frame>,
+}
+
+impl IcoDecoder {
+    /// 从文件路径解码所有目录项
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let data = std::fs::read(path)?;
+        let (kind, _) = ico_header(&data).ok_or("不是有效的 ICO/CUR 文件")?;
+        let entries = ico_entries(&data);
+
+        let frames = entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let detail = if kind == 2 {
+                    format!("热点 ({}, {})", entry.planes_or_hotspot_x, entry.bit_count_or_hotspot_y)
+                } else {
+                    format!("{} 位", entry.bit_count_or_hotspot_y)
+                };
+                let kind_name = if entry.is_png(&data) { "PNG" } else { "BMP" };
+                match decode_entry(&data, entry) {
+                    Ok(img) => Frame {
+                        note: Some(format!("第 {} 项, {}, {}", i + 1, kind_name, detail)),
+                        ..Frame::from_image(img)
+                    },
+                    Err(e) => Frame {
+                        note: Some(format!("第 {} 项解码失败: {}", i + 1, e)),
+                        ..Frame::from_image(RgbaImage::new(1, 1))
+                    },
+                }
+            })
+            .collect();
+        Ok(Self { frames })
+    }
+
+    /// 获取所有帧
+    pub fn into_frames(self) -> Vec<Frame> {
+        self.frames
+    }
+}
+
+// 解码单个目录项：PNG 项直接解码，BMP 项重新封装为只有一项的 ICO 再解码
+fn decode_entry(data: &[u8], entry: &IcoEntry) -> Result<RgbaImage, Box<dyn std::error::Error>> {
+    let image_data = entry.data(data).ok_or("数据超出文件范围")?;
+    if entry.is_png(data) {
+        return Ok(image::load_from_memory_with_format(image_data, ImageFormat::Png)?.to_rgba8());
+    }
+
+    let mut file = Vec::with_capacity(22 + image_data.len());
+    file.extend_from_slice(&[0, 0, 1, 0, 1, 0]);
+    let dim = |v: u32| if v >= 256 { 0 } else { v as u8 };
+    file.extend_from_slice(&[dim(entry.width), dim(entry.height), entry.color_count, 0]);
+    // CUR 的热点字段在 ICO 中为平面数和位数，按 1 个平面、由 DIB 头决定位数处理
+    file.extend_from_slice(&1u16.to_le_bytes());
+    file.extend_from_slice(&0u16.to_le_bytes());
+    file.extend_from_slice(&(image_data.len() as u32).to_le_bytes());
+    file.extend_from_slice(&22u32.to_le_bytes());
+    file.extend_from_slice(image_data);
+    Ok(image::load_from_memory_with_format(&file, ImageFormat::Ico)?.to_rgba8())
+}
@@ -7,21 +7,40 @@ mod stereo;
 mod extractanlysis;
 mod framebrowser;
 mod combine;
+mod pixelexpr;
+mod diffreport;
 mod apng_decoder;
+mod gif_decoder;
+mod webp_decoder;
+mod tiff_decoder;
+mod ico_decoder;
+mod timing;
+mod frameexport;
+mod datatransform;
+mod bitview;
+mod embed;
+mod presets;
+mod selection;
+mod clipboard;
+mod rawimport;
 
 use eframe::egui;
 use egui::*;
 use rfd;
 use stereo::Stereo;
 use extractanlysis::ExtractDialog;
+use embed::EmbedDialog;
 use fileanalysis::FileAnalysis;
 use framebrowser::FrameBrowser;
+use selection::{HistogramView, Selection};
+use rawimport::RawImportDialog;
 
 use transform::Transform;
 use combine::ImageCombiner;
 
+/// 一个打开的图像（标签页）及其全部状态：通道变换、缩放滚动、选区和各个对话框
 #[derive(Default)]
-struct StegApp {
+struct Document {
     transform: Option<Transform>,
     current_file_path: Option<String>,
     zoom_level: f32,
@@ -30,29 +49,94 @@ struct StegApp {
 
     stereo: Option<Stereo>,
     extract_dialog: Option<ExtractDialog>,
+    embed_dialog: Option<EmbedDialog>,
     frame_browser: Option<FrameBrowser>,
     combine_dialog: Option<ImageCombiner>,
 
-
+    /// 主视图中的框选区域，以及拖动框选时的起点（像素坐标）
+    selection: Option<Selection>,
+    selection_anchor: Option<(u32, u32)>,
+    histogram_view: Option<HistogramView>,
 
     current_channel_text: String,
     show_file_analysis: bool,
     show_extract_dialog: bool,
+    show_embed_dialog: bool,
     show_stereo_dialog: bool,
     show_frame_browser: bool,
     show_combine_dialog: bool,
+    show_histogram: bool,
+}
+
+impl Document {
+    /// 标签页标题：文件名，没有对应文件时为“未命名”
+    fn title(&self) -> String {
+        self.current_file_path
+            .as_deref()
+            .map(|path| {
+                std::path::Path::new(path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.to_string())
+            })
+            .unwrap_or_else(|| "未命名".to_string())
+    }
+}
+
+#[derive(Default)]
+struct StegApp {
+    /// 当前标签页
+    doc: Document,
+    /// 所有标签页，当前标签页的位置上是换出后留下的空文档
+    documents: Vec<Document>,
+    active: usize,
+    /// 快速切换窗口及其过滤文本
+    show_switcher: bool,
+    switcher_query: String,
+
+    /// 框选模式：拖动时框选而不是滚动（按住 Shift 拖动也可以框选）
+    select_mode: bool,
+    paste_shortcut: clipboard::PasteShortcut,
+    raw_import: Option<RawImportDialog>,
+
     show_about: bool,
+    show_raw_import: bool,
 
 }
 
 
 
+// 将标准输入的数据写入新建的临时文件：文件名包含进程号，并以独占方式创建，
+// 不会覆盖其他实例或其他用户预先放置的文件
+fn write_stdin_file(data: &[u8]) -> std::io::Result<std::path::PathBuf> {
+    use std::io::Write;
+    let extension = image::guess_format(data)
+        .ok()
+        .and_then(|format| format.extensions_str().first().copied())
+        .unwrap_or("bin");
+    let mut last_error = None;
+    for n in 0..100 {
+        let path = std::env::temp_dir().join(format!("stegsolve_stdin_{}_{}.{}", std::process::id(), n, extension));
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(data)?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => last_error = Some(e),
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| std::io::Error::other("无法创建临时文件")))
+}
+
 fn main() {
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1000.0, 700.0]),
         ..Default::default()
     };
+    // 命令行参数：图片路径，或 - 表示从标准输入读取
+    let startup_arg = std::env::args().nth(1);
     if let Err(e)=eframe::run_native(
         "StegSolve-rs",
         options,
@@ -70,7 +154,11 @@ fn main() {
                 .insert(0, "misans".to_owned());
 
             cc.egui_ctx.set_fonts(fonts);
-            Ok(Box::<StegApp>::default())
+            let mut app = StegApp::default();
+            if let Some(arg) = startup_arg {
+                app.open_argument(&arg);
+            }
+            Ok(Box::new(app))
         }),
     ) {
         eprintln!("Error: {}", e);
@@ -99,24 +187,291 @@ impl StegApp {
     // }
 
     fn open_image(&mut self, path: &std::path::Path) {
-        match image::open(path) {
+        // image 无法识别的格式（例如 CUR）退回到帧浏览器的解码器，取第一帧
+        let opened = image::open(path).or_else(|e| {
+            let mut browser = framebrowser::FrameBrowser::new();
+            match browser.load_frames(path).ok().and(browser.first_image()) {
+                Some(img) => Ok(image::DynamicImage::ImageRgba8(img.clone())),
+                None => Err(e),
+            }
+        });
+        match opened {
             Ok(img) => {
-                self.transform = Some(Transform::new(img));
-                self.current_file_path = Some(path.to_string_lossy().to_string());
-                self.texture = None;
-                self.zoom_level = 1.0;
-                self.scroll_pos = Vec2::ZERO;
-                if let Some(t) = &self.transform {
-                    self.stereo = Some(Stereo::new(t.get_image().clone()));
+                self.open_tab();
+                self.set_working_image(img);
+                self.doc.current_file_path = Some(path.to_string_lossy().to_string());
+                self.doc.frame_browser = Some(framebrowser::FrameBrowser::new());
+                if let Some(browser) = &mut self.doc.frame_browser {
+                    let _ = browser.load_frames(&self.doc.current_file_path.as_ref().unwrap());
+                }
+            }
+            Err(e) => {
+                // 无法识别的文件按原始像素数据导入
+                eprintln!("打开图片失败: {:?}，改为原始数据导入", e);
+                self.open_raw_import(path);
+            }
+        }
+    }
+
+    fn open_raw_import(&mut self, path: &std::path::Path) {
+        match std::fs::read(path) {
+            Ok(data) => {
+                self.raw_import = Some(RawImportDialog::new(path.to_string_lossy().to_string(), data));
+                self.show_raw_import = true;
+            }
+            Err(e) => eprintln!("读取文件失败: {}", e),
+        }
+    }
+
+    // 打开命令行参数指定的图片；参数为 - 时读取标准输入，
+    // 写入临时文件后按普通文件打开，以便文件分析和帧浏览器也能使用
+    fn open_argument(&mut self, arg: &str) {
+        if arg != "-" {
+            self.open_image(std::path::Path::new(arg));
+            return;
+        }
+        let mut data = Vec::new();
+        if let Err(e) = std::io::Read::read_to_end(&mut std::io::stdin(), &mut data) {
+            eprintln!("读取标准输入失败: {}", e);
+            return;
+        }
+        match write_stdin_file(&data) {
+            Ok(path) => self.open_image(&path),
+            Err(e) => {
+                // 无法创建临时文件时直接在内存中解码
+                eprintln!("写入临时文件失败: {}", e);
+                self.open_memory(data);
+            }
+        }
+    }
+
+    // 从内存中的数据打开图像，无法识别时按原始像素数据导入
+    fn open_memory(&mut self, data: Vec<u8>) {
+        match image::load_from_memory(&data) {
+            Ok(img) => {
+                self.open_tab();
+                self.set_working_image(img);
+                self.doc.current_file_path = None;
+                self.doc.frame_browser = None;
+            }
+            Err(_) => {
+                self.raw_import = Some(RawImportDialog::new("标准输入".to_string(), data));
+                self.show_raw_import = true;
+            }
+        }
+    }
+
+    // 在新标签页中粘贴剪贴板中的图像（没有对应的文件，文件分析和帧浏览器不可用）
+    fn paste_image(&mut self, text: Option<&str>) {
+        match clipboard::paste_image(text) {
+            Ok(img) => {
+                self.open_tab();
+                self.set_working_image(image::DynamicImage::ImageRgba8(img));
+                self.doc.current_file_path = None;
+                self.doc.frame_browser = None;
+            }
+            Err(e) => eprintln!("粘贴图片失败: {}", e),
+        }
+    }
+
+    // ── 标签页 ─────────────────────────────
+
+    // 为新打开的图像准备标签页：当前标签页为空时直接使用，否则新建并切换过去
+    fn open_tab(&mut self) {
+        if self.documents.is_empty() {
+            self.documents.push(Document::default());
+        }
+        if self.doc.transform.is_some() {
+            self.documents.push(Document::default());
+            self.switch_tab(self.documents.len() - 1);
+        }
+    }
+
+    // 切换到第 index 个标签页：当前文档换回列表，目标文档换出
+    fn switch_tab(&mut self, index: usize) {
+        if index == self.active || index >= self.documents.len() {
+            return;
+        }
+        std::mem::swap(&mut self.doc, &mut self.documents[self.active]);
+        std::mem::swap(&mut self.doc, &mut self.documents[index]);
+        self.active = index;
+    }
+
+    fn close_tab(&mut self, index: usize) {
+        if index >= self.documents.len() {
+            return;
+        }
+        if self.documents.len() == 1 {
+            self.doc = Document::default();
+            return;
+        }
+        self.documents.remove(index);
+        if index == self.active {
+            self.active = self.active.min(self.documents.len() - 1);
+            self.doc = std::mem::take(&mut self.documents[self.active]);
+        } else if index < self.active {
+            self.active -= 1;
+        }
+    }
+
+    // 标签页标题（当前标签页的文档已换出，需要从 doc 读取）
+    fn tab_title(&self, index: usize) -> String {
+        let doc = if index == self.active { &self.doc } else { &self.documents[index] };
+        format!("{}. {}", index + 1, doc.title())
+    }
+
+    // 将第 index 个标签页的原图作为输入发送到当前标签页的合成器
+    fn send_to_combiner(&mut self, ctx: &Context, index: usize) {
+        let Some(doc) = self.documents.get(index).filter(|_| index != self.active) else {
+            return;
+        };
+        let (Some(transform), Some(combiner)) = (&doc.transform, &mut self.doc.combine_dialog) else {
+            return;
+        };
+        combiner.add_image(ctx, transform.get_original().clone(), doc.title());
+        self.doc.show_combine_dialog = true;
+    }
+
+    // 标签栏：点击切换，右键菜单可关闭或发送到当前图像的合成器
+    fn tabs_ui(&mut self, ctx: &Context, ui: &mut Ui) {
+        let mut switch = None;
+        let mut close = None;
+        let mut send = None;
+        ui.horizontal_wrapped(|ui| {
+            for index in 0..self.documents.len() {
+                let response = ui.selectable_label(index == self.active, self.tab_title(index));
+                if response.clicked() {
+                    switch = Some(index);
                 }
-                self.frame_browser = Some(framebrowser::FrameBrowser::new());
-                if let Some(browser) = &mut self.frame_browser {
-                    let _ = browser.load_frames(&self.current_file_path.as_ref().unwrap());
+                response.context_menu(|ui| {
+                    if ui
+                        .add_enabled(index != self.active, Button::new("作为第二张图像发送到当前合成器"))
+                        .clicked()
+                    {
+                        send = Some(index);
+                        ui.close_menu();
+                    }
+                    if ui.button("关闭").clicked() {
+                        close = Some(index);
+                        ui.close_menu();
+                    }
+                });
+                if ui.small_button("✕").clicked() {
+                    close = Some(index);
                 }
-                self.combine_dialog = Some(ImageCombiner::new(self.transform.as_ref().unwrap().get_image().clone()));
-                
+                ui.separator();
+            }
+            if ui.button("切换 (Ctrl+P)").clicked() {
+                self.show_switcher = true;
             }
-            Err(e) => eprintln!("打开图片失败: {:?}", e),
+        });
+        if let Some(index) = send {
+            self.send_to_combiner(ctx, index);
+        }
+        if let Some(index) = switch {
+            self.switch_tab(index);
+        }
+        if let Some(index) = close {
+            self.close_tab(index);
+        }
+    }
+
+    // 快速切换窗口：输入文字过滤标签页，回车切换到第一个匹配项
+    fn switcher_ui(&mut self, ctx: &Context) {
+        let mut open = self.show_switcher;
+        let mut switch = None;
+        Window::new("切换图像")
+            .open(&mut open)
+            .collapsible(false)
+            .anchor(Align2::CENTER_TOP, vec2(0.0, 60.0))
+            .show(ctx, |ui| {
+                let response = ui.text_edit_singleline(&mut self.switcher_query);
+                response.request_focus();
+                let query = self.switcher_query.to_lowercase();
+                let matches: Vec<usize> = (0..self.documents.len())
+                    .filter(|&index| self.tab_title(index).to_lowercase().contains(&query))
+                    .collect();
+                for &index in &matches {
+                    if ui.selectable_label(index == self.active, self.tab_title(index)).clicked() {
+                        switch = Some(index);
+                    }
+                }
+                if ui.input(|i| i.key_pressed(Key::Enter)) {
+                    switch = matches.first().copied();
+                }
+            });
+        // 消耗掉 Esc，避免同一帧里的选区也响应它
+        if ctx.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Escape)) {
+            open = false;
+        }
+        if let Some(index) = switch {
+            self.switch_tab(index);
+            open = false;
+        }
+        if !open {
+            self.switcher_query.clear();
+        }
+        self.show_switcher = open;
+    }
+
+    // 替换当前处理的图像，并重建依赖它的立体视图和合成器
+    fn set_working_image(&mut self, img: image::DynamicImage) {
+        self.doc.transform = Some(Transform::new(img));
+        self.doc.texture = None;
+        self.doc.zoom_level = 1.0;
+        self.doc.scroll_pos = Vec2::ZERO;
+        self.doc.selection = None;
+        self.doc.selection_anchor = None;
+        // 提取区域的坐标属于旧图像
+        if let Some(dialog) = &mut self.doc.extract_dialog {
+            dialog.region = None;
+        }
+        if let Some(t) = &self.doc.transform {
+            self.doc.stereo = Some(Stereo::new(t.get_image().clone()));
+        }
+        self.doc.combine_dialog = Some(ImageCombiner::new(self.doc.transform.as_ref().unwrap().get_image().clone()));
+    }
+
+    // 选区工具栏：坐标以及裁剪、复制、保存、设为提取区域和直方图
+    fn selection_ui(&mut self, ui: &mut Ui) {
+        let (Some(selection), Some(transform)) = (self.doc.selection, &self.doc.transform) else {
+            return;
+        };
+        let mut cropped = None;
+        ui.horizontal(|ui| {
+            ui.label(format!("选区: {}", selection.text()));
+            ui.separator();
+            if ui.button("裁剪").on_hover_text("将原图的选区部分作为新的工作图像").clicked() {
+                cropped = Some(selection.crop(transform.get_original()));
+            }
+            if ui.button("复制").on_hover_text("复制当前显示的选区图像").clicked() {
+                if let Err(e) = clipboard::copy_image(&selection.crop(transform.get_image())) {
+                    eprintln!("复制到剪贴板失败: {}", e);
+                }
+            }
+            if ui.button("保存").clicked() {
+                if let Some(path) = rfd::FileDialog::new().set_file_name("selection.png").save_file() {
+                    stereo::save_rgba_image(&selection.crop(transform.get_image()), path);
+                }
+            }
+            if ui.button("设为提取区域").clicked() {
+                self.doc.extract_dialog.get_or_insert_with(ExtractDialog::default).region = Some(selection);
+                self.doc.show_extract_dialog = true;
+            }
+            if ui.button("直方图").on_hover_text("原图选区的各通道直方图").clicked() {
+                let histogram = selection::Histogram::new(&selection.crop(transform.get_original()));
+                self.doc.histogram_view = Some(HistogramView::new(histogram, format!("选区 {}", selection.text())));
+                self.doc.show_histogram = true;
+            }
+            // 有其他窗口接收键盘输入时 Esc 留给它们
+            let modal_open = self.show_switcher || self.show_raw_import || self.show_about;
+            let escape = !modal_open && ui.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Escape));
+            if ui.button("取消选区").clicked() || escape {
+                self.doc.selection = None;
+            }
+        });
+        if let Some(cropped) = cropped {
+            self.set_working_image(image::DynamicImage::ImageRgba8(cropped));
         }
     }
 }
@@ -135,6 +490,13 @@ impl eframe::App for StegApp {
             }
         }
 
+        // Ctrl+V 粘贴图片（正在编辑文本时不拦截）
+        if let Some(text) = self.paste_shortcut.poll(ctx) {
+            if !ctx.wants_keyboard_input() {
+                self.paste_image(text.as_deref());
+            }
+        }
+
         TopBottomPanel::top("top_panel").show(ctx, |ui| {
             menu::bar(ui, |ui| {
                 // 文件菜单
@@ -145,8 +507,18 @@ impl eframe::App for StegApp {
                         }
                         ui.close_menu();    
                     }
+                    if ui.button("导入原始数据").clicked() {
+                        if let Some(path) = rfd::FileDialog::new().pick_file() {
+                            self.open_raw_import(&path);
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("粘贴图片 (Ctrl+V)").clicked() {
+                        self.paste_image(None);
+                        ui.close_menu();
+                    }
                     if ui.button("另存为").clicked() {
-                        if let Some(transform) = &self.transform {
+                        if let Some(transform) = &self.doc.transform {
                             if let Some(path) = rfd::FileDialog::new().save_file() {
                                 transform.get_image().save(path).unwrap();
                             }
@@ -159,28 +531,35 @@ impl eframe::App for StegApp {
                 // 分析菜单
                 ui.menu_button("分析", |ui| {
                     if ui.button("文件格式").clicked() {
-                        self.show_file_analysis = true;
+                        self.doc.show_file_analysis = true;
                         ui.close_menu();    
                     }
 
                     if ui.button("数据提取").clicked() {
-                        self.show_extract_dialog = true;
+                        self.doc.show_extract_dialog = true;
                         // 初始化数据提取对话框（仅在首次点击时创建）
-                        if self.extract_dialog.is_none() {
-                            self.extract_dialog = Some(ExtractDialog::default());
+                        if self.doc.extract_dialog.is_none() {
+                            self.doc.extract_dialog = Some(ExtractDialog::default());
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("数据嵌入").clicked() {
+                        self.doc.show_embed_dialog = true;
+                        if self.doc.embed_dialog.is_none() {
+                            self.doc.embed_dialog = Some(EmbedDialog::default());
                         }
                         ui.close_menu();
                     }
                     if ui.button("立体视图").clicked() {
-                        self.show_stereo_dialog = true;
+                        self.doc.show_stereo_dialog = true;
                         ui.close_menu();
                     }
                     if ui.button("帧浏览器").clicked() {
-                        self.show_frame_browser = true;
+                        self.doc.show_frame_browser = true;
                         ui.close_menu();
                     }
                     if ui.button("图像合成器").clicked() {
-                        self.show_combine_dialog = true;
+                        self.doc.show_combine_dialog = true;
                         ui.close_menu();
                     }
                 });
@@ -195,37 +574,93 @@ impl eframe::App for StegApp {
             });
         });
 
+        // Ctrl+Tab / Ctrl+Shift+Tab 切换标签页，Ctrl+P 打开快速切换
+        if self.documents.len() > 1 {
+            // 先匹配带 Shift 的组合，否则 Ctrl+Tab 会同时匹配 Ctrl+Shift+Tab
+            let (previous, next) = ctx.input_mut(|i| {
+                (
+                    i.consume_key(Modifiers::COMMAND | Modifiers::SHIFT, Key::Tab),
+                    i.consume_key(Modifiers::COMMAND, Key::Tab),
+                )
+            });
+            let count = self.documents.len();
+            if next {
+                self.switch_tab((self.active + 1) % count);
+            }
+            if previous {
+                self.switch_tab((self.active + count - 1) % count);
+            }
+            if ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND, Key::P)) {
+                self.show_switcher = true;
+            }
+        }
+
+        if self.documents.len() > 1 {
+            TopBottomPanel::top("tabs").show(ctx, |ui| {
+                self.tabs_ui(ctx, ui);
+            });
+        }
+
+        if self.show_switcher {
+            self.switcher_ui(ctx);
+        }
+
         CentralPanel::default().show(ctx, |ui| {
             ScrollArea::both()
                 .id_salt("image_scroll")
-                .scroll_offset(self.scroll_pos)
+                .scroll_offset(self.doc.scroll_pos)
                 .show(ui, |ui| {
-                    if let Some(transform) = &self.transform {
-                        if self.texture.is_none() {
+                    if let Some(transform) = &self.doc.transform {
+                        if self.doc.texture.is_none() {
                             let rgba_image = transform.get_image();
                             let size = [rgba_image.width() as usize, rgba_image.height() as usize];
                             let image_data = ColorImage::from_rgba_unmultiplied(
                                 size,
                                 rgba_image.as_raw(),
                             );
-                            self.texture = Some(ui.ctx().load_texture(
+                            self.doc.texture = Some(ui.ctx().load_texture(
                                 "image",
                                 image_data,
                                 TextureOptions::default()
                             ));
                         }
 
-                        if let Some(texture) = &self.texture {
-                            let desired_size = texture.size_vec2() * self.zoom_level;
+                        if let Some(texture) = &self.doc.texture {
+                            let desired_size = texture.size_vec2() * self.doc.zoom_level;
                             let (rect, response) = ui.allocate_exact_size(
                                 desired_size,
                                 Sense::drag(),
                             );
-                            
-                            // 处理拖拽滚动
+
+                            // 屏幕坐标转换为像素坐标
+                            let (width, height) = transform.get_image().dimensions();
+                            let zoom = self.doc.zoom_level;
+                            let to_pixel = |pos: Pos2| {
+                                let p = (pos - rect.min) / zoom;
+                                (
+                                    (p.x.max(0.0) as u32).min(width.saturating_sub(1)),
+                                    (p.y.max(0.0) as u32).min(height.saturating_sub(1)),
+                                )
+                            };
+
+                            // 框选模式或按住 Shift 时拖动框选，否则拖拽滚动
+                            if response.drag_started() && (self.select_mode || ui.input(|i| i.modifiers.shift)) {
+                                // drag_started 在超过拖动阈值后才触发，起点取按下时的位置
+                                self.doc.selection_anchor = ui.input(|i| i.pointer.press_origin()).map(to_pixel);
+                            }
                             if response.dragged() {
-                                let delta = response.drag_delta();
-                                self.scroll_pos -= delta;
+                                match (self.doc.selection_anchor, response.interact_pointer_pos()) {
+                                    (Some(anchor), Some(pos)) => {
+                                        self.doc.selection = Some(Selection::from_corners(anchor, to_pixel(pos), width, height));
+                                    }
+                                    _ => {
+                                        let delta = response.drag_delta();
+                                        self.doc.scroll_pos -= delta;
+                                    }
+                                }
+                            }
+                            if response.drag_stopped() {
+                                self.doc.selection_anchor = None;
                             }
                             
                             // 居中显示图片
@@ -236,13 +671,24 @@ impl eframe::App for StegApp {
                                 Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)),
                                 Color32::WHITE,
                             );
+
+                            if let Some(selection) = self.doc.selection {
+                                let min = rect.min + vec2(selection.x as f32, selection.y as f32) * zoom;
+                                let size = vec2(selection.width as f32, selection.height as f32) * zoom;
+                                painter.rect_stroke(
+                                    Rect::from_min_size(min, size),
+                                    0.0,
+                                    Stroke::new(1.5, Color32::YELLOW),
+                                    StrokeKind::Middle,
+                                );
+                            }
                         }
                     }
                 });
         });
 
-        if self.show_file_analysis {
-            if let Some(file_path) = &self.current_file_path {
+        if self.doc.show_file_analysis {
+            if let Some(file_path) = &self.doc.current_file_path {
                 let viewport_id = ViewportId::from_hash_of("file_analysis");
                 let viewport = ViewportBuilder::default()
                     .with_title("文件分析")
@@ -272,20 +718,20 @@ impl eframe::App for StegApp {
                 );
 
                 if should_close {
-                    self.show_file_analysis = false;
+                    self.doc.show_file_analysis = false;
                 }
 
             }
         }
 
-        if self.show_extract_dialog {
-            if let Some(transform) = &self.transform {
+        if self.doc.show_extract_dialog {
+            if let Some(transform) = &self.doc.transform {
                 let viewport_id = ViewportId::from_hash_of("extract_dialog");
                 let viewport = ViewportBuilder::default()
                     .with_title("数据提取")
                     .with_resizable(true)
                     //自动调整大小
-                    .with_inner_size([810.0, 430.0])
+                    .with_inner_size([810.0, 640.0])
                     .with_decorations(true);
         
                 // 临时变量跟踪关闭状态
@@ -302,8 +748,14 @@ impl eframe::App for StegApp {
                             }
         
                             // 正常绘制对话框内容
-                            if let Some(dialog) = self.extract_dialog.as_mut() {
-                                if dialog.ui(ui, transform.get_image()) {
+                            let frames = self
+                                .doc
+                                .frame_browser
+                                .as_ref()
+                                .map(|browser| browser.frame_images())
+                                .unwrap_or_default();
+                            if let Some(dialog) = self.doc.extract_dialog.as_mut() {
+                                if dialog.ui(ui, transform.get_image(), &frames) {
                                     should_close = true;
                                 }
                             }
@@ -317,15 +769,54 @@ impl eframe::App for StegApp {
 
                 // 同步关闭状态到主程序
                 if should_close  {
-                    self.show_extract_dialog = false;
+                    self.doc.show_extract_dialog = false;
                 }
             }
         }
 
 
+        if self.doc.show_embed_dialog {
+            if let Some(transform) = &self.doc.transform {
+                let viewport_id = ViewportId::from_hash_of("embed_dialog");
+                let viewport = ViewportBuilder::default()
+                    .with_title("数据嵌入")
+                    .with_resizable(true)
+                    .with_inner_size([810.0, 560.0])
+                    .with_decorations(true);
+
+                let mut should_close = false;
+
+                ctx.show_viewport_immediate(
+                    viewport_id,
+                    viewport,
+                    |ctx, _class| {
+                        CentralPanel::default().show(ctx, |ui| {
+                            if ctx.input(|i| i.viewport().close_requested()) {
+                                should_close = true;
+                            }
 
-        if self.show_stereo_dialog {
-            if let Some(_transform) = &self.transform {
+                            // 嵌入到原始图像而不是当前显示的变换结果
+                            if let Some(dialog) = self.doc.embed_dialog.as_mut() {
+                                if dialog.ui(ui, transform.get_original()) {
+                                    should_close = true;
+                                }
+                            }
+                        });
+
+                        if should_close {
+                            ctx.send_viewport_cmd(ViewportCommand::Close);
+                        }
+                    },
+                );
+
+                if should_close {
+                    self.doc.show_embed_dialog = false;
+                }
+            }
+        }
+
+        if self.doc.show_stereo_dialog {
+            if let Some(_transform) = &self.doc.transform {
                 let viewport_id = ViewportId::from_hash_of("stereo_dialog");
                 let viewport = ViewportBuilder::default()
                     .with_title("立体图分析")
@@ -344,7 +835,7 @@ impl eframe::App for StegApp {
                                 should_close = true;
                             }
 
-                            if let Some(stereo) = self.stereo.as_mut() {
+                            if let Some(stereo) = self.doc.stereo.as_mut() {
                                 stereo.update(ctx, ui);
                             }
                         });
@@ -356,14 +847,14 @@ impl eframe::App for StegApp {
                 );
 
                 if should_close {
-                    self.show_stereo_dialog = false;
+                    self.doc.show_stereo_dialog = false;
                 }
             }
 
         }
 
-        if self.show_frame_browser {
-            if let Some(browser) = &mut self.frame_browser {
+        if self.doc.show_frame_browser {
+            if let Some(browser) = &mut self.doc.frame_browser {
                 let viewport_id = ViewportId::from_hash_of("frame_browser");
                 let viewport = ViewportBuilder::default()
                     .with_title("帧浏览器")
@@ -392,12 +883,12 @@ impl eframe::App for StegApp {
                 );
 
                 if should_close {
-                    self.show_frame_browser = false;
+                    self.doc.show_frame_browser = false;
                 }
             }
         }
 
-        if self.show_combine_dialog {
+        if self.doc.show_combine_dialog {
             let viewport_id = ViewportId::from_hash_of("combine_dialog");
             let viewport = ViewportBuilder::default()
                 .with_title("图像合成器")
@@ -416,8 +907,14 @@ impl eframe::App for StegApp {
                             should_close = true;
                         }
         
-                        if let Some(combiner) = &mut self.combine_dialog {
-                            combiner.update(ui);
+                        let frames = self
+                            .doc
+                            .frame_browser
+                            .as_ref()
+                            .map(|browser| browser.frame_images())
+                            .unwrap_or_default();
+                        if let Some(combiner) = &mut self.doc.combine_dialog {
+                            combiner.update(ui, &frames);
                         }
                     });
         
@@ -429,10 +926,65 @@ impl eframe::App for StegApp {
         
             if should_close {
                 // 在关闭窗口时重置状态
-                if let Some(combiner) = &mut self.combine_dialog {
+                if let Some(combiner) = &mut self.doc.combine_dialog {
                     combiner.reset();
                 }
-                self.show_combine_dialog = false;
+                self.doc.show_combine_dialog = false;
+            }
+        }
+
+        if self.show_raw_import {
+            let viewport_id = ViewportId::from_hash_of("raw_import");
+            let viewport = ViewportBuilder::default()
+                .with_title("原始数据导入")
+                .with_resizable(true)
+                .with_inner_size([700.0, 600.0])
+                .with_decorations(true);
+
+            let mut should_close = false;
+            let mut imported = None;
+
+            ctx.show_viewport_immediate(
+                viewport_id,
+                viewport,
+                |ctx, _class| {
+                    CentralPanel::default().show(ctx, |ui| {
+                        if ctx.input(|i| i.viewport().close_requested()) {
+                            should_close = true;
+                        }
+
+                        if let Some(dialog) = self.raw_import.as_mut() {
+                            imported = dialog.ui(ui);
+                        }
+                    });
+
+                    if should_close {
+                        ctx.send_viewport_cmd(ViewportCommand::Close);
+                    }
+                },
+            );
+
+            // 导入的图像作为新的工作图像，文件分析仍指向原始数据文件
+            if let Some(img) = imported {
+                self.open_tab();
+                self.set_working_image(image::DynamicImage::ImageRgba8(img));
+                self.doc.current_file_path = self.raw_import.as_ref().map(|dialog| dialog.source.clone());
+                self.doc.frame_browser = None;
+                should_close = true;
+            }
+            if should_close {
+                self.show_raw_import = false;
+            }
+        }
+
+        if self.doc.show_histogram {
+            if let Some(view) = &mut self.doc.histogram_view {
+                Window::new("选区直方图")
+                    .open(&mut self.doc.show_histogram)
+                    .resizable(true)
+                    .show(ctx, |ui| {
+                        view.ui(ui);
+                    });
             }
         }
 
@@ -450,6 +1002,7 @@ impl eframe::App for StegApp {
 
 
         TopBottomPanel::bottom("controls").show(ctx, |ui| {
+            self.selection_ui(ui);
             ui.horizontal(|ui| {
                 // 处理鼠标滚轮和上下键
                 let mut zoom_delta = 0.0;
@@ -474,10 +1027,10 @@ impl eframe::App for StegApp {
                     }
                 });
 
-                self.zoom_level = (self.zoom_level + zoom_delta).clamp(0.1, 5.0);
+                self.doc.zoom_level = (self.doc.zoom_level + zoom_delta).clamp(0.1, 5.0);
 
                 // 缩放控制
-                ui.add(Slider::new(&mut self.zoom_level, 0.1..=5.0).text("缩放"));
+                ui.add(Slider::new(&mut self.doc.zoom_level, 0.1..=5.0).text("缩放"));
 
                 // 导航按钮
                 ui.separator();
@@ -494,7 +1047,7 @@ impl eframe::App for StegApp {
                 let left_clicked = ui.add(left_button).clicked();
                 let right_clicked = ui.add(right_button).clicked();
                 
-                if let Some(transform) = &self.transform {
+                if let Some(transform) = &self.doc.transform {
                     ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
                         ui.set_min_width(200.0);
                         ui.label(format!("通道: {}", transform.get_text()));
@@ -502,21 +1055,24 @@ impl eframe::App for StegApp {
                 }
 
                 if left_clicked || left_key_pressed {
-                    if let Some(transform) = &mut self.transform {
+                    if let Some(transform) = &mut self.doc.transform {
                         transform.back();
-                        self.texture = None;
-                        self.current_channel_text = transform.get_text();
+                        self.doc.texture = None;
+                        self.doc.current_channel_text = transform.get_text();
                     }
                 }
                 
                 if right_clicked || right_key_pressed {
-                    if let Some(transform) = &mut self.transform {
+                    if let Some(transform) = &mut self.doc.transform {
                         transform.forward();
-                        self.texture = None;
-                        self.current_channel_text = transform.get_text();
+                        self.doc.texture = None;
+                        self.doc.current_channel_text = transform.get_text();
                     }
                 }
 
+                ui.separator();
+                ui.checkbox(&mut self.select_mode, "框选").on_hover_text("拖动图像框选区域（也可按住 Shift 拖动）");
+
                 // 文件操作
                 ui.separator();
                 if ui.button("打开").clicked() {
@@ -525,7 +1081,7 @@ impl eframe::App for StegApp {
                     }
                 }
                 if ui.button("另存为").clicked() {
-                    if let Some(transform) = &self.transform {
+                    if let Some(transform) = &self.doc.transform {
                         if let Some(path) = rfd::FileDialog::new().save_file() {
                             let img = transform.get_image();
                             img.save(path).unwrap();
@@ -0,0 +1,432 @@
+// ──────────────────────────────
+// 逐像素表达式：合成时按通道计算 r/g/b/a 的值
+//
+// 语法示例：
+//   r = (a.r ^ b.g) & 1 ? 255 : 0; g = r; b = r
+// 可用变量：a.r a.g a.b a.a a.l（亮度）、b.*（同上）、x、y，以及已赋值的 r g b a
+// 运算符与 C 相同：?: || && | ^ & == != < <= > >= << >> + - * / % 一元 - ! ~
+// 函数：min(x, y) max(x, y) abs(x)
+// 未赋值的 r/g/b 取 a 图像的值，未赋值的 a 为 255，结果截断到 0..=255
+
+/// 表达式中可读取的像素值
+pub struct PixelVars {
+    /// 第一张（累积结果）与第二张图像的 [r, g, b, a]
+    pub a: [u8; 4],
+    pub b: [u8; 4],
+    pub x: u32,
+    pub y: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(i64),
+    Ident(String),
+    Op(&'static str),
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Num(i64),
+    /// 图像通道：(0 = a / 1 = b, 通道号，4 为亮度)
+    Pixel(usize, usize),
+    /// 已赋值的输出通道
+    Output(usize),
+    X,
+    Y,
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Ternary(Box<Expr>, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+/// 编译后的表达式程序，由若干条通道赋值组成
+#[derive(Debug, Clone)]
+pub struct PixelProgram {
+    assignments: Vec<(usize, Expr)>,
+}
+
+// 按优先级从低到高排列的二元运算符
+const BINARY_LEVELS: &[&[&str]] = &[
+    &["||"],
+    &["&&"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["==", "!="],
+    &["<", "<=", ">", ">="],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+// 按长度从长到短排列，保证优先匹配双字符运算符
+const OPERATORS: &[&str] = &[
+    "||", "&&", "==", "!=", "<=", ">=", "<<", ">>", "|", "^", "&", "<", ">", "+", "-", "*", "/", "%", "!", "~", "?",
+    ":", "(", ")", ",", "=", ";", ".",
+];
+
+fn channel_index(name: &str) -> Option<usize> {
+    match name {
+        "r" => Some(0),
+        "g" => Some(1),
+        "b" => Some(2),
+        "a" => Some(3),
+        _ => None,
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            tokens.push(Token::Op(";"));
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = if let Some(hex) = text.strip_prefix("0x") {
+                i64::from_str_radix(hex, 16)
+            } else if let Some(bin) = text.strip_prefix("0b") {
+                i64::from_str_radix(bin, 2)
+            } else {
+                text.parse()
+            };
+            tokens.push(Token::Num(value.map_err(|_| format!("无效的数字: {}", text))?));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| format!("无法识别的字符: {}", c))?;
+            tokens.push(Token::Op(op));
+            i += op.len();
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// 已赋值的输出通道，之后的表达式可以引用
+    assigned: [bool; 4],
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        if self.peek() == Some(&Token::Op(Self::static_op(op))) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), String> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            Err(format!("缺少 '{}'", op))
+        }
+    }
+
+    fn static_op(op: &str) -> &'static str {
+        OPERATORS.iter().find(|o| **o == op).copied().unwrap_or("")
+    }
+
+    fn program(&mut self) -> Result<PixelProgram, String> {
+        let mut assignments = Vec::new();
+        while self.peek().is_some() {
+            if self.eat(";") {
+                continue;
+            }
+            let channel = match self.next() {
+                Some(Token::Ident(name)) => channel_index(&name).ok_or_else(|| format!("未知的输出通道: {}", name))?,
+                other => return Err(format!("应为输出通道 r/g/b/a，实际为 {:?}", other)),
+            };
+            self.expect("=")?;
+            let expr = self.ternary()?;
+            if !matches!(self.peek(), None | Some(Token::Op(";"))) {
+                return Err(format!("表达式后有多余内容: {:?}", self.peek().unwrap()));
+            }
+            self.assigned[channel] = true;
+            assignments.push((channel, expr));
+        }
+        if assignments.is_empty() {
+            return Err("表达式为空".to_string());
+        }
+        Ok(PixelProgram { assignments })
+    }
+
+    fn ternary(&mut self) -> Result<Expr, String> {
+        let cond = self.binary(0)?;
+        if self.eat("?") {
+            let then = self.ternary()?;
+            self.expect(":")?;
+            let otherwise = self.ternary()?;
+            return Ok(Expr::Ternary(Box::new(cond), Box::new(then), Box::new(otherwise)));
+        }
+        Ok(cond)
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, String> {
+        if level == BINARY_LEVELS.len() {
+            return self.unary();
+        }
+        let mut left = self.binary(level + 1)?;
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            if !BINARY_LEVELS[level].contains(&op) {
+                break;
+            }
+            self.pos += 1;
+            let right = self.binary(level + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        for op in ["-", "!", "~"] {
+            if self.eat(op) {
+                return Ok(Expr::Unary(Self::static_op(op), Box::new(self.unary()?)));
+            }
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Num(value)) => Ok(Expr::Num(value)),
+            Some(Token::Op("(")) => {
+                let expr = self.ternary()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) => {
+                if self.eat(".") {
+                    let image = match name.as_str() {
+                        "a" => 0,
+                        "b" => 1,
+                        _ => return Err(format!("未知的图像: {}（应为 a 或 b）", name)),
+                    };
+                    let channel = match self.next() {
+                        Some(Token::Ident(c)) if c == "l" => 4,
+                        Some(Token::Ident(c)) => channel_index(&c).ok_or_else(|| format!("未知的通道: {}", c))?,
+                        other => return Err(format!("应为通道名，实际为 {:?}", other)),
+                    };
+                    return Ok(Expr::Pixel(image, channel));
+                }
+                if self.eat("(") {
+                    let mut args = Vec::new();
+                    if !self.eat(")") {
+                        loop {
+                            args.push(self.ternary()?);
+                            if self.eat(")") {
+                                break;
+                            }
+                            self.expect(",")?;
+                        }
+                    }
+                    let arity = match name.as_str() {
+                        "min" | "max" => 2,
+                        "abs" => 1,
+                        _ => return Err(format!("未知的函数: {}", name)),
+                    };
+                    if args.len() != arity {
+                        return Err(format!("{} 需要 {} 个参数", name, arity));
+                    }
+                    return Ok(Expr::Call(name, args));
+                }
+                match name.as_str() {
+                    "x" => Ok(Expr::X),
+                    "y" => Ok(Expr::Y),
+                    _ => match channel_index(&name) {
+                        Some(c) if self.assigned[c] => Ok(Expr::Output(c)),
+                        Some(_) => Err(format!("通道 {} 尚未赋值，读取图像通道请使用 a.{} 或 b.{}", name, name, name)),
+                        None => Err(format!("未知的变量: {}", name)),
+                    },
+                }
+            }
+            Some(token) => Err(format!("意外的符号: {:?}", token)),
+            None => Err("表达式意外结束".to_string()),
+        }
+    }
+}
+
+fn luminance(p: [u8; 4]) -> i64 {
+    (299 * p[0] as i64 + 587 * p[1] as i64 + 114 * p[2] as i64) / 1000
+}
+
+fn eval(expr: &Expr, vars: &PixelVars, out: &[i64; 4]) -> i64 {
+    match expr {
+        Expr::Num(v) => *v,
+        Expr::Pixel(image, channel) => {
+            let p = if *image == 0 { vars.a } else { vars.b };
+            if *channel == 4 { luminance(p) } else { p[*channel] as i64 }
+        }
+        Expr::Output(c) => out[*c],
+        Expr::X => vars.x as i64,
+        Expr::Y => vars.y as i64,
+        Expr::Unary(op, e) => {
+            let v = eval(e, vars, out);
+            match *op {
+                "-" => v.wrapping_neg(),
+                "!" => (v == 0) as i64,
+                _ => !v,
+            }
+        }
+        Expr::Binary(op, l, r) => {
+            let l = eval(l, vars, out);
+            // 逻辑运算短路求值
+            match *op {
+                "&&" => return (l != 0 && eval(r, vars, out) != 0) as i64,
+                "||" => return (l != 0 || eval(r, vars, out) != 0) as i64,
+                _ => {}
+            }
+            let r = eval(r, vars, out);
+            match *op {
+                "|" => l | r,
+                "^" => l ^ r,
+                "&" => l & r,
+                "==" => (l == r) as i64,
+                "!=" => (l != r) as i64,
+                "<" => (l < r) as i64,
+                "<=" => (l <= r) as i64,
+                ">" => (l > r) as i64,
+                ">=" => (l >= r) as i64,
+                "<<" => l.wrapping_shl(r.clamp(0, 63) as u32),
+                ">>" => l.wrapping_shr(r.clamp(0, 63) as u32),
+                "+" => l.wrapping_add(r),
+                "-" => l.wrapping_sub(r),
+                "*" => l.wrapping_mul(r),
+                // 除以 0 结果为 0
+                "/" => l.checked_div(r).unwrap_or(0),
+                _ => l.checked_rem(r).unwrap_or(0),
+            }
+        }
+        Expr::Ternary(c, t, f) => {
+            if eval(c, vars, out) != 0 { eval(t, vars, out) } else { eval(f, vars, out) }
+        }
+        Expr::Call(name, args) => {
+            let v: Vec<i64> = args.iter().map(|a| eval(a, vars, out)).collect();
+            match name.as_str() {
+                "min" => v[0].min(v[1]),
+                "max" => v[0].max(v[1]),
+                _ => v[0].wrapping_abs(),
+            }
+        }
+    }
+}
+
+impl PixelProgram {
+    /// 编译表达式，出错时返回说明
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        Parser {
+            tokens,
+            pos: 0,
+            assigned: [false; 4],
+        }
+        .program()
+    }
+
+    /// 计算一个像素
+    pub fn eval(&self, vars: &PixelVars) -> [u8; 4] {
+        let mut out = [vars.a[0] as i64, vars.a[1] as i64, vars.a[2] as i64, 255];
+        for (channel, expr) in &self.assignments {
+            out[*channel] = eval(expr, vars, &out);
+        }
+        out.map(|v| v.clamp(0, 255) as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(a: [u8; 4], b: [u8; 4]) -> PixelVars {
+        PixelVars { a, b, x: 5, y: 9 }
+    }
+
+    fn eval(source: &str, a: [u8; 4], b: [u8; 4]) -> [u8; 4] {
+        PixelProgram::parse(source).unwrap().eval(&vars(a, b))
+    }
+
+    #[test]
+    fn default_expression_xors_lsbs() {
+        let source = "r = (a.r ^ b.r) & 1 ? 255 : 0; g = r; b = r";
+        assert_eq!(eval(source, [3, 0, 0, 0], [2, 0, 0, 0]), [255, 255, 255, 255]);
+        assert_eq!(eval(source, [3, 0, 0, 0], [1, 0, 0, 0]), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn unassigned_channels_keep_first_image() {
+        assert_eq!(eval("a = 7", [10, 20, 30, 40], [0; 4]), [10, 20, 30, 7]);
+        assert_eq!(eval("g = b.g\nb = x + y", [10, 20, 30, 40], [1, 2, 3, 4]), [10, 2, 14, 255]);
+    }
+
+    #[test]
+    fn operators_follow_c_precedence() {
+        assert_eq!(eval("r = 1 + 2 * 3", [0; 4], [0; 4])[0], 7);
+        assert_eq!(eval("r = (1 + 2) * 3", [0; 4], [0; 4])[0], 9);
+        assert_eq!(eval("r = 1 << 2 + 1", [0; 4], [0; 4])[0], 8);
+        assert_eq!(eval("r = 6 & 3 == 3", [0; 4], [0; 4])[0], 0);
+        assert_eq!(eval("r = 0 ? 1 : 2 ? 3 : 4", [0; 4], [0; 4])[0], 3);
+        assert_eq!(eval("r = -3 + !0 + ~-8", [0; 4], [0; 4])[0], 5);
+        assert_eq!(eval("r = 0x10 | 0b11", [0; 4], [0; 4])[0], 19);
+    }
+
+    #[test]
+    fn results_are_clamped_and_division_by_zero_is_zero() {
+        assert_eq!(eval("r = 300; g = -5; b = 7 / 0; a = 7 % 0", [0; 4], [0; 4]), [255, 0, 0, 0]);
+    }
+
+    #[test]
+    fn functions_and_luminance() {
+        let source = "r = min(a.r, b.r); g = max(a.g, b.g); b = abs(a.b - b.b)";
+        assert_eq!(eval(source, [9, 9, 9, 0], [4, 12, 20, 0]), [4, 12, 11, 255]);
+        assert_eq!(eval("r = a.l", [255, 255, 255, 0], [0; 4])[0], 255);
+        assert_eq!(eval("r = b.l", [0; 4], [0, 255, 0, 0])[0], 149);
+    }
+
+    #[test]
+    fn outputs_can_be_reused_after_assignment() {
+        assert_eq!(eval("r = a.r * 2; g = r + 1; b = g + r", [10, 0, 0, 0], [0; 4]), [20, 21, 41, 255]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let sources = [
+            "", "r =", "q = 1", "r = g", "r = c.r", "r = a.q", "r = foo(1)", "r = min(1)", "r = (1", "r = 1 2",
+            "r = 1 $ 2", "r = 0xzz",
+        ];
+        for source in sources {
+            assert!(PixelProgram::parse(source).is_err(), "{:?} 应当解析失败", source);
+        }
+    }
+}
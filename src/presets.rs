@@ -0,0 +1,306 @@
+use crate::extractanlysis::{BitOrder, ExtractDirection, ExtractSettings, LengthHeader, RgbOrder};
+use eframe::egui;
+use egui::Ui;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// ──────────────────────────────
+// 提取预设：内置常见工具的参数组合，用户预设以 JSON 形式保存在配置目录中
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ExtractPreset {
+    pub name: String,
+    pub settings: ExtractSettings,
+}
+
+// 通道序号：与 ExtractSettings::channel_selections 的顺序一致
+const R: usize = 0;
+const G: usize = 1;
+const B: usize = 2;
+const A: usize = 3;
+
+/// 构造预设，bits 为 (通道序号, 选中的位号列表)
+fn preset(
+    name: &str,
+    bits: &[(usize, &[usize])],
+    direction: ExtractDirection,
+    bit_order: BitOrder,
+    rgb_order: RgbOrder,
+    length_header: LengthHeader,
+) -> ExtractPreset {
+    let mut settings = ExtractSettings {
+        extract_direction: direction,
+        bit_order,
+        rgb_order,
+        length_header,
+        ..Default::default()
+    };
+    for &(channel, channel_bits) in bits {
+        for &bit in channel_bits {
+            // 数组索引0对应最高位（7）
+            settings.channel_selections[channel].bits[7 - bit] = true;
+        }
+    }
+    ExtractPreset {
+        name: name.to_string(),
+        settings,
+    }
+}
+
+/// 内置预设
+/// zsteg 的 "lsb" 指通道内从低位读起；本工具 LSB 优先时 RGB 顺序会反向（与原版 StegSolve 一致），
+/// 因此 zsteg 的 rgb,lsb 对应 LSB优先 + BGR。LSBSteg 基于 OpenCV，通道顺序为 BGR。
+pub fn builtin_presets() -> Vec<ExtractPreset> {
+    use BitOrder::*;
+    use ExtractDirection::*;
+    use LengthHeader as H;
+    use RgbOrder::*;
+
+    let rgb0: &[(usize, &[usize])] = &[(R, &[0]), (G, &[0]), (B, &[0])];
+    let rgb01: &[(usize, &[usize])] = &[(R, &[0, 1]), (G, &[0, 1]), (B, &[0, 1])];
+    let rgba0: &[(usize, &[usize])] = &[(R, &[0]), (G, &[0]), (B, &[0]), (A, &[0])];
+
+    vec![
+        preset("RGB LSB 按行 MSB优先", rgb0, Row, MSBFirst, RGB, H::None),
+        preset("Alpha 第0位", &[(A, &[0])], Row, MSBFirst, RGB, H::None),
+        preset("BGR 第0-1位 按列", rgb01, Column, MSBFirst, BGR, H::None),
+        preset("zsteg b1,r,lsb,xy", &[(R, &[0])], Row, MSBFirst, RGB, H::None),
+        preset("zsteg b1,g,lsb,xy", &[(G, &[0])], Row, MSBFirst, RGB, H::None),
+        preset("zsteg b1,b,lsb,xy", &[(B, &[0])], Row, MSBFirst, RGB, H::None),
+        preset("zsteg b1,rgb,lsb,xy", rgb0, Row, MSBFirst, RGB, H::None),
+        preset("zsteg b1,bgr,lsb,xy", rgb0, Row, MSBFirst, BGR, H::None),
+        preset("zsteg b1,abgr,lsb,xy", rgba0, Row, MSBFirst, BGR, H::None),
+        preset("zsteg b1,rgb,lsb,yx", rgb0, Column, MSBFirst, RGB, H::None),
+        preset("zsteg b2,rgb,lsb,xy", rgb01, Row, LSBFirst, BGR, H::None),
+        preset("zsteg b2,rgb,msb,xy", rgb01, Row, MSBFirst, RGB, H::None),
+        preset("LSBSteg 文本 (BGR 第0位, 16位长度)", rgb0, Row, MSBFirst, BGR, H::U16),
+        preset("LSBSteg 二进制 (BGR 第0位, 64位长度)", rgb0, Row, MSBFirst, BGR, H::U64),
+        preset("stegano lsb.reveal (RGB 第0位, \"长度:\" 前缀)", rgb0, Row, MSBFirst, RGB, H::None),
+    ]
+}
+
+/// 用户预设文件路径
+pub fn presets_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("stegsolve-rs").join("extract_presets.json"))
+}
+
+/// 读取用户预设，文件不存在或解析失败时返回空列表
+pub fn load_user_presets(path: &Path) -> Vec<ExtractPreset> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            eprintln!("解析预设文件失败: {}", e);
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// 保存用户预设
+pub fn save_user_presets(path: &Path, presets: &[ExtractPreset]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(presets).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
+
+/// 预设选择与管理控件（提取与嵌入对话框共用）
+pub struct PresetManager {
+    /// 用户预设文件；为 None 时用户预设只保存在内存中
+    path: Option<PathBuf>,
+    user_presets: Vec<ExtractPreset>,
+    builtin: Vec<ExtractPreset>,
+    /// 当前选中的预设：(是否内置, 序号)
+    selected: Option<(bool, usize)>,
+    /// 新预设名称
+    new_name: String,
+    status: String,
+}
+
+impl Default for PresetManager {
+    fn default() -> Self {
+        Self::new(presets_path())
+    }
+}
+
+impl PresetManager {
+    /// 使用指定的用户预设文件；path 为 None 时不读写磁盘
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            user_presets: path.as_deref().map(load_user_presets).unwrap_or_default(),
+            path,
+            builtin: builtin_presets(),
+            selected: None,
+            new_name: String::new(),
+            status: String::new(),
+        }
+    }
+
+    fn selected_preset(&self) -> Option<&ExtractPreset> {
+        match self.selected {
+            Some((true, i)) => self.builtin.get(i),
+            Some((false, i)) => self.user_presets.get(i),
+            None => None,
+        }
+    }
+
+    /// 绘制预设控件，选中并应用预设时更新 settings 并返回 true
+    pub fn ui(&mut self, ui: &mut Ui, id: &str, settings: &mut ExtractSettings) -> bool {
+        let mut applied = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.label("预设:");
+            let selected_text = self
+                .selected_preset()
+                .map(|p| p.name.clone())
+                .unwrap_or_else(|| "选择预设".to_string());
+            let combo = egui::ComboBox::from_id_salt((id, "preset_combo"))
+                .width(260.0)
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    ui.label("内置");
+                    for (i, preset) in self.builtin.iter().enumerate() {
+                        ui.selectable_value(&mut self.selected, Some((true, i)), &preset.name);
+                    }
+                    if !self.user_presets.is_empty() {
+                        ui.separator();
+                        ui.label("自定义");
+                        for (i, preset) in self.user_presets.iter().enumerate() {
+                            ui.selectable_value(&mut self.selected, Some((false, i)), &preset.name);
+                        }
+                    }
+                });
+            // 打开列表时重新读取，显示其他对话框或标签页中保存的预设
+            if combo.response.clicked() {
+                self.reload();
+            }
+
+            if ui.button("应用").clicked() {
+                if let Some(preset) = self.selected_preset() {
+                    // 保留当前变换链，预设只描述位平面与提取选项时不覆盖它
+                    let chain = std::mem::take(&mut settings.transform_chain);
+                    *settings = preset.settings.clone();
+                    if settings.transform_chain.is_empty() {
+                        settings.transform_chain = chain;
+                    }
+                    applied = true;
+                }
+            }
+
+            let is_user = matches!(self.selected, Some((false, _)));
+            if ui.add_enabled(is_user, egui::Button::new("删除")).clicked() {
+                if let Some((false, i)) = self.selected {
+                    let name = self.user_presets[i].name.clone();
+                    self.selected = None;
+                    self.persist(|presets| presets.retain(|p| p.name != name));
+                }
+            }
+
+            ui.separator();
+            ui.add(egui::TextEdit::singleline(&mut self.new_name).hint_text("预设名称").desired_width(120.0));
+            let name = self.new_name.trim().to_string();
+            if ui.add_enabled(!name.is_empty(), egui::Button::new("保存为预设")).clicked() {
+                let preset = ExtractPreset {
+                    name: name.clone(),
+                    settings: settings.clone(),
+                };
+                // 同名预设直接覆盖
+                self.persist(|presets| match presets.iter().position(|p| p.name == preset.name) {
+                    Some(i) => presets[i] = preset,
+                    None => presets.push(preset),
+                });
+                self.selected = self
+                    .user_presets
+                    .iter()
+                    .position(|p| p.name == name)
+                    .map(|i| (false, i));
+                self.new_name.clear();
+            }
+
+            if !self.status.is_empty() {
+                ui.label(&self.status);
+            }
+        });
+        applied
+    }
+
+    // 重新读取用户预设，选中的自定义预设按名称保持选中
+    fn reload(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let selected_name = match self.selected {
+            Some((false, i)) => self.user_presets.get(i).map(|p| p.name.clone()),
+            _ => None,
+        };
+        self.user_presets = load_user_presets(path);
+        if let Some(name) = selected_name {
+            self.selected = self
+                .user_presets
+                .iter()
+                .position(|p| p.name == name)
+                .map(|i| (false, i));
+        }
+    }
+
+    // 修改用户预设并写入磁盘：每个对话框和标签页各有一个 PresetManager，
+    // 因此先重新读取文件，把修改应用到最新内容上，避免覆盖其他地方保存的预设
+    fn persist(&mut self, change: impl FnOnce(&mut Vec<ExtractPreset>)) {
+        self.reload();
+        change(&mut self.user_presets);
+        if let Some((false, i)) = self.selected {
+            if i >= self.user_presets.len() {
+                self.selected = None;
+            }
+        }
+        let Some(path) = &self.path else {
+            return;
+        };
+        self.status = match save_user_presets(path, &self.user_presets) {
+            Ok(()) => String::new(),
+            Err(e) => format!("保存预设失败: {}", e),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(name: &str) -> ExtractPreset {
+        ExtractPreset {
+            name: name.to_string(),
+            settings: ExtractSettings::default(),
+        }
+    }
+
+    fn names(presets: &[ExtractPreset]) -> Vec<&str> {
+        presets.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    #[test]
+    fn persist_keeps_presets_saved_elsewhere() {
+        let dir = std::env::temp_dir().join(format!("stegsolve_presets_test_{}", std::process::id()));
+        let path = dir.join("extract_presets.json");
+        let mut manager = PresetManager::new(Some(path.clone()));
+        assert!(manager.user_presets.is_empty());
+
+        // 另一个对话框在此期间保存了预设
+        save_user_presets(&path, &[named("其他")]).unwrap();
+        manager.persist(|presets| presets.push(named("本地")));
+
+        let saved = load_user_presets(&path);
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(names(&saved), vec!["其他", "本地"]);
+        assert_eq!(names(&manager.user_presets), vec!["其他", "本地"]);
+        assert!(manager.status.is_empty());
+    }
+
+    #[test]
+    fn manager_without_path_stays_in_memory() {
+        let mut manager = PresetManager::new(None);
+        manager.persist(|presets| presets.push(named("临时")));
+        manager.reload();
+        assert_eq!(names(&manager.user_presets), vec!["临时"]);
+        assert!(manager.status.is_empty());
+    }
+}
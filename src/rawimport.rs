@@ -0,0 +1,501 @@
+use eframe::egui::{self, TextureHandle, TextureOptions};
+use image::{Rgba, RgbaImage};
+
+// ──────────────────────────────
+// 原始像素导入：按给定的尺寸和像素格式解释无文件头的像素数据
+
+/// 像素格式
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RawPixelFormat {
+    Gray1,
+    Gray2,
+    Gray4,
+    Gray8,
+    Gray16,
+    Rgb565,
+    Rgb555,
+    Rgb24,
+    Bgr24,
+    Rgba32,
+    Bgra32,
+    Argb32,
+}
+
+impl RawPixelFormat {
+    pub fn all() -> [RawPixelFormat; 12] {
+        [
+            Self::Gray1,
+            Self::Gray2,
+            Self::Gray4,
+            Self::Gray8,
+            Self::Gray16,
+            Self::Rgb565,
+            Self::Rgb555,
+            Self::Rgb24,
+            Self::Bgr24,
+            Self::Rgba32,
+            Self::Bgra32,
+            Self::Argb32,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gray1 => "1 位灰度",
+            Self::Gray2 => "2 位灰度",
+            Self::Gray4 => "4 位灰度",
+            Self::Gray8 => "8 位灰度",
+            Self::Gray16 => "16 位灰度",
+            Self::Rgb565 => "16 位 RGB565",
+            Self::Rgb555 => "16 位 RGB555",
+            Self::Rgb24 => "24 位 RGB",
+            Self::Bgr24 => "24 位 BGR",
+            Self::Rgba32 => "32 位 RGBA",
+            Self::Bgra32 => "32 位 BGRA",
+            Self::Argb32 => "32 位 ARGB",
+        }
+    }
+
+    pub fn bits_per_pixel(&self) -> u32 {
+        match self {
+            Self::Gray1 => 1,
+            Self::Gray2 => 2,
+            Self::Gray4 => 4,
+            Self::Gray8 => 8,
+            Self::Gray16 | Self::Rgb565 | Self::Rgb555 => 16,
+            Self::Rgb24 | Self::Bgr24 => 24,
+            Self::Rgba32 | Self::Bgra32 | Self::Argb32 => 32,
+        }
+    }
+}
+
+/// 导入参数
+#[derive(Clone, PartialEq)]
+pub struct RawImportSettings {
+    pub width: u32,
+    pub height: u32,
+    /// 像素数据在文件中的起始偏移（字节）
+    pub offset: usize,
+    pub format: RawPixelFormat,
+    /// 每行末尾的填充字节数
+    pub row_padding: usize,
+    /// 小端：16/32 位像素按小端整数读取，低于 8 位的像素从字节的低位开始排列
+    pub little_endian: bool,
+    /// 行从下到上存储（如 BMP）
+    pub bottom_up: bool,
+}
+
+impl Default for RawImportSettings {
+    fn default() -> Self {
+        Self {
+            width: 256,
+            height: 256,
+            offset: 0,
+            format: RawPixelFormat::Rgb24,
+            row_padding: 0,
+            little_endian: true,
+            bottom_up: false,
+        }
+    }
+}
+
+impl RawImportSettings {
+    /// 每行的字节数（含填充）
+    pub fn row_bytes(&self) -> usize {
+        (self.width as usize * self.format.bits_per_pixel() as usize).div_ceil(8) + self.row_padding
+    }
+
+    /// 按当前宽度和数据长度能容纳的完整行数
+    pub fn max_height(&self, data_len: usize) -> u32 {
+        (data_len.saturating_sub(self.offset) / self.row_bytes().max(1)).min(u32::MAX as usize) as u32
+    }
+}
+
+// 将 bits 位的值扩展为 8 位
+fn scale_to_u8(value: u32, bits: u32) -> u8 {
+    (value * 255 / ((1 << bits) - 1)) as u8
+}
+
+// 解码一行中的第 x 个像素，数据不足时返回 None
+fn decode_pixel(row: &[u8], x: usize, settings: &RawImportSettings) -> Option<Rgba<u8>> {
+    let format = settings.format;
+    let bits = format.bits_per_pixel() as usize;
+    if bits < 8 {
+        let bit = x * bits;
+        let byte = *row.get(bit / 8)? as u32;
+        let shift = if settings.little_endian { bit % 8 } else { 8 - bits - bit % 8 };
+        let v = scale_to_u8(byte >> shift & ((1 << bits) - 1), bits as u32);
+        return Some(Rgba([v, v, v, 255]));
+    }
+
+    let bytes = row.get(x * bits / 8..(x + 1) * bits / 8)?;
+    let word = |b: &[u8]| {
+        let iter = b.iter().map(|&v| v as u32);
+        if settings.little_endian {
+            iter.rev().fold(0, |acc, v| acc << 8 | v)
+        } else {
+            iter.fold(0, |acc, v| acc << 8 | v)
+        }
+    };
+    Some(match format {
+        RawPixelFormat::Gray8 => Rgba([bytes[0], bytes[0], bytes[0], 255]),
+        RawPixelFormat::Gray16 => {
+            let v = (word(bytes) >> 8) as u8;
+            Rgba([v, v, v, 255])
+        }
+        RawPixelFormat::Rgb565 => {
+            let w = word(bytes);
+            Rgba([scale_to_u8(w >> 11 & 0x1f, 5), scale_to_u8(w >> 5 & 0x3f, 6), scale_to_u8(w & 0x1f, 5), 255])
+        }
+        RawPixelFormat::Rgb555 => {
+            let w = word(bytes);
+            Rgba([scale_to_u8(w >> 10 & 0x1f, 5), scale_to_u8(w >> 5 & 0x1f, 5), scale_to_u8(w & 0x1f, 5), 255])
+        }
+        RawPixelFormat::Rgb24 => Rgba([bytes[0], bytes[1], bytes[2], 255]),
+        RawPixelFormat::Bgr24 => Rgba([bytes[2], bytes[1], bytes[0], 255]),
+        // 32 位格式按字节顺序命名，小端时整个像素字节反转
+        _ => {
+            let mut b = [bytes[0], bytes[1], bytes[2], bytes[3]];
+            if settings.little_endian {
+                b.reverse();
+            }
+            match format {
+                RawPixelFormat::Rgba32 => Rgba(b),
+                RawPixelFormat::Bgra32 => Rgba([b[2], b[1], b[0], b[3]]),
+                _ => Rgba([b[1], b[2], b[3], b[0]]),
+            }
+        }
+    })
+}
+
+/// 按参数解码原始像素数据，数据不足的像素为透明
+pub fn decode_raw(data: &[u8], settings: &RawImportSettings) -> RgbaImage {
+    decode_raw_sampled(data, settings, 1)
+}
+
+/// 预览用的解码：图像的长边超过 max_side 时每隔若干行列取一个像素，
+/// 不会为预览分配完整尺寸的图像
+/// 返回预览图像和抽样步长
+pub fn decode_raw_preview(data: &[u8], settings: &RawImportSettings, max_side: u32) -> (RgbaImage, u32) {
+    let longest = settings.width.max(settings.height).max(1);
+    let step = longest.div_ceil(max_side.max(1));
+    (decode_raw_sampled(data, settings, step), step)
+}
+
+// 每隔 step 行列解码一个像素
+fn decode_raw_sampled(data: &[u8], settings: &RawImportSettings, step: u32) -> RgbaImage {
+    let (width, height) = (settings.width.max(1), settings.height.max(1));
+    let step = step.max(1);
+    let row_bytes = settings.row_bytes();
+    let mut img = RgbaImage::new(width.div_ceil(step), height.div_ceil(step));
+    for ty in 0..img.height() {
+        // 从下到上存储时，输出的第 ty 行来自倒数的数据行
+        let y = ty * step;
+        let source_y = if settings.bottom_up { height - 1 - y } else { y };
+        let start = settings.offset + source_y as usize * row_bytes;
+        let row = data.get(start..data.len().min(start + row_bytes)).unwrap_or(&[]);
+        for tx in 0..img.width() {
+            if let Some(pixel) = decode_pixel(row, (tx * step) as usize, settings) {
+                img.put_pixel(tx, ty, pixel);
+            }
+        }
+    }
+    img
+}
+
+/// 按行自相关猜测图像宽度：对每个候选行跨度（字节），计算数据与错开该跨度后的数据的平均绝对差，
+/// 再与相邻跨度的平均值相比，上下相邻行相似时在真实跨度处出现明显的低谷
+/// 返回 (宽度, 低谷比例) 按比例从小到大排列，比例越小越可能
+pub fn guess_widths(data: &[u8], settings: &RawImportSettings, count: usize) -> Vec<(u32, f32)> {
+    let bits = settings.format.bits_per_pixel() as usize;
+    // 像素不足 1 字节时以字节为步长，否则以像素为步长
+    let step = (bits / 8).max(1);
+    let data = data.get(settings.offset..).unwrap_or(&[]);
+    let sample = &data[..data.len().min(1 << 18)];
+    let max_stride = (sample.len() / 4).min(4096 * step);
+    if max_stride < step * 4 {
+        return Vec::new();
+    }
+
+    let diff = |stride: usize| -> f32 {
+        let n = sample.len() - stride;
+        // 样本过多时按步长抽样
+        let skip = (n / 32000).max(1);
+        let (sum, cnt) = (0..n)
+            .step_by(skip)
+            .fold((0u64, 0u64), |(s, c), i| (s + sample[i].abs_diff(sample[i + stride]) as u64, c + 1));
+        sum as f32 / cnt.max(1) as f32
+    };
+    let strides: Vec<usize> = (1..=max_stride / step + 1).map(|k| k * step).collect();
+    let diffs: Vec<f32> = strides.iter().map(|&s| diff(s)).collect();
+
+    let min_width = 4;
+    let mut candidates: Vec<(u32, f32)> = (1..diffs.len() - 1)
+        .filter_map(|i| {
+            let neighbours = (diffs[i - 1] + diffs[i + 1]) / 2.0;
+            let stride = strides[i];
+            let width = (stride * 8 / bits) as u32;
+            if width < min_width || neighbours <= 0.0 {
+                return None;
+            }
+            Some((width, diffs[i] / neighbours))
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.1.total_cmp(&b.1));
+    candidates.truncate(count);
+    candidates
+}
+
+/// 打开文件时的初始高度上限
+const DEFAULT_MAX_HEIGHT: u32 = 4096;
+
+/// 原始像素导入对话框
+pub struct RawImportDialog {
+    /// 数据来源（文件路径）
+    pub source: String,
+    data: Vec<u8>,
+    pub settings: RawImportSettings,
+    /// 生成预览时使用的参数，参数变化时重新生成
+    preview_settings: Option<RawImportSettings>,
+    preview: Option<TextureHandle>,
+    /// 预览的抽样步长（1 为原尺寸）
+    preview_step: u32,
+    candidates: Vec<(u32, f32)>,
+}
+
+impl RawImportDialog {
+    pub fn new(source: String, data: Vec<u8>) -> Self {
+        let mut settings = RawImportSettings::default();
+        // 大文件只取前若干行，避免初始图像超出纹理尺寸限制
+        settings.height = settings.max_height(data.len()).clamp(1, DEFAULT_MAX_HEIGHT);
+        Self {
+            source,
+            data,
+            settings,
+            preview_settings: None,
+            preview: None,
+            preview_step: 1,
+            candidates: Vec::new(),
+        }
+    }
+
+    /// 绘制对话框，点击导入时返回解码后的图像
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<RgbaImage> {
+        let mut imported = None;
+        ui.label(format!("数据: {} ({} 字节)", self.source, self.data.len()));
+
+        let settings = &mut self.settings;
+        egui::Grid::new("raw_import_settings").num_columns(2).show(ui, |ui| {
+            ui.label("像素格式:");
+            egui::ComboBox::from_id_salt("raw_format")
+                .selected_text(settings.format.name())
+                .show_ui(ui, |ui| {
+                    for format in RawPixelFormat::all() {
+                        ui.selectable_value(&mut settings.format, format, format.name());
+                    }
+                });
+            ui.end_row();
+
+            ui.label("宽度:");
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut settings.width).range(1..=65535));
+                if ui.button("猜测宽度").clicked() {
+                    self.candidates = guess_widths(&self.data, settings, 8);
+                }
+            });
+            ui.end_row();
+
+            ui.label("高度:");
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut settings.height).range(1..=65535));
+                if ui.button("填满数据").clicked() {
+                    settings.height = settings.max_height(self.data.len()).clamp(1, 65535);
+                }
+            });
+            ui.end_row();
+
+            ui.label("起始偏移:");
+            ui.add(egui::DragValue::new(&mut settings.offset).range(0..=self.data.len()));
+            ui.end_row();
+
+            ui.label("行填充字节:");
+            ui.add(egui::DragValue::new(&mut settings.row_padding).range(0..=65535));
+            ui.end_row();
+
+            ui.label("字节序:");
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut settings.little_endian, true, "小端");
+                ui.radio_value(&mut settings.little_endian, false, "大端");
+                ui.checkbox(&mut settings.bottom_up, "从下到上");
+            });
+            ui.end_row();
+        });
+
+        if !self.candidates.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                ui.label("候选宽度:");
+                for &(width, ratio) in &self.candidates {
+                    if ui
+                        .button(width.to_string())
+                        .on_hover_text(format!("相对差异 {:.3}", ratio))
+                        .clicked()
+                    {
+                        self.settings.width = width;
+                        self.settings.height = self.settings.max_height(self.data.len()).clamp(1, 65535);
+                    }
+                }
+            });
+        }
+
+        ui.label(format!(
+            "每行 {} 字节，需要 {} 字节",
+            self.settings.row_bytes(),
+            self.settings.offset + self.settings.row_bytes() * self.settings.height as usize
+        ));
+
+        ui.horizontal(|ui| {
+            if ui.button("导入").clicked() {
+                imported = Some(decode_raw(&self.data, &self.settings));
+            }
+        });
+        ui.separator();
+
+        if self.preview_settings.as_ref() != Some(&self.settings) {
+            let max_side = ui.ctx().input(|i| i.max_texture_side) as u32;
+            let (img, step) = decode_raw_preview(&self.data, &self.settings, max_side.min(4096));
+            self.preview_step = step;
+            let size = [img.width() as usize, img.height() as usize];
+            let image_data = egui::ColorImage::from_rgba_unmultiplied(size, img.as_raw());
+            self.preview = Some(ui.ctx().load_texture("raw_import_preview", image_data, TextureOptions::NEAREST));
+            self.preview_settings = Some(self.settings.clone());
+        }
+        if let Some(texture) = &self.preview {
+            if self.preview_step > 1 {
+                ui.label(format!("预览已按 1/{} 抽样", self.preview_step));
+            }
+            egui::ScrollArea::both().id_salt("raw_import_preview").show(ui, |ui| {
+                ui.image(texture);
+            });
+        }
+        imported
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(width: u32, height: u32, format: RawPixelFormat) -> RawImportSettings {
+        RawImportSettings {
+            width,
+            height,
+            format,
+            ..RawImportSettings::default()
+        }
+    }
+
+    #[test]
+    fn decodes_byte_formats() {
+        let data = [1, 2, 3, 4, 5, 6, 7, 8];
+        let img = decode_raw(&data, &settings(2, 1, RawPixelFormat::Rgb24));
+        assert_eq!(img.get_pixel(1, 0).0, [4, 5, 6, 255]);
+        let img = decode_raw(&data, &settings(2, 1, RawPixelFormat::Bgr24));
+        assert_eq!(img.get_pixel(0, 0).0, [3, 2, 1, 255]);
+
+        // 32 位格式小端时整个像素字节反转
+        let mut s = settings(2, 1, RawPixelFormat::Argb32);
+        s.little_endian = false;
+        assert_eq!(decode_raw(&data, &s).get_pixel(0, 0).0, [2, 3, 4, 1]);
+        s.little_endian = true;
+        assert_eq!(decode_raw(&data, &s).get_pixel(0, 0).0, [3, 2, 1, 4]);
+        // 小端 BGRA 即整数 0xBBGGRRAA，内存中依次为 A R G B
+        s.format = RawPixelFormat::Bgra32;
+        assert_eq!(decode_raw(&data, &s).get_pixel(1, 0).0, [6, 7, 8, 5]);
+    }
+
+    #[test]
+    fn decodes_packed_formats() {
+        let mut s = settings(1, 1, RawPixelFormat::Rgb565);
+        assert_eq!(decode_raw(&[0x1f, 0xf8], &s).get_pixel(0, 0).0, [255, 0, 255, 255]);
+        s.little_endian = false;
+        assert_eq!(decode_raw(&[0x07, 0xe0], &s).get_pixel(0, 0).0, [0, 255, 0, 255]);
+        s.format = RawPixelFormat::Rgb555;
+        assert_eq!(decode_raw(&[0x7c, 0x00], &s).get_pixel(0, 0).0, [255, 0, 0, 255]);
+        s.format = RawPixelFormat::Gray16;
+        assert_eq!(decode_raw(&[0xab, 0xcd], &s).get_pixel(0, 0).0, [0xab, 0xab, 0xab, 255]);
+
+        // 1 位像素：大端从字节高位开始，小端从低位开始
+        let mut s = settings(8, 1, RawPixelFormat::Gray1);
+        s.little_endian = false;
+        let row: Vec<u8> = decode_raw(&[0b1000_0010], &s).pixels().map(|p| p[0]).collect();
+        assert_eq!(row, [255, 0, 0, 0, 0, 0, 255, 0]);
+        s.little_endian = true;
+        let row: Vec<u8> = decode_raw(&[0b1000_0010], &s).pixels().map(|p| p[0]).collect();
+        assert_eq!(row, [0, 255, 0, 0, 0, 0, 0, 255]);
+        s.format = RawPixelFormat::Gray4;
+        s.width = 2;
+        assert_eq!(decode_raw(&[0xf5], &s).get_pixel(0, 0)[0], 0x55);
+    }
+
+    #[test]
+    fn offset_padding_and_bottom_up() {
+        let mut s = settings(2, 2, RawPixelFormat::Gray8);
+        s.offset = 1;
+        s.row_padding = 1;
+        assert_eq!(s.row_bytes(), 3);
+        let data = [99, 1, 2, 99, 3, 4, 99];
+        let img = decode_raw(&data, &s);
+        assert_eq!(img.get_pixel(1, 1)[0], 4);
+        s.bottom_up = true;
+        let img = decode_raw(&data, &s);
+        assert_eq!(img.get_pixel(0, 0)[0], 3);
+        assert_eq!(img.get_pixel(1, 1)[0], 2);
+        assert_eq!(s.max_height(data.len()), 2);
+    }
+
+    #[test]
+    fn missing_data_is_transparent() {
+        let img = decode_raw(&[1, 2, 3, 4], &settings(2, 2, RawPixelFormat::Rgb24));
+        assert_eq!(img.get_pixel(0, 0).0, [1, 2, 3, 255]);
+        assert_eq!(img.get_pixel(1, 0).0, [0, 0, 0, 0]);
+        assert_eq!(img.get_pixel(0, 1).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn preview_is_sampled_to_max_side() {
+        let data: Vec<u8> = (0..100 * 40).map(|i| (i % 251) as u8).collect();
+        let s = settings(100, 40, RawPixelFormat::Gray8);
+        let (preview, step) = decode_raw_preview(&data, &s, 30);
+        assert_eq!(step, 4);
+        assert_eq!(preview.dimensions(), (25, 10));
+        assert_eq!(preview.get_pixel(3, 2)[0], decode_raw(&data, &s).get_pixel(12, 8)[0]);
+        let (full, step) = decode_raw_preview(&data, &s, 4096);
+        assert_eq!((step, full.dimensions()), (1, (100, 40)));
+    }
+
+    // 上下相邻行相似的合成图像
+    fn smooth_image(width: usize, height: usize, bytes_per_pixel: usize) -> Vec<u8> {
+        let mut data = Vec::with_capacity(width * height * bytes_per_pixel);
+        for y in 0..height {
+            for x in 0..width {
+                for c in 0..bytes_per_pixel {
+                    let v = (x * x / 7 + x * 3 + c * 50) as f32 + (y as f32 / 5.0).sin() * 4.0;
+                    data.push((v as usize % 256) as u8);
+                }
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn guesses_true_width_first() {
+        let cases = [(173, 40, RawPixelFormat::Rgb24), (320, 40, RawPixelFormat::Gray8), (37, 60, RawPixelFormat::Rgba32)];
+        for (width, height, format) in cases {
+            let bytes = format.bits_per_pixel() as usize / 8;
+            let data = smooth_image(width, height, bytes);
+            let guesses = guess_widths(&data, &settings(1, 1, format), 5);
+            assert_eq!(guesses[0].0, width as u32, "{:?}", guesses);
+        }
+        assert!(guess_widths(&[0; 8], &settings(1, 1, RawPixelFormat::Gray8), 5).is_empty());
+    }
+}
@@ -0,0 +1,232 @@
+use eframe::egui;
+use image::RgbaImage;
+
+// ──────────────────────────────
+// 主视图中的矩形选区，以及选区的直方图
+
+/// 图像上的矩形选区（像素坐标）
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Selection {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Selection {
+    /// 由拖动的起点和终点（均为像素坐标，含端点）生成选区，并限制在图像范围内
+    pub fn from_corners(a: (u32, u32), b: (u32, u32), image_width: u32, image_height: u32) -> Self {
+        let x0 = a.0.min(b.0).min(image_width.saturating_sub(1));
+        let y0 = a.1.min(b.1).min(image_height.saturating_sub(1));
+        let x1 = a.0.max(b.0).min(image_width.saturating_sub(1));
+        let y1 = a.1.max(b.1).min(image_height.saturating_sub(1));
+        Self {
+            x: x0,
+            y: y0,
+            width: x1 - x0 + 1,
+            height: y1 - y0 + 1,
+        }
+    }
+
+    /// 裁剪出选区内的图像（超出范围的部分被截掉）
+    pub fn crop(&self, img: &RgbaImage) -> RgbaImage {
+        image::imageops::crop_imm(img, self.x, self.y, self.width, self.height).to_image()
+    }
+
+    /// 坐标说明文本
+    pub fn text(&self) -> String {
+        format!(
+            "({}, {}) - ({}, {}), {}x{}",
+            self.x,
+            self.y,
+            self.x + self.width - 1,
+            self.y + self.height - 1,
+            self.width,
+            self.height
+        )
+    }
+}
+
+const CHANNEL_NAMES: [&str; 4] = ["R", "G", "B", "A"];
+const CHANNEL_COLORS: [egui::Color32; 4] = [
+    egui::Color32::from_rgb(220, 60, 60),
+    egui::Color32::from_rgb(60, 180, 60),
+    egui::Color32::from_rgb(60, 100, 220),
+    egui::Color32::GRAY,
+];
+
+/// 每个通道 256 个取值的像素计数
+pub struct Histogram {
+    pub counts: [[u32; 256]; 4],
+    pub total: u32,
+}
+
+impl Histogram {
+    pub fn new(img: &RgbaImage) -> Self {
+        let mut counts = [[0u32; 256]; 4];
+        for p in img.pixels() {
+            for c in 0..4 {
+                counts[c][p[c] as usize] += 1;
+            }
+        }
+        Self {
+            counts,
+            total: img.width() * img.height(),
+        }
+    }
+
+    /// 通道的 (最小值, 最大值, 平均值, 不同取值数)
+    pub fn stats(&self, channel: usize) -> (u8, u8, f64, usize) {
+        let counts = &self.counts[channel];
+        let min = counts.iter().position(|&n| n > 0).unwrap_or(0) as u8;
+        let max = counts.iter().rposition(|&n| n > 0).unwrap_or(0) as u8;
+        let sum: u64 = counts.iter().enumerate().map(|(v, &n)| v as u64 * n as u64).sum();
+        let mean = sum as f64 / self.total.max(1) as f64;
+        let distinct = counts.iter().filter(|&&n| n > 0).count();
+        (min, max, mean, distinct)
+    }
+
+    /// 导出为 CSV：value,r,g,b,a
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("value,r,g,b,a\n");
+        for v in 0..256 {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                v, self.counts[0][v], self.counts[1][v], self.counts[2][v], self.counts[3][v]
+            ));
+        }
+        csv
+    }
+}
+
+/// 直方图窗口的内容
+pub struct HistogramView {
+    pub histogram: Histogram,
+    /// 选区说明
+    pub title: String,
+    pub channels: [bool; 4],
+    /// 对数纵轴，便于观察很少出现的取值
+    pub log_scale: bool,
+}
+
+impl HistogramView {
+    pub fn new(histogram: Histogram, title: String) -> Self {
+        Self {
+            histogram,
+            title,
+            channels: [true, true, true, false],
+            log_scale: false,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(&self.title);
+        ui.horizontal(|ui| {
+            for (enabled, name) in self.channels.iter_mut().zip(CHANNEL_NAMES) {
+                ui.checkbox(enabled, name);
+            }
+            ui.checkbox(&mut self.log_scale, "对数刻度");
+            if ui.button("导出 CSV").clicked() {
+                if let Some(path) = rfd::FileDialog::new().set_file_name("histogram.csv").save_file() {
+                    if let Err(e) = std::fs::write(path, self.histogram.to_csv()) {
+                        eprintln!("保存文件失败: {}", e);
+                    }
+                }
+            }
+        });
+
+        let scale = |n: u32| if self.log_scale { (n as f32 + 1.0).ln() } else { n as f32 };
+        let peak = (0..4)
+            .filter(|&c| self.channels[c])
+            .flat_map(|c| self.histogram.counts[c].iter().map(|&n| scale(n)))
+            .fold(0.0f32, f32::max)
+            .max(1.0);
+
+        let (rect, response) =
+            ui.allocate_exact_size(egui::vec2(ui.available_width().max(256.0), 160.0), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+        let column = rect.width() / 256.0;
+        for c in (0..4).filter(|&c| self.channels[c]) {
+            let points: Vec<egui::Pos2> = (0..256)
+                .map(|v| {
+                    let x = rect.left() + (v as f32 + 0.5) * column;
+                    let y = rect.bottom() - scale(self.histogram.counts[c][v]) / peak * rect.height();
+                    egui::pos2(x, y)
+                })
+                .collect();
+            painter.add(egui::Shape::line(points, egui::Stroke::new(1.0, CHANNEL_COLORS[c])));
+        }
+        if let Some(pos) = response.hover_pos() {
+            let v = (((pos.x - rect.left()) / column) as usize).min(255);
+            let counts: Vec<String> = (0..4)
+                .map(|c| format!("{}={}", CHANNEL_NAMES[c], self.histogram.counts[c][v]))
+                .collect();
+            response.on_hover_text(format!("值 {}: {}", v, counts.join(" ")));
+        }
+
+        egui::Grid::new("histogram_stats").striped(true).show(ui, |ui| {
+            ui.label("通道");
+            ui.label("最小");
+            ui.label("最大");
+            ui.label("平均");
+            ui.label("不同取值");
+            ui.end_row();
+            for (c, name) in CHANNEL_NAMES.iter().enumerate() {
+                let (min, max, mean, distinct) = self.histogram.stats(c);
+                ui.label(*name);
+                ui.label(min.to_string());
+                ui.label(max.to_string());
+                ui.label(format!("{:.2}", mean));
+                ui.label(distinct.to_string());
+                ui.end_row();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn corners_in_any_order() {
+        let expected = Selection {
+            x: 2,
+            y: 3,
+            width: 4,
+            height: 2,
+        };
+        assert_eq!(Selection::from_corners((2, 3), (5, 4), 10, 10), expected);
+        assert_eq!(Selection::from_corners((5, 4), (2, 3), 10, 10), expected);
+        assert_eq!(Selection::from_corners((2, 4), (5, 3), 10, 10), expected);
+        assert_eq!(expected.text(), "(2, 3) - (5, 4), 4x2");
+    }
+
+    #[test]
+    fn corners_clamped_to_image() {
+        let selection = Selection::from_corners((7, 1), (50, 60), 10, 8);
+        assert_eq!((selection.x, selection.y, selection.width, selection.height), (7, 1, 3, 7));
+        // 完全在图像外时退化为边缘的单个像素
+        let selection = Selection::from_corners((20, 20), (30, 30), 10, 8);
+        assert_eq!((selection.x, selection.y, selection.width, selection.height), (9, 7, 1, 1));
+        let single = Selection::from_corners((4, 4), (4, 4), 10, 8);
+        assert_eq!((single.width, single.height), (1, 1));
+    }
+
+    #[test]
+    fn crop_and_histogram() {
+        let img = RgbaImage::from_fn(6, 4, |x, y| Rgba([x as u8, y as u8, 9, 255]));
+        let cropped = Selection::from_corners((1, 1), (3, 2), 6, 4).crop(&img);
+        assert_eq!(cropped.dimensions(), (3, 2));
+        assert_eq!(cropped.get_pixel(0, 0).0, [1, 1, 9, 255]);
+
+        let histogram = Histogram::new(&cropped);
+        assert_eq!(histogram.total, 6);
+        assert_eq!(histogram.counts[0][1..4], [2, 2, 2]);
+        assert_eq!(histogram.stats(0), (1, 3, 2.0, 3));
+        assert_eq!(histogram.stats(2), (9, 9, 9.0, 1));
+        assert!(histogram.to_csv().contains("\n9,0,0,6,0\n"));
+    }
+}
@@ -4,10 +4,75 @@ use rfd::FileDialog;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+/// 像素与偏移处像素的组合方式
+#[derive(Clone, Copy, PartialEq)]
+pub enum StereoOp {
+    Xor,
+    AbsDiff,
+    Sub,
+    Avg,
+}
+
+impl StereoOp {
+    pub fn all() -> [StereoOp; 4] {
+        [StereoOp::Xor, StereoOp::AbsDiff, StereoOp::Sub, StereoOp::Avg]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StereoOp::Xor => "异或",
+            StereoOp::AbsDiff => "绝对差",
+            StereoOp::Sub => "相减 (模 256)",
+            StereoOp::Avg => "平均",
+        }
+    }
+
+    fn apply(&self, a: u8, b: u8) -> u8 {
+        match self {
+            StereoOp::Xor => a ^ b,
+            StereoOp::AbsDiff => a.abs_diff(b),
+            StereoOp::Sub => a.wrapping_sub(b),
+            StereoOp::Avg => ((a as u16 + b as u16) / 2) as u8,
+        }
+    }
+}
+
+/// 偏移超出图像边界时的处理方式
+#[derive(Clone, Copy, PartialEq)]
+pub enum StereoEdge {
+    /// 回绕到另一侧
+    Wrap,
+    /// 取边缘像素
+    Clamp,
+}
+
+/// 除水平偏移外的立体视图选项
+#[derive(Clone, PartialEq)]
+pub struct StereoOptions {
+    /// 垂直偏移（正值与下方像素组合）
+    pub dy: i32,
+    pub op: StereoOp,
+    pub edge: StereoEdge,
+    /// 参与运算的通道 R/G/B/A；未选中的颜色通道输出 0，未选中 alpha 时输出不透明
+    pub channels: [bool; 4],
+}
+
+impl Default for StereoOptions {
+    fn default() -> Self {
+        Self {
+            dy: 0,
+            op: StereoOp::Xor,
+            edge: StereoEdge::Wrap,
+            channels: [true, true, true, false],
+        }
+    }
+}
+
 pub struct StereoTransform {
     original_image: RgbaImage,
     transform: RgbaImage,
     trans_num: i32,
+    options: StereoOptions,
 }
 
 impl StereoTransform {
@@ -16,6 +81,7 @@ impl StereoTransform {
             original_image: img.clone(),
             transform: RgbaImage::new(img.width(), img.height()),
             trans_num: 0,
+            options: StereoOptions::default(),
         };
         st.calc_trans();
         st
@@ -24,17 +90,25 @@ impl StereoTransform {
     fn calc_trans(&mut self) {
         let width = self.original_image.width() as i32;
         let height = self.original_image.height() as i32;
+        let options = &self.options;
 
         self.transform = RgbaImage::new(width as u32, height as u32); // Recreate the transform image
 
         for i in 0..width {
             for j in 0..height {
                 let fcol = self.original_image.get_pixel(i as u32, j as u32);
-                let offset = ((i + self.trans_num).rem_euclid(width)) as u32;
-                let ocol = self.original_image.get_pixel(offset, j as u32);
+                let (ox, oy) = match options.edge {
+                    StereoEdge::Wrap => ((i + self.trans_num).rem_euclid(width), (j + options.dy).rem_euclid(height)),
+                    StereoEdge::Clamp => ((i + self.trans_num).clamp(0, width - 1), (j + options.dy).clamp(0, height - 1)),
+                };
+                let ocol = self.original_image.get_pixel(ox as u32, oy as u32);
 
-                let new_pixel =
-                    image::Rgba([fcol[0] ^ ocol[0], fcol[1] ^ ocol[1], fcol[2] ^ ocol[2], 255]);
+                let mut new_pixel = image::Rgba([0, 0, 0, 255]);
+                for c in 0..4 {
+                    if options.channels[c] {
+                        new_pixel[c] = options.op.apply(fcol[c], ocol[c]);
+                    }
+                }
 
                 self.transform.put_pixel(i as u32, j as u32, new_pixel);
             }
@@ -42,25 +116,71 @@ impl StereoTransform {
     }
 
     pub fn back(&mut self) {
+        let max = self.max_offset();
         self.trans_num -= 1;
-        if self.trans_num < 0 {
-            self.trans_num = self.original_image.width() as i32 - 1;
+        if self.trans_num < -max {
+            self.trans_num = max;
         }
         println!("Back pressed: trans_num = {}", self.trans_num);
         self.calc_trans();
     }
 
     pub fn forward(&mut self) {
+        let max = self.max_offset();
         self.trans_num += 1;
-        if self.trans_num >= self.original_image.width() as i32 {
-            self.trans_num = 0;
+        if self.trans_num > max {
+            self.trans_num = -max;
         }
         println!("Forward pressed: trans_num = {}", self.trans_num);
         self.calc_trans();
     }
 
+    /// 水平偏移的最大绝对值：负值向左、正值向右，取边缘模式下两个方向不等价
+    pub fn max_offset(&self) -> i32 {
+        self.original_image.width().max(1) as i32 - 1
+    }
+
+    /// 垂直偏移的最大绝对值
+    pub fn max_dy(&self) -> i32 {
+        self.original_image.height().max(1) as i32 - 1
+    }
+
+    /// 直接设置偏移量（限制在 ±max_offset 内）
+    pub fn set_offset(&mut self, offset: i32) {
+        let max = self.max_offset();
+        self.trans_num = offset.clamp(-max, max);
+        self.calc_trans();
+    }
+
+    pub fn offset(&self) -> i32 {
+        self.trans_num
+    }
+
+    pub fn options(&self) -> &StereoOptions {
+        &self.options
+    }
+
+    /// 设置垂直偏移、组合方式、边界处理和通道，并重新计算
+    pub fn set_options(&mut self, options: StereoOptions) {
+        let max = self.max_dy();
+        self.options = StereoOptions {
+            dy: options.dy.clamp(-max, max),
+            ..options
+        };
+        self.calc_trans();
+    }
+
+    pub fn get_original(&self) -> &RgbaImage {
+        &self.original_image
+    }
+
     pub fn get_text(&self) -> String {
-        format!("偏移量: {}", self.trans_num)
+        format!(
+            "偏移量: ({}, {})  {}",
+            self.trans_num,
+            self.options.dy,
+            self.options.op.name()
+        )
     }
 
     pub fn get_image(&self) -> &RgbaImage {
@@ -68,9 +188,131 @@ impl StereoTransform {
     }
 }
 
+/// 自动检测候选偏移时最多尝试的平移量
+pub const MAX_DETECT_SHIFT: u32 = 1024;
+
+/// 计算图像与自身水平平移 1..=max_shift 列（回绕）后的相似度，返回值下标 i 对应平移 i + 1
+/// 相似度 = 1 - RGB 平均绝对差 / 255；为控制耗时，只在最多 64 行 × 256 列的均匀网格上取样
+pub fn shift_similarity(img: &RgbaImage, max_shift: u32) -> Vec<f32> {
+    let (width, height) = img.dimensions();
+    if width < 2 || height == 0 {
+        return Vec::new();
+    }
+    let row_step = height.div_ceil(64).max(1);
+    let col_step = width.div_ceil(256).max(1);
+    let rows: Vec<u32> = (0..height).step_by(row_step as usize).collect();
+    let cols: Vec<u32> = (0..width).step_by(col_step as usize).collect();
+    let max_shift = max_shift.min(width - 1);
+
+    (1..=max_shift)
+        .map(|shift| {
+            let mut total = 0u64;
+            for &y in &rows {
+                for &x in &cols {
+                    let a = img.get_pixel(x, y);
+                    let b = img.get_pixel((x + shift) % width, y);
+                    total += (0..3).map(|c| a[c].abs_diff(b[c]) as u64).sum::<u64>();
+                }
+            }
+            let count = rows.len() as u64 * cols.len() as u64 * 3;
+            1.0 - total as f32 / count as f32 / 255.0
+        })
+        .collect()
+}
+
+/// 从相似度曲线中选出局部极大值，按相似度降序返回最多 count 个 (偏移, 相似度)
+pub fn best_offsets(similarity: &[f32], count: usize) -> Vec<(i32, f32)> {
+    let mut peaks: Vec<(i32, f32)> = similarity
+        .iter()
+        .enumerate()
+        .filter(|&(i, &v)| {
+            let left = if i > 0 { similarity[i - 1] } else { f32::MIN };
+            let right = similarity.get(i + 1).copied().unwrap_or(f32::MIN);
+            v >= left && v >= right
+        })
+        .map(|(i, &v)| (i as i32 + 1, v))
+        .collect();
+    peaks.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    peaks.truncate(count);
+    peaks
+}
+
+/// 由自动立体图（magic eye）估计深度图：对每个像素，在基准周期 period ± radius 范围内
+/// 寻找使 window × window 邻域灰度平均绝对差最小的水平重复距离，并将其归一化为灰度
+/// 重复距离越小表示越靠近观察者，默认显示为越亮；invert 时反转
+pub fn depth_map(img: &RgbaImage, period: u32, radius: u32, window: u32, invert: bool) -> RgbaImage {
+    let (width, height) = img.dimensions();
+    let (w, h) = (width as usize, height as usize);
+    let grey: Vec<f32> = img
+        .pixels()
+        .map(|p| 0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32)
+        .collect();
+
+    let min_shift = period.saturating_sub(radius).max(1);
+    let max_shift = (period + radius).min(width.saturating_sub(1));
+    let half = (window / 2) as usize;
+    let mut best_cost = vec![f64::MAX; w * h];
+    let mut best_shift = vec![min_shift; w * h];
+    let mut integral = vec![0f64; (w + 1) * (h + 1)];
+
+    for shift in min_shift..=max_shift {
+        let s = shift as usize;
+        // 逐像素与左侧 shift 列处像素的差值积分图，超出左边界的位置代价取最大
+        for y in 0..h {
+            let mut row_sum = 0f64;
+            for x in 0..w {
+                let d = if x >= s {
+                    (grey[y * w + x] - grey[y * w + x - s]).abs() as f64
+                } else {
+                    255.0
+                };
+                row_sum += d;
+                integral[(y + 1) * (w + 1) + x + 1] = integral[y * (w + 1) + x + 1] + row_sum;
+            }
+        }
+        for y in 0..h {
+            let (y0, y1) = (y.saturating_sub(half), (y + half + 1).min(h));
+            for x in 0..w {
+                let (x0, x1) = (x.saturating_sub(half), (x + half + 1).min(w));
+                let sum = integral[y1 * (w + 1) + x1] - integral[y0 * (w + 1) + x1] - integral[y1 * (w + 1) + x0]
+                    + integral[y0 * (w + 1) + x0];
+                let cost = sum / ((y1 - y0) * (x1 - x0)) as f64;
+                if cost < best_cost[y * w + x] {
+                    best_cost[y * w + x] = cost;
+                    best_shift[y * w + x] = shift;
+                }
+            }
+        }
+    }
+
+    let lo = best_shift.iter().copied().min().unwrap_or(0);
+    let hi = best_shift.iter().copied().max().unwrap_or(0);
+    let range = (hi - lo).max(1) as f32;
+    let mut out = RgbaImage::new(width, height);
+    for (pixel, &shift) in out.pixels_mut().zip(&best_shift) {
+        let mut v = ((hi - shift) as f32 / range * 255.0).round() as u8;
+        if invert {
+            v = 255 - v;
+        }
+        *pixel = image::Rgba([v, v, v, 255]);
+    }
+    out
+}
+
 pub struct Stereo {
     transform: Rc<RefCell<StereoTransform>>, // RefCell stores a mutable reference to the StereoTransform
     texture: Option<egui::TextureHandle>,
+    /// 自动检测出的候选偏移 (偏移, 相似度)，首次显示时计算
+    candidates: Option<Vec<(i32, f32)>>,
+    /// 是否显示深度图
+    depth_mode: bool,
+    /// 深度图参数：基准周期、搜索半径、匹配窗口、是否反转
+    depth_period: u32,
+    depth_radius: u32,
+    depth_window: u32,
+    depth_invert: bool,
+    depth_image: Option<RgbaImage>,
+    depth_texture: Option<egui::TextureHandle>,
 }
 
 impl Stereo {
@@ -78,9 +320,87 @@ impl Stereo {
         Self {
             transform: Rc::new(RefCell::new(StereoTransform::new(img))),
             texture: None,
+            candidates: None,
+            depth_mode: false,
+            depth_period: 0,
+            depth_radius: 0,
+            depth_window: 9,
+            depth_invert: false,
+            depth_image: None,
+            depth_texture: None,
         }
     }
 
+    // 计算候选偏移（最多检测到图像宽度的一半，且不超过 MAX_DETECT_SHIFT）
+    fn detect_offsets(&mut self) -> &[(i32, f32)] {
+        self.candidates.get_or_insert_with(|| {
+            let transform = self.transform.borrow();
+            let img = transform.get_original();
+            let similarity = shift_similarity(img, (img.width() / 2).min(MAX_DETECT_SHIFT));
+            best_offsets(&similarity, 10)
+        })
+    }
+
+    // 偏移量滑块、数值输入与候选偏移列表
+    fn offset_ui(&mut self, ui: &mut egui::Ui) {
+        let max_offset = self.transform.borrow().max_offset();
+        let mut offset = self.transform.borrow().offset();
+        ui.horizontal(|ui| {
+            ui.label("水平偏移:");
+            let slider = ui.add(egui::Slider::new(&mut offset, -max_offset..=max_offset).show_value(false));
+            let drag = ui.add(egui::DragValue::new(&mut offset).range(-max_offset..=max_offset));
+            if slider.changed() || drag.changed() {
+                self.transform.borrow_mut().set_offset(offset);
+            }
+        });
+
+        let max_dy = self.transform.borrow().max_dy();
+        let mut options = self.transform.borrow().options().clone();
+        ui.horizontal(|ui| {
+            ui.label("垂直偏移:");
+            ui.add(egui::Slider::new(&mut options.dy, -max_dy..=max_dy).show_value(false));
+            ui.add(egui::DragValue::new(&mut options.dy).range(-max_dy..=max_dy));
+        });
+        ui.horizontal_wrapped(|ui| {
+            ui.label("运算:");
+            egui::ComboBox::from_id_salt("stereo_op")
+                .selected_text(options.op.name())
+                .show_ui(ui, |ui| {
+                    for op in StereoOp::all() {
+                        ui.selectable_value(&mut options.op, op, op.name());
+                    }
+                });
+            ui.separator();
+            ui.label("边界:");
+            ui.radio_value(&mut options.edge, StereoEdge::Wrap, "回绕");
+            ui.radio_value(&mut options.edge, StereoEdge::Clamp, "取边缘");
+            ui.separator();
+            ui.label("通道:");
+            for (c, name) in ["R", "G", "B", "A"].iter().enumerate() {
+                ui.checkbox(&mut options.channels[c], *name);
+            }
+        });
+        if options != *self.transform.borrow().options() {
+            self.transform.borrow_mut().set_options(options);
+        }
+
+        let candidates = self.detect_offsets().to_vec();
+        ui.horizontal_wrapped(|ui| {
+            ui.label("候选周期:");
+            if let Some(&(best, _)) = candidates.first() {
+                if ui.button("跳到最佳").clicked() {
+                    self.transform.borrow_mut().set_offset(best);
+                }
+            }
+            for (candidate, similarity) in candidates {
+                let label = format!("{} ({:.1}%)", candidate, similarity * 100.0);
+                if ui.selectable_label(offset == candidate, label).clicked() {
+                    self.transform.borrow_mut().set_offset(candidate);
+                }
+            }
+        });
+    }
+
     fn update_texture(&mut self, ui: &mut egui::Ui) {
         let transform_borrow = self.transform.borrow();
         let image = transform_borrow.get_image();
@@ -111,7 +431,62 @@ impl Stereo {
         );
     }
 
+    // 深度图参数、计算与保存
+    fn depth_ui(&mut self, ui: &mut egui::Ui) {
+        // 基准周期默认取检测到的最佳偏移，搜索半径默认为周期的 1/4
+        if self.depth_period == 0 {
+            self.depth_period = self.detect_offsets().first().map(|&(o, _)| o as u32).unwrap_or(1);
+            self.depth_radius = (self.depth_period / 4).max(1);
+        }
+        let width = self.transform.borrow().get_original().width().max(2);
+        ui.horizontal_wrapped(|ui| {
+            ui.label("基准周期:");
+            ui.add(egui::DragValue::new(&mut self.depth_period).range(1..=width - 1));
+            ui.label("搜索半径:");
+            ui.add(egui::DragValue::new(&mut self.depth_radius).range(0..=width / 2));
+            ui.label("匹配窗口:");
+            ui.add(egui::DragValue::new(&mut self.depth_window).range(1..=63));
+            ui.checkbox(&mut self.depth_invert, "反转");
+            if ui.button("计算深度图").clicked() {
+                let depth = depth_map(
+                    self.transform.borrow().get_original(),
+                    self.depth_period,
+                    self.depth_radius,
+                    self.depth_window,
+                    self.depth_invert,
+                );
+                let size = [depth.width() as usize, depth.height() as usize];
+                let color_image = egui::ColorImage::from_rgba_unmultiplied(size, depth.as_raw());
+                self.depth_texture = Some(ui.ctx().load_texture("stereo-depth", color_image, egui::TextureOptions::default()));
+                self.depth_image = Some(depth);
+            }
+            if ui.add_enabled(self.depth_image.is_some(), egui::Button::new("保存")).clicked() {
+                if let Some(path) = FileDialog::new()
+                    .add_filter("图片", &["png", "jpg", "jpeg", "bmp"])
+                    .set_file_name("depth.png")
+                    .save_file()
+                {
+                    if let Some(depth) = &self.depth_image {
+                        save_rgba_image(depth, path);
+                    }
+                }
+            }
+        });
+        if let Some(texture) = &self.depth_texture {
+            ui.image(texture);
+        }
+    }
+
     pub fn update(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.depth_mode, false, "偏移视图");
+            ui.radio_value(&mut self.depth_mode, true, "深度图");
+        });
+        if self.depth_mode {
+            self.depth_ui(ui);
+            return;
+        }
+
         // 检查键盘输入
         let left_pressed = ctx.input(|i| i.key_pressed(egui::Key::ArrowLeft));
         let right_pressed = ctx.input(|i| i.key_pressed(egui::Key::ArrowRight));
@@ -133,6 +508,8 @@ impl Stereo {
             };
             ui.label(text);
 
+            self.offset_ui(ui);
+
             self.update_texture(ui);
             if let Some(texture) = &self.texture {
                 let size = texture.size_vec2();
@@ -188,3 +565,49 @@ pub fn save_rgba_image(img: &image::RgbaImage, path: std::path::PathBuf) {
         eprintln!("保存失败: {}", e);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 一行 4 像素，红色通道依次为 10,20,30,40
+    fn row() -> RgbaImage {
+        RgbaImage::from_fn(4, 1, |x, _| image::Rgba([(x as u8 + 1) * 10, 0, 0, 255]))
+    }
+
+    #[test]
+    fn negative_offset_clamps_to_left_edge() {
+        let mut st = StereoTransform::new(row());
+        st.set_options(StereoOptions { edge: StereoEdge::Clamp, op: StereoOp::AbsDiff, ..StereoOptions::default() });
+        st.set_offset(-1);
+        assert_eq!(st.offset(), -1);
+        let reds: Vec<u8> = st.get_image().pixels().map(|p| p[0]).collect();
+        // 第 0 列取边缘自身，其余与左侧相邻像素相差 10
+        assert_eq!(reds, vec![0, 10, 10, 10]);
+    }
+
+    #[test]
+    fn offsets_are_limited_to_signed_range() {
+        let mut st = StereoTransform::new(row());
+        st.set_offset(-100);
+        assert_eq!(st.offset(), -3);
+        st.back();
+        assert_eq!(st.offset(), 3);
+        st.forward();
+        assert_eq!(st.offset(), -3);
+        st.set_options(StereoOptions { dy: 5, ..StereoOptions::default() });
+        assert_eq!(st.options().dy, 0);
+    }
+
+    #[test]
+    fn similarity_peaks_at_period_on_sampled_grid() {
+        // 宽度远大于取样列数，周期 37 的条纹仍应被识别为最佳偏移
+        let img = RgbaImage::from_fn(3000, 300, |x, y| {
+            let v = ((x % 37) * 7 + (y % 5)) as u8;
+            image::Rgba([v, v.wrapping_mul(3), 255 - v, 255])
+        });
+        let similarity = shift_similarity(&img, 100);
+        assert_eq!(similarity.len(), 100);
+        assert_eq!(best_offsets(&similarity, 1)[0].0, 37);
+    }
+}
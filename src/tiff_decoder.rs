@@ -0,0 +1,331 @@
+use crate::apng_decoder::index_map;
+use crate::framebrowser::Frame;
+use flate2::read::ZlibDecoder;
+use image::{Rgba, RgbaImage};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::tags::Tag;
+use tiff::ColorType;
+
+/// TIFF解码器 - 逐个读取 IFD，每个 IFD 作为一帧（页）
+pub struct TiffDecoder {
+    frames: Vec<Frame>,
+}
+
+impl TiffDecoder {
+    /// 从文件路径解码TIFF的所有页，无法解码的页保留为带说明的空白帧
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = File::open(&path)?;
+        let mut decoder = Decoder::new(BufReader::new(file))?;
+        let mut frames = Vec::new();
+
+        loop {
+            let page = frames.len() + 1;
+            let frame = match decode_page(&mut decoder) {
+                Ok((frame, color)) => Frame {
+                    note: Some(format!("第 {} 页, {}", page, color)),
+                    ..frame
+                },
+                Err(e) => Frame {
+                    note: Some(format!("第 {} 页解码失败: {}", page, e)),
+                    ..Frame::from_image(RgbaImage::new(1, 1))
+                },
+            };
+            frames.push(frame);
+
+            if !decoder.more_images() {
+                break;
+            }
+            if let Err(e) = decoder.next_image() {
+                eprintln!("读取下一个 IFD 失败: {}", e);
+                break;
+            }
+        }
+
+        Ok(Self { frames })
+    }
+
+    /// 获取所有帧
+    pub fn into_frames(self) -> Vec<Frame> {
+        self.frames
+    }
+}
+
+// PhotometricInterpretation 中调色板图像的取值
+const PHOTOMETRIC_PALETTE: u16 = 3;
+
+// 解码当前 IFD 对应的图像，同时返回颜色类型的说明
+fn decode_page<R: Read + Seek>(decoder: &mut Decoder<R>) -> Result<(Frame, String), Box<dyn std::error::Error>> {
+    // tiff 库不解码调色板图像，自行读取索引并按 ColorMap 展开
+    if decoder.find_tag_unsigned::<u16>(Tag::PhotometricInterpretation)? == Some(PHOTOMETRIC_PALETTE) {
+        return decode_palette_page(decoder);
+    }
+    let (width, height) = decoder.dimensions()?;
+    let color = decoder.colortype()?;
+    let result = decoder.read_image()?;
+    Ok((Frame::from_image(to_rgba(width, height, color, result)?), format!("{:?}", color)))
+}
+
+/// 将解码结果转换为 RGBA，支持 8/16 位的灰度、RGB、CMYK（含 alpha）、YCbCr，以及 8 位以下的灰度
+fn to_rgba(width: u32, height: u32, color: ColorType, result: DecodingResult) -> Result<RgbaImage, String> {
+    let (channels, bits) = match color {
+        ColorType::Gray(b) => (1, b),
+        ColorType::GrayA(b) => (2, b),
+        ColorType::RGB(b) => (3, b),
+        ColorType::RGBA(b) => (4, b),
+        ColorType::CMYK(b) => (4, b),
+        ColorType::CMYKA(b) => (5, b),
+        ColorType::YCbCr(b) => (3, b),
+        other => return Err(format!("不支持的颜色类型 {:?}", other)),
+    };
+
+    // 统一缩放为 8 位样本
+    let samples: Vec<u8> = match result {
+        DecodingResult::U8(data) if bits < 8 && channels == 1 => unpack_gray(&data, width, height, bits),
+        DecodingResult::U8(data) => data,
+        DecodingResult::U16(data) => data.iter().map(|v| (v >> 8) as u8).collect(),
+        _ => return Err(format!("不支持的样本格式 ({:?})", color)),
+    };
+
+    let pixel_count = width as usize * height as usize;
+    if samples.len() < pixel_count * channels {
+        return Err("图像数据长度不足".to_string());
+    }
+
+    let mut img = RgbaImage::new(width, height);
+    for (pixel, s) in img.pixels_mut().zip(samples.chunks_exact(channels)) {
+        *pixel = match color {
+            ColorType::Gray(_) => Rgba([s[0], s[0], s[0], 255]),
+            ColorType::GrayA(_) => Rgba([s[0], s[0], s[0], s[1]]),
+            ColorType::RGB(_) => Rgba([s[0], s[1], s[2], 255]),
+            ColorType::RGBA(_) => Rgba([s[0], s[1], s[2], s[3]]),
+            ColorType::CMYK(_) | ColorType::CMYKA(_) => {
+                let k = 255 - s[3] as u32;
+                let c = |v: u8| ((255 - v as u32) * k / 255) as u8;
+                let alpha = if channels == 5 { s[4] } else { 255 };
+                Rgba([c(s[0]), c(s[1]), c(s[2]), alpha])
+            }
+            ColorType::YCbCr(_) => {
+                // 默认的 YCbCrCoefficients（BT.601）与全范围 ReferenceBlackWhite
+                let (y, cb, cr) = (s[0] as f32, s[1] as f32 - 128.0, s[2] as f32 - 128.0);
+                let c = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+                Rgba([c(y + 1.402 * cr), c(y - 0.344136 * cb - 0.714136 * cr), c(y + 1.772 * cb), 255])
+            }
+            _ => unreachable!(),
+        };
+    }
+    Ok(img)
+}
+
+// 展开每行按字节对齐的低位深样本（不缩放）
+fn unpack_samples(data: &[u8], width: u32, height: u32, bits: u8) -> Vec<u8> {
+    let row_bytes = (width as usize * bits as usize).div_ceil(8);
+    let max = ((1u32 << bits) - 1) as u8;
+    let mut out = Vec::with_capacity(width as usize * height as usize);
+    for row in data.chunks(row_bytes).take(height as usize) {
+        for x in 0..width as usize {
+            let bit = x * bits as usize;
+            let byte = row.get(bit / 8).copied().unwrap_or(0);
+            out.push((byte >> (8 - bits as usize - bit % 8)) & max);
+        }
+    }
+    out
+}
+
+// 展开每行按字节对齐的低位深灰度数据，并缩放到 0..=255
+fn unpack_gray(data: &[u8], width: u32, height: u32, bits: u8) -> Vec<u8> {
+    let max = (1u32 << bits) - 1;
+    unpack_samples(data, width, height, bits)
+        .into_iter()
+        .map(|value| (value as u32 * 255 / max) as u8)
+        .collect()
+}
+
+// 读取调色板图像的索引（按条带存储，支持无压缩、PackBits、LZW、Deflate），按 ColorMap 展开为 RGBA
+fn decode_palette_page<R: Read + Seek>(decoder: &mut Decoder<R>) -> Result<(Frame, String), Box<dyn std::error::Error>> {
+    let (width, height) = decoder.dimensions()?;
+    let bits = decoder.find_tag_unsigned::<u8>(Tag::BitsPerSample)?.unwrap_or(1);
+    if !matches!(bits, 1 | 2 | 4 | 8) {
+        return Err(format!("不支持 {} 位的调色板图像", bits).into());
+    }
+    if decoder.find_tag(Tag::TileWidth)?.is_some() {
+        return Err("不支持分块（tile）存储的调色板图像".into());
+    }
+    if decoder.find_tag_unsigned::<u16>(Tag::Predictor)?.unwrap_or(1) != 1 {
+        return Err("不支持带预测器的调色板图像".into());
+    }
+    let compression = decoder.find_tag_unsigned::<u16>(Tag::Compression)?.unwrap_or(1);
+
+    // ColorMap 依次存放全部红、绿、蓝分量，每个分量 16 位
+    let color_map = decoder.get_tag_u16_vec(Tag::ColorMap)?;
+    let entries = 1usize << bits;
+    if color_map.len() < entries * 3 {
+        return Err("ColorMap 长度不足".into());
+    }
+    let palette: Vec<[u8; 4]> = (0..entries)
+        .map(|i| {
+            let c = |k: usize| (color_map[k * entries + i] >> 8) as u8;
+            [c(0), c(1), c(2), 255]
+        })
+        .collect();
+
+    let offsets = decoder.get_tag_u64_vec(Tag::StripOffsets)?;
+    let byte_counts = decoder.get_tag_u64_vec(Tag::StripByteCounts)?;
+    let rows_per_strip = decoder.find_tag_unsigned::<u32>(Tag::RowsPerStrip)?.unwrap_or(height).clamp(1, height.max(1));
+    let row_bytes = (width as usize * bits as usize).div_ceil(8);
+    let mut data = Vec::with_capacity(row_bytes * height as usize);
+    for (strip, (&offset, &count)) in offsets.iter().zip(&byte_counts).enumerate() {
+        let rows = height.saturating_sub(strip as u32 * rows_per_strip).min(rows_per_strip);
+        if rows == 0 {
+            break;
+        }
+        let reader = decoder.inner();
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut stored = Vec::new();
+        reader.take(count).read_to_end(&mut stored)?;
+        let mut strip_data = decompress(&stored, compression)?;
+        strip_data.resize(rows as usize * row_bytes, 0);
+        data.extend_from_slice(&strip_data);
+    }
+
+    let indices = unpack_samples(&data, width, height, bits);
+    if indices.len() < width as usize * height as usize {
+        return Err("图像数据长度不足".into());
+    }
+    let mut img = RgbaImage::new(width, height);
+    for (pixel, &index) in img.pixels_mut().zip(&indices) {
+        *pixel = Rgba(palette[index as usize]);
+    }
+    let frame = Frame {
+        index_map: Some(index_map(&indices, width, height)),
+        palette,
+        ..Frame::from_image(img)
+    };
+    Ok((frame, format!("Palette({})", bits)))
+}
+
+// 按 Compression 标签解压一个条带
+fn decompress(data: &[u8], compression: u16) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    match compression {
+        1 => Ok(data.to_vec()),
+        5 => Ok(weezl::decode::Decoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8).decode(data)?),
+        8 | 32946 => {
+            let mut out = Vec::new();
+            ZlibDecoder::new(data).read_to_end(&mut out)?;
+            Ok(out)
+        }
+        32773 => Ok(unpack_bits(data)),
+        other => Err(format!("不支持的压缩方式 {}", other).into()),
+    }
+}
+
+// PackBits：n >= 0 时复制后面 n + 1 个字节，n < 0（-128 除外）时将下一个字节重复 1 - n 次
+fn unpack_bits(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        let n = data[i] as i8;
+        i += 1;
+        if n >= 0 {
+            let end = (i + n as usize + 1).min(data.len());
+            out.extend_from_slice(&data[i..end]);
+            i = end;
+        } else if n != -128 {
+            if let Some(&byte) = data.get(i) {
+                out.resize(out.len() + (1 - n as isize) as usize, byte);
+            }
+            i += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    // 构造单页、单条带的小端调色板 TIFF
+    fn palette_tiff(width: u32, height: u32, bits: u16, compression: u16, strip: &[u8]) -> Vec<u8> {
+        let entries = 1usize << bits;
+        // 第 i 个颜色为 (i, 255 - i, i * 2)
+        let mut color_map = Vec::new();
+        for k in 0..3 {
+            for i in 0..entries {
+                let v = [i, 255 - i, i * 2][k] as u16 & 0xff;
+                color_map.extend_from_slice(&(v << 8 | v).to_le_bytes());
+            }
+        }
+        let ifd_len = 2 + 10 * 12 + 4;
+        let color_map_offset = 8 + ifd_len as u32;
+        let strip_offset = color_map_offset + color_map.len() as u32;
+        // (标签, 类型：3 = SHORT / 4 = LONG, 个数, 值或偏移)
+        let tags: [(u16, u16, u32, u32); 10] = [
+            (256, 4, 1, width),
+            (257, 4, 1, height),
+            (258, 3, 1, bits as u32),
+            (259, 3, 1, compression as u32),
+            (262, 3, 1, PHOTOMETRIC_PALETTE as u32),
+            (273, 4, 1, strip_offset),
+            (277, 3, 1, 1),
+            (278, 4, 1, height),
+            (279, 4, 1, strip.len() as u32),
+            (320, 3, (entries * 3) as u32, color_map_offset),
+        ];
+        let mut file = b"II*\0".to_vec();
+        file.extend_from_slice(&8u32.to_le_bytes());
+        file.extend_from_slice(&(tags.len() as u16).to_le_bytes());
+        for (tag, kind, count, value) in tags {
+            file.extend_from_slice(&tag.to_le_bytes());
+            file.extend_from_slice(&kind.to_le_bytes());
+            file.extend_from_slice(&count.to_le_bytes());
+            file.extend_from_slice(&value.to_le_bytes());
+        }
+        file.extend_from_slice(&0u32.to_le_bytes());
+        file.extend_from_slice(&color_map);
+        file.extend_from_slice(strip);
+        file
+    }
+
+    fn decode(file: Vec<u8>) -> (Frame, String) {
+        let mut decoder = Decoder::new(Cursor::new(file)).unwrap();
+        decode_page(&mut decoder).unwrap()
+    }
+
+    #[test]
+    fn palette_page_expands_color_map() {
+        let indices = [0u8, 1, 2, 3, 200, 255];
+        let (frame, color) = decode(palette_tiff(3, 2, 8, 1, &indices));
+        assert_eq!(color, "Palette(8)");
+        assert_eq!(frame.raw.get_pixel(1, 0).0, [1, 254, 2, 255]);
+        assert_eq!(frame.raw.get_pixel(1, 1).0, [200, 55, 144, 255]);
+        assert_eq!(frame.palette.len(), 256);
+        assert_eq!(frame.index_map.unwrap().get_pixel(2, 1).0[0], 255);
+    }
+
+    #[test]
+    fn palette_page_with_packed_and_compressed_strips() {
+        // 4 位索引，每行 5 个像素占 3 字节
+        let packed = [0x01, 0x23, 0x40, 0xff, 0xff, 0xf0];
+        let expected = [0u8, 1, 2, 3, 4, 15, 15, 15, 15, 15];
+        let check = |file: Vec<u8>| {
+            let (frame, _) = decode(file);
+            let map = frame.index_map.unwrap();
+            let indices: Vec<u8> = map.pixels().map(|p| p[0]).collect();
+            assert_eq!(indices, expected);
+            assert_eq!(frame.raw.get_pixel(0, 1).0, [15, 240, 30, 255]);
+        };
+        check(palette_tiff(5, 2, 4, 1, &packed));
+        // PackBits：字面量 3 字节，0xff 重复 2 次，字面量 1 字节
+        check(palette_tiff(5, 2, 4, 32773, &[2, 0x01, 0x23, 0x40, 0xff, 0xff, 0, 0xf0]));
+        let lzw = weezl::encode::Encoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8).encode(&packed).unwrap();
+        check(palette_tiff(5, 2, 4, 5, &lzw));
+    }
+
+    #[test]
+    fn unpack_bits_handles_runs_and_literals() {
+        assert_eq!(unpack_bits(&[0xfd, 7, 0, 9, 0x80, 0xff, 3]), [7, 7, 7, 7, 9, 3, 3]);
+    }
+}
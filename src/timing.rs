@@ -0,0 +1,469 @@
+use eframe::egui;
+use egui::{Color32, Sense, Ui};
+
+// ──────────────────────────────
+// 时间通道：将动画帧延迟序列视为隐藏数据，解码为比特、字节或摩尔斯电码
+
+/// 延迟数值的单位
+#[derive(Clone, Copy, PartialEq)]
+pub enum TimingUnit {
+    /// 文件中存储的原始分子（GIF 为 1/100 秒，APNG 为 delay_num，WebP 为毫秒）
+    Raw,
+    /// 换算为毫秒
+    Millis,
+}
+
+/// 延迟序列的解码方式
+#[derive(Clone, Copy, PartialEq)]
+pub enum TimingDecoder {
+    /// 大于阈值为 1，否则为 0
+    Threshold,
+    /// 出现最多的两个数值，较小者为 0，较大者为 1
+    TwoValue,
+    /// 每个延迟值取低 8 位作为一个字节
+    Bytes,
+    /// 短延迟为点，长延迟为划，更长的延迟为字母分隔
+    Morse,
+}
+
+impl TimingDecoder {
+    pub fn all() -> [TimingDecoder; 4] {
+        [
+            TimingDecoder::Threshold,
+            TimingDecoder::TwoValue,
+            TimingDecoder::Bytes,
+            TimingDecoder::Morse,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimingDecoder::Threshold => "阈值 → 比特",
+            TimingDecoder::TwoValue => "两值 → 比特",
+            TimingDecoder::Bytes => "低 8 位 → 字节",
+            TimingDecoder::Morse => "摩尔斯电码",
+        }
+    }
+}
+
+/// 帧延迟的分子与分母（秒 = num / den）
+pub type FrameDelay = (u32, u32);
+
+/// 按单位换算延迟序列
+pub fn delay_values(delays: &[FrameDelay], unit: TimingUnit) -> Vec<u32> {
+    delays
+        .iter()
+        .map(|&(num, den)| match unit {
+            TimingUnit::Raw => num,
+            TimingUnit::Millis if den == 0 => 0,
+            TimingUnit::Millis => ((num as f64 * 1000.0) / den as f64).round() as u32,
+        })
+        .collect()
+}
+
+/// 出现次数最多的两个不同数值（升序）
+pub fn two_most_common(values: &[u32]) -> Option<(u32, u32)> {
+    let mut counts: Vec<(u32, usize)> = Vec::new();
+    for &v in values {
+        match counts.iter_mut().find(|(value, _)| *value == v) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((v, 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    match counts.as_slice() {
+        [first, second, ..] => Some((first.0.min(second.0), first.0.max(second.0))),
+        _ => None,
+    }
+}
+
+/// 按 MSB 优先将比特打包为字节，末尾不足 8 位的部分丢弃
+pub fn pack_bits(bits: &[bool]) -> Vec<u8> {
+    bits.chunks_exact(8)
+        .map(|chunk| chunk.iter().fold(0u8, |byte, &bit| (byte << 1) | bit as u8))
+        .collect()
+}
+
+/// 按数值间最大的两个间隔将不同的延迟值分为三类，返回 (点的上限, 划的上限)
+pub fn morse_limits(values: &[u32]) -> (u32, u32) {
+    let mut distinct: Vec<u32> = values.to_vec();
+    distinct.sort_unstable();
+    distinct.dedup();
+    if distinct.len() < 2 {
+        let v = distinct.first().copied().unwrap_or(0);
+        return (v, v);
+    }
+    // 相邻数值的间隔，按大小降序取前两个作为分界
+    let mut gaps: Vec<(u32, usize)> = distinct.windows(2).enumerate().map(|(i, w)| (w[1] - w[0], i)).collect();
+    gaps.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    let mut cuts: Vec<usize> = gaps.iter().take(2).map(|&(_, i)| i).collect();
+    cuts.sort_unstable();
+    let dot_max = distinct[cuts[0]];
+    let dash_max = if cuts.len() > 1 { distinct[cuts[1]] } else { *distinct.last().unwrap() };
+    (dot_max, dash_max)
+}
+
+/// 摩尔斯码表
+fn morse_char(code: &str) -> Option<char> {
+    const TABLE: &[(&str, char)] = &[
+        (".-", 'A'), ("-...", 'B'), ("-.-.", 'C'), ("-..", 'D'), (".", 'E'), ("..-.", 'F'),
+        ("--.", 'G'), ("....", 'H'), ("..", 'I'), (".---", 'J'), ("-.-", 'K'), (".-..", 'L'),
+        ("--", 'M'), ("-.", 'N'), ("---", 'O'), (".--.", 'P'), ("--.-", 'Q'), (".-.", 'R'),
+        ("...", 'S'), ("-", 'T'), ("..-", 'U'), ("...-", 'V'), (".--", 'W'), ("-..-", 'X'),
+        ("-.--", 'Y'), ("--..", 'Z'), ("-----", '0'), (".----", '1'), ("..---", '2'),
+        ("...--", '3'), ("....-", '4'), (".....", '5'), ("-....", '6'), ("--...", '7'),
+        ("---..", '8'), ("----.", '9'), (".-.-.-", '.'), ("--..--", ','), ("..--..", '?'),
+        ("-.-.--", '!'), ("-..-.", '/'), ("-.--.", '('), ("-.--.-", ')'), ("---...", ':'),
+        ("-...-", '='), (".-.-.", '+'), ("-....-", '-'), ("..--.-", '_'), (".--.-.", '@'),
+    ];
+    TABLE.iter().find(|(c, _)| *c == code).map(|&(_, ch)| ch)
+}
+
+/// 将由点、划和空格分隔的摩尔斯串翻译为文本，无法识别的字母记为 '?'
+pub fn morse_to_text(morse: &str) -> String {
+    morse
+        .split(' ')
+        .filter(|code| !code.is_empty())
+        .map(|code| morse_char(code).unwrap_or('?'))
+        .collect()
+}
+
+/// 时间通道面板（嵌入在帧浏览器中）
+pub struct TimingPanel {
+    unit: TimingUnit,
+    decoder: TimingDecoder,
+    /// 阈值解码的阈值（大于阈值为 1）
+    threshold: u32,
+    /// 两值解码的数值（较小 → 0，较大 → 1）
+    low: u32,
+    high: u32,
+    /// 摩尔斯解码：不超过 dot_max 为点，不超过 dash_max 为划，更大为字母分隔
+    dot_max: u32,
+    dash_max: u32,
+    /// 比特取反
+    invert: bool,
+    /// 跳过开头的延迟个数
+    skip: usize,
+    /// 上次自动计算参数时的序列，序列变化时重新计算
+    last_values: Vec<u32>,
+}
+
+impl Default for TimingPanel {
+    fn default() -> Self {
+        Self {
+            unit: TimingUnit::Raw,
+            decoder: TimingDecoder::Threshold,
+            threshold: 0,
+            low: 0,
+            high: 0,
+            dot_max: 0,
+            dash_max: 0,
+            invert: false,
+            skip: 0,
+            last_values: Vec::new(),
+        }
+    }
+}
+
+impl TimingPanel {
+    // 根据序列自动设置解码参数
+    fn auto_params(&mut self, values: &[u32]) {
+        let min = values.iter().copied().min().unwrap_or(0);
+        let max = values.iter().copied().max().unwrap_or(0);
+        self.threshold = min + (max - min) / 2;
+        if let Some((low, high)) = two_most_common(values) {
+            self.low = low;
+            self.high = high;
+        }
+        let (dot_max, dash_max) = morse_limits(values);
+        self.dot_max = dot_max;
+        self.dash_max = dash_max;
+    }
+
+    /// 按当前解码方式得到比特序列（None 表示该延迟不属于任何一类）
+    fn decode_bits(&self, values: &[u32]) -> Vec<Option<bool>> {
+        values
+            .iter()
+            .map(|&v| {
+                let bit = match self.decoder {
+                    TimingDecoder::Threshold => Some(v > self.threshold),
+                    TimingDecoder::TwoValue if v == self.low => Some(false),
+                    TimingDecoder::TwoValue if v == self.high => Some(true),
+                    _ => None,
+                };
+                bit.map(|b| b != self.invert)
+            })
+            .collect()
+    }
+
+    /// 解码结果：(中间表示, 字节数据)
+    pub fn decode(&self, values: &[u32]) -> (String, Vec<u8>) {
+        match self.decoder {
+            TimingDecoder::Threshold | TimingDecoder::TwoValue => {
+                let bits = self.decode_bits(values);
+                let repr: String = bits
+                    .iter()
+                    .map(|b| match b {
+                        Some(true) => '1',
+                        Some(false) => '0',
+                        None => '?',
+                    })
+                    .collect();
+                let known: Vec<bool> = bits.into_iter().flatten().collect();
+                (repr, pack_bits(&known))
+            }
+            TimingDecoder::Bytes => {
+                let bytes: Vec<u8> = values.iter().map(|&v| v as u8).collect();
+                let repr = bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ");
+                (repr, bytes)
+            }
+            TimingDecoder::Morse => {
+                let mut morse = String::new();
+                for &v in values {
+                    if v <= self.dot_max {
+                        morse.push('.');
+                    } else if v <= self.dash_max {
+                        morse.push('-');
+                    } else {
+                        morse.push(' ');
+                    }
+                }
+                let text = morse_to_text(&morse);
+                (morse, text.into_bytes())
+            }
+        }
+    }
+
+    /// 绘制面板，delays 为所有动画帧的延迟
+    pub fn ui(&mut self, ui: &mut Ui, delays: &[FrameDelay]) {
+        if delays.is_empty() {
+            ui.label("当前文件没有帧延迟信息");
+            return;
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("单位:");
+            ui.radio_value(&mut self.unit, TimingUnit::Raw, "原始值");
+            ui.radio_value(&mut self.unit, TimingUnit::Millis, "毫秒");
+            ui.separator();
+            ui.label("跳过开头:");
+            ui.add(egui::DragValue::new(&mut self.skip).range(0..=delays.len()));
+            ui.label("个");
+        });
+
+        let all_values = delay_values(delays, self.unit);
+        let values = &all_values[self.skip.min(all_values.len())..];
+        if values != self.last_values.as_slice() {
+            self.auto_params(values);
+            self.last_values = values.to_vec();
+        }
+
+        self.plot(ui, values);
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label(format!("延迟序列 ({} 个):", values.len()));
+            if ui.button("复制").clicked() {
+                let text = values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",");
+                ui.ctx().copy_text(text);
+            }
+        });
+        let mut sequence = values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ");
+        ui.add(
+            egui::TextEdit::multiline(&mut sequence)
+                .desired_rows(3)
+                .desired_width(f32::INFINITY),
+        );
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("解码:");
+            egui::ComboBox::from_id_salt("timing_decoder")
+                .selected_text(self.decoder.name())
+                .show_ui(ui, |ui| {
+                    for decoder in TimingDecoder::all() {
+                        ui.selectable_value(&mut self.decoder, decoder, decoder.name());
+                    }
+                });
+            match self.decoder {
+                TimingDecoder::Threshold => {
+                    ui.label("阈值 >");
+                    ui.add(egui::DragValue::new(&mut self.threshold));
+                    ui.checkbox(&mut self.invert, "取反");
+                }
+                TimingDecoder::TwoValue => {
+                    ui.label("0 =");
+                    ui.add(egui::DragValue::new(&mut self.low));
+                    ui.label("1 =");
+                    ui.add(egui::DragValue::new(&mut self.high));
+                    ui.checkbox(&mut self.invert, "取反");
+                }
+                TimingDecoder::Bytes => {}
+                TimingDecoder::Morse => {
+                    ui.label("点 ≤");
+                    ui.add(egui::DragValue::new(&mut self.dot_max));
+                    ui.label("划 ≤");
+                    ui.add(egui::DragValue::new(&mut self.dash_max));
+                    ui.label("更大为分隔");
+                }
+            }
+            if ui.button("自动").clicked() {
+                self.auto_params(values);
+            }
+        });
+
+        let (repr, bytes) = self.decode(values);
+        ui.label("中间结果:");
+        let mut repr_text = repr;
+        ui.add(
+            egui::TextEdit::multiline(&mut repr_text)
+                .font(egui::TextStyle::Monospace)
+                .desired_rows(2)
+                .desired_width(f32::INFINITY),
+        );
+        ui.horizontal_wrapped(|ui| {
+            ui.label(format!("解码数据: {} 字节", bytes.len()));
+            if ui.button("复制文本").clicked() {
+                ui.ctx().copy_text(String::from_utf8_lossy(&bytes).to_string());
+            }
+            if ui.button("保存二进制").clicked() {
+                if let Some(path) = rfd::FileDialog::new().set_file_name("timing.bin").save_file() {
+                    if let Err(e) = std::fs::write(path, &bytes) {
+                        eprintln!("保存文件失败: {}", e);
+                    }
+                }
+            }
+        });
+        let mut text: String = bytes
+            .iter()
+            .map(|&b| {
+                let c = b as char;
+                if c.is_ascii_graphic() || c == ' ' { c } else { '.' }
+            })
+            .collect();
+        ui.add(
+            egui::TextEdit::multiline(&mut text)
+                .font(egui::TextStyle::Monospace)
+                .desired_rows(2)
+                .desired_width(f32::INFINITY),
+        );
+    }
+
+    // 延迟序列柱状图，阈值解码时绘制阈值线
+    fn plot(&self, ui: &mut Ui, values: &[u32]) {
+        let height = 120.0;
+        let (rect, response) = ui.allocate_exact_size(egui::vec2(ui.available_width(), height), Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+        if values.is_empty() {
+            return;
+        }
+
+        let max = values.iter().copied().max().unwrap_or(0).max(1) as f32;
+        let bar_width = rect.width() / values.len() as f32;
+        let y_of = |v: f32| rect.bottom() - v / max * (height - 4.0);
+        let bits = self.decode_bits(values);
+        for (i, &v) in values.iter().enumerate() {
+            let x = rect.left() + i as f32 * bar_width;
+            let color = match (self.decoder, bits[i]) {
+                (TimingDecoder::Threshold | TimingDecoder::TwoValue, Some(true)) => Color32::from_rgb(220, 120, 60),
+                (TimingDecoder::Threshold | TimingDecoder::TwoValue, None) => Color32::GRAY,
+                _ => Color32::from_rgb(70, 130, 200),
+            };
+            let bar = egui::Rect::from_min_max(
+                egui::pos2(x, y_of(v as f32)),
+                egui::pos2(x + (bar_width - 1.0).max(1.0), rect.bottom()),
+            );
+            painter.rect_filled(bar, 0.0, color);
+        }
+        if self.decoder == TimingDecoder::Threshold {
+            let y = y_of(self.threshold as f32);
+            painter.hline(rect.x_range(), y, egui::Stroke::new(1.0, Color32::RED));
+        }
+
+        if let Some(pos) = response.hover_pos() {
+            let i = ((pos.x - rect.left()) / bar_width) as usize;
+            if let Some(v) = values.get(i) {
+                response.on_hover_text(format!("#{}: {}", i + self.skip, v));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn panel(decoder: TimingDecoder, values: &[u32]) -> TimingPanel {
+        let mut panel = TimingPanel {
+            decoder,
+            ..TimingPanel::default()
+        };
+        panel.auto_params(values);
+        panel
+    }
+
+    #[test]
+    fn morse_limits_with_few_distinct_values() {
+        assert_eq!(morse_limits(&[]), (0, 0));
+        assert_eq!(morse_limits(&[5, 5]), (5, 5));
+        // 只有两种数值时没有字母分隔
+        assert_eq!(morse_limits(&[10, 30, 10]), (10, 30));
+        assert_eq!(morse_limits(&[10, 30, 70, 10, 30]), (10, 30));
+    }
+
+    #[test]
+    fn two_most_common_breaks_ties_by_value() {
+        assert_eq!(two_most_common(&[3, 1, 3, 1, 2]), Some((1, 3)));
+        assert_eq!(two_most_common(&[5, 2, 9]), Some((2, 5)));
+        assert_eq!(two_most_common(&[7, 9, 9, 9]), Some((7, 9)));
+        assert_eq!(two_most_common(&[4, 4]), None);
+        assert_eq!(two_most_common(&[]), None);
+    }
+
+    #[test]
+    fn delay_values_handles_zero_denominator() {
+        let delays = [(10, 100), (3, 0), (1, 3)];
+        assert_eq!(delay_values(&delays, TimingUnit::Raw), vec![10, 3, 1]);
+        assert_eq!(delay_values(&delays, TimingUnit::Millis), vec![100, 0, 333]);
+    }
+
+    #[test]
+    fn pack_bits_drops_trailing_partial_byte() {
+        let bits: Vec<bool> = "1010000111".chars().map(|c| c == '1').collect();
+        assert_eq!(pack_bits(&bits), vec![0xa1]);
+        assert_eq!(pack_bits(&bits[..7]), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn morse_to_text_marks_unknown_letters() {
+        assert_eq!(morse_to_text("... --- ..."), "SOS");
+        assert_eq!(morse_to_text("  .- ....... "), "A?");
+        assert_eq!(morse_to_text(""), "");
+    }
+
+    #[test]
+    fn decode_threshold_and_two_value() {
+        let values = [2, 8, 8, 2, 2, 2, 2, 8];
+        let mut threshold = panel(TimingDecoder::Threshold, &values);
+        assert_eq!(threshold.threshold, 5);
+        assert_eq!(threshold.decode(&values), ("01100001".to_string(), vec![0x61]));
+        threshold.invert = true;
+        assert_eq!(threshold.decode(&values).1, vec![0x9e]);
+
+        // 不属于两类数值的延迟显示为 '?'，不参与打包
+        let values = [2, 8, 5, 8, 2, 2, 2, 2, 8, 8];
+        let two_value = panel(TimingDecoder::TwoValue, &values);
+        assert_eq!((two_value.low, two_value.high), (2, 8));
+        assert_eq!(two_value.decode(&values), ("01?1000011".to_string(), vec![0x61]));
+    }
+
+    #[test]
+    fn decode_bytes_and_morse() {
+        let bytes = panel(TimingDecoder::Bytes, &[]);
+        assert_eq!(bytes.decode(&[0x141, 7]), ("41 07".to_string(), vec![0x41, 0x07]));
+
+        // 点 = 1，划 = 3，字母分隔 = 7
+        let values = [1, 3, 7, 3, 1, 1, 1];
+        let morse = panel(TimingDecoder::Morse, &values);
+        assert_eq!(morse.decode(&values), (".- -...".to_string(), b"AB".to_vec()));
+    }
+}
@@ -1,6 +1,9 @@
 use image::{DynamicImage, RgbaImage, Rgba};
 use rand::Rng;
 
+/// 变换总数（编号 0 为原图）
+pub const TRANS_COUNT: i32 = 42;
+
 pub struct Transform {
     original_image: RgbaImage,   // 原始图像
     transformed_image: RgbaImage,// 变换后的图像
@@ -15,7 +18,7 @@ impl Transform {
             original_image: rgba_img.clone(),
             transformed_image: rgba_img,
             trans_num: 0,
-            max_trans: 41, // 最大变换编号
+            max_trans: TRANS_COUNT - 1, // 最大变换编号
         }
     }
 
@@ -24,15 +27,25 @@ impl Transform {
         &self.transformed_image
     }
 
+    // 获取未经变换的原始图像
+    pub fn get_original(&self) -> &RgbaImage {
+        &self.original_image
+    }
+
     // 获取当前变换的描述文本（与原版 StegSolve 对应）
     pub fn get_text(&self) -> String {
-        match self.trans_num {
+        Self::text_for(self.trans_num)
+    }
+
+    // 指定编号变换的描述文本
+    pub fn text_for(trans_num: i32) -> String {
+        match trans_num {
             0 => "正常图像".to_string(),
             1 => "颜色反转 (Xor)".to_string(),
-            2..=9  => format!("Alpha plane {}", 9 - self.trans_num),
-            10..=17 => format!("Red plane {}", 17 - self.trans_num),
-            18..=25 => format!("Green plane {}", 25 - self.trans_num),
-            26..=33 => format!("Blue plane {}", 33 - self.trans_num),
+            2..=9  => format!("Alpha plane {}", 9 - trans_num),
+            10..=17 => format!("Red plane {}", 17 - trans_num),
+            18..=25 => format!("Green plane {}", 25 - trans_num),
+            26..=33 => format!("Blue plane {}", 33 - trans_num),
             34 => "Full alpha".to_string(),
             35 => "Full red".to_string(),
             36 => "Full green".to_string(),
@@ -45,6 +58,12 @@ impl Transform {
         }
     }
 
+    // 直接切换到指定编号的变换（超出范围时回绕）
+    pub fn set_trans_num(&mut self, trans_num: i32) {
+        self.trans_num = trans_num.rem_euclid(self.max_trans + 1);
+        self.calc_trans();
+    }
+
     // 切换到上一个变换
     pub fn back(&mut self) {
         self.trans_num -= 1;
@@ -0,0 +1,241 @@
+use crate::apng_decoder::{clear_region, composite};
+use crate::framebrowser::{Frame, FrameBlend, FrameDispose};
+use image::{ImageFormat, RgbaImage};
+use std::path::Path;
+
+/// RIFF 容器中的一个块
+pub struct RiffChunk {
+    /// 块类型（FourCC）
+    pub name: String,
+    /// 块头在文件中的偏移
+    pub offset: usize,
+    /// 块数据长度（不含填充字节）
+    pub size: usize,
+}
+
+impl RiffChunk {
+    /// 块数据起始偏移
+    pub fn data_start(&self) -> usize {
+        self.offset + 8
+    }
+
+    /// 块数据结束偏移（不含）
+    pub fn data_end(&self) -> usize {
+        self.offset + 8 + self.size
+    }
+}
+
+/// 遍历 [start, end) 范围内的 RIFF 块，块数据长度为奇数时跳过填充字节
+/// 块长度超出范围时截断为剩余长度，并停止遍历
+pub fn riff_chunks(data: &[u8], start: usize, end: usize) -> Vec<RiffChunk> {
+    let end = end.min(data.len());
+    let mut chunks = Vec::new();
+    let mut pos = start;
+    while pos + 8 <= end {
+        let name = String::from_utf8_lossy(&data[pos..pos + 4]).to_string();
+        let size = u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]]) as usize;
+        let available = end - pos - 8;
+        chunks.push(RiffChunk {
+            name,
+            offset: pos,
+            size: size.min(available),
+        });
+        if size > available {
+            break;
+        }
+        pos += 8 + size + (size & 1);
+    }
+    chunks
+}
+
+/// 读取 24 位小端整数
+pub fn get_u24_le(data: &[u8], offset: usize) -> u32 {
+    if offset + 2 >= data.len() {
+        0
+    } else {
+        u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], 0])
+    }
+}
+
+/// 检查是否为 RIFF/WEBP 文件
+pub fn is_webp(data: &[u8]) -> bool {
+    data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP"
+}
+
+/// WebP解码器 - 解析 ANIM/ANMF 块，逐帧解码子帧并按偏移、混合和处置标志合成画布
+pub struct WebpDecoder {
+    frames: Vec<Frame>,
+}
+
+impl WebpDecoder {
+    /// 从文件路径解码WebP的所有帧，非动画文件解码为单帧
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let data = std::fs::read(path)?;
+        if !is_webp(&data) {
+            return Err("不是有效的 WebP 文件".into());
+        }
+        let riff_end = (u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize + 8).min(data.len());
+        let chunks = riff_chunks(&data, 12, riff_end);
+
+        let vp8x = chunks.iter().find(|c| c.name == "VP8X" && c.size >= 10);
+        let animated = vp8x.is_some_and(|c| data[c.data_start()] & 0x02 != 0);
+        if !animated {
+            let img = image::load_from_memory_with_format(&data, ImageFormat::WebP)?.to_rgba8();
+            return Ok(Self {
+                frames: vec![Frame::from_image(img)],
+            });
+        }
+
+        let vp8x = vp8x.unwrap();
+        let width = get_u24_le(&data, vp8x.data_start() + 4) + 1;
+        let height = get_u24_le(&data, vp8x.data_start() + 7) + 1;
+        let mut canvas = RgbaImage::new(width, height);
+        let mut frames = Vec::new();
+
+        for chunk in chunks.iter().filter(|c| c.name == "ANMF" && c.size >= 16) {
+            let start = chunk.data_start();
+            // 偏移以 2 像素为单位存储
+            let x = get_u24_le(&data, start) * 2;
+            let y = get_u24_le(&data, start + 3) * 2;
+            let fw = get_u24_le(&data, start + 6) + 1;
+            let fh = get_u24_le(&data, start + 9) + 1;
+            let duration = get_u24_le(&data, start + 12);
+            let flags = data[start + 15];
+            let blend = if flags & 0x02 != 0 { FrameBlend::Source } else { FrameBlend::Over };
+            let dispose = if flags & 0x01 != 0 { FrameDispose::Background } else { FrameDispose::None };
+
+            let (raw, note) = match decode_anmf_frame(&data, start + 16, chunk.data_end(), fw, fh) {
+                Ok(img) => (img, None),
+                Err(e) => (RgbaImage::new(fw, fh), Some(format!("子帧解码失败: {}", e))),
+            };
+
+            composite(&mut canvas, &raw, x, y, blend);
+            let composed = canvas.clone();
+            if dispose == FrameDispose::Background {
+                clear_region(&mut canvas, x, y, fw, fh);
+            }
+
+            frames.push(Frame {
+                composed,
+                raw,
+                x,
+                y,
+                // ANMF 的持续时间单位为毫秒
+                delay_num: duration,
+                delay_den: 1000,
+                dispose,
+                blend,
+                index_map: None,
+                palette: Vec::new(),
+                low_bytes: None,
+                note,
+            });
+        }
+
+        if frames.is_empty() {
+            return Err("动画 WebP 中没有 ANMF 帧".into());
+        }
+        Ok(Self { frames })
+    }
+
+    /// 获取所有帧
+    pub fn into_frames(self) -> Vec<Frame> {
+        self.frames
+    }
+}
+
+/// 将 ANMF 帧中的 ALPH / VP8 / VP8L 子块重新封装为独立的 WebP 文件并解码
+fn decode_anmf_frame(
+    data: &[u8],
+    start: usize,
+    end: usize,
+    width: u32,
+    height: u32,
+) -> Result<RgbaImage, Box<dyn std::error::Error>> {
+    let sub_chunks = riff_chunks(data, start, end);
+    let bitstream = sub_chunks
+        .iter()
+        .find(|c| c.name == "VP8 " || c.name == "VP8L")
+        .ok_or("缺少 VP8/VP8L 数据块")?;
+    let alpha = sub_chunks.iter().find(|c| c.name == "ALPH");
+
+    let mut body = Vec::new();
+    // 有损数据带独立 alpha 时需要 VP8X 头声明 alpha 和画布尺寸
+    if let (Some(alpha), "VP8 ") = (alpha, bitstream.name.as_str()) {
+        body.extend_from_slice(b"VP8X");
+        body.extend_from_slice(&10u32.to_le_bytes());
+        body.extend_from_slice(&[0x10, 0, 0, 0]);
+        body.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+        body.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+        push_chunk(&mut body, data, alpha);
+    }
+    push_chunk(&mut body, data, bitstream);
+
+    let mut file = Vec::with_capacity(body.len() + 12);
+    file.extend_from_slice(b"RIFF");
+    file.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+    file.extend_from_slice(b"WEBP");
+    file.extend_from_slice(&body);
+    Ok(image::load_from_memory_with_format(&file, ImageFormat::WebP)?.to_rgba8())
+}
+
+// 复制一个完整的块（含块头和填充字节）
+fn push_chunk(out: &mut Vec<u8>, data: &[u8], chunk: &RiffChunk) {
+    out.extend_from_slice(&data[chunk.offset..chunk.data_end()]);
+    if chunk.size & 1 == 1 {
+        out.push(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(name: &[u8; 4], size: u32, data: &[u8]) -> Vec<u8> {
+        let mut out = name.to_vec();
+        out.extend_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    fn summary(chunks: &[RiffChunk]) -> Vec<(&str, usize, usize)> {
+        chunks.iter().map(|c| (c.name.as_str(), c.offset, c.size)).collect()
+    }
+
+    #[test]
+    fn riff_chunks_skip_padding_of_odd_sizes() {
+        let mut data = b"RIFF\0\0\0\0WEBP".to_vec();
+        data.extend(chunk(b"ABCD", 3, &[1, 2, 3, 0]));
+        data.extend(chunk(b"EFGH", 2, &[4, 5]));
+        // 末尾不足一个块头的字节被忽略
+        data.extend_from_slice(&[9; 5]);
+
+        let chunks = riff_chunks(&data, 12, data.len());
+        assert_eq!(summary(&chunks), vec![("ABCD", 12, 3), ("EFGH", 24, 2)]);
+        assert_eq!((chunks[0].data_start(), chunks[0].data_end()), (20, 23));
+        assert_eq!(&data[chunks[1].data_start()..chunks[1].data_end()], &[4, 5]);
+    }
+
+    #[test]
+    fn riff_chunks_truncate_oversized_chunk() {
+        let mut data = chunk(b"VP8X", 1, &[7, 0]);
+        data.extend(chunk(b"ANMF", 100, &[1, 2, 3, 4]));
+        data.extend(chunk(b"EXIF", 0, &[]));
+        // ANMF 声明 100 字节，只剩 12 字节：截断并停止遍历，不再识别其后的 EXIF
+        let chunks = riff_chunks(&data, 0, data.len());
+        assert_eq!(summary(&chunks), vec![("VP8X", 0, 1), ("ANMF", 10, 12)]);
+
+        // end 超出数据长度时按数据长度处理
+        assert_eq!(riff_chunks(&data, 0, usize::MAX).len(), 2);
+        assert!(riff_chunks(&data[..7], 0, 7).is_empty());
+    }
+
+    #[test]
+    fn u24_reads_little_endian_within_bounds() {
+        let data = [0x01, 0x02, 0x03, 0x04];
+        assert_eq!(get_u24_le(&data, 0), 0x030201);
+        assert_eq!(get_u24_le(&data, 1), 0x040302);
+        assert_eq!(get_u24_le(&data, 2), 0);
+        assert_eq!(get_u24_le(&[], 0), 0);
+    }
+}